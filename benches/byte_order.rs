@@ -0,0 +1,44 @@
+//! Compares reads through the runtime `Endian` setting against readers with
+//! the byte order fixed in the type. Run with `cargo bench`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use binary_rw::{
+    byteorder::{BigEndian, ByteOrder},
+    memorystream::Memorystream,
+    BinaryReader, Endian,
+};
+
+const VALUES: usize = 1 << 16;
+const ROUNDS: u32 = 50;
+
+fn sum_u32s<B: ByteOrder>(reader: &mut BinaryReader<'_, B>) -> u64 {
+    reader.seek_to(0).expect("Failed to seek");
+
+    let mut sum = 0u64;
+    for _ in 0..VALUES {
+        sum += reader.read_u32().expect("Failed to read u32") as u64;
+    }
+
+    sum
+}
+
+fn time<F: FnMut() -> u64>(mut f: F) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        black_box(f());
+    }
+
+    start.elapsed() / ROUNDS
+}
+
+fn main() {
+    let mut stream = Memorystream::from_vec(vec![0xA5; VALUES * 4]);
+
+    let runtime = time(|| sum_u32s(&mut BinaryReader::with_endian(&mut stream, Endian::Big)));
+    let fixed = time(|| sum_u32s(&mut BinaryReader::<BigEndian>::with_byte_order(&mut stream)));
+
+    println!("runtime Endian::Big:  {:?} per {} reads", runtime, VALUES);
+    println!("BigEndian type param: {:?} per {} reads", fixed, VALUES);
+}
@@ -0,0 +1,68 @@
+//! Counts heap allocations made by readers in a tight parse loop, showing
+//! that decoding reads reuse the reader's scratch buffer instead of
+//! allocating a temporary per call. Run with `cargo bench`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use binary_rw::{memorystream::Memorystream, BinaryReader};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    // Growing a vector in place isn't a new allocation.
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const RECORDS: usize = 10_000;
+const VALUES_PER_RECORD: usize = 16;
+
+fn allocations<F: FnMut()>(mut f: F) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn main() {
+    let mut stream = Memorystream::from_vec(vec![0x5A; RECORDS * (VALUES_PER_RECORD * 4 + 16)]);
+    let mut reader = BinaryReader::new(&mut stream);
+
+    let primitives = allocations(|| {
+        reader.seek_to(0).expect("Failed to seek");
+        for _ in 0..RECORDS * VALUES_PER_RECORD {
+            reader.read_u32().expect("Failed to read u32");
+        }
+    });
+
+    let slices = allocations(|| {
+        reader.seek_to(0).expect("Failed to seek");
+        for _ in 0..RECORDS {
+            reader.read_u32_slice(VALUES_PER_RECORD).expect("Failed to read slice");
+            reader.read_bitmask_indices(128).expect("Failed to read bitmask");
+        }
+    });
+
+    println!("{} u32 reads: {} allocations", RECORDS * VALUES_PER_RECORD, primitives);
+    println!(
+        "{} slice + bitmask reads: {} allocations ({} are the returned vectors)",
+        RECORDS,
+        slices,
+        RECORDS * 2
+    );
+}
@@ -0,0 +1,153 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, parse_quote, Data, DeriveInput, Field, Fields, GenericParam, Generics, Index,
+};
+
+#[proc_macro_derive(BinaryReadable, attributes(binary))]
+pub fn derive_binary_readable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand_readable(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+#[proc_macro_derive(BinaryWritable, attributes(binary))]
+pub fn derive_binary_writable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand_writable(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn struct_fields(input: &DeriveInput) -> syn::Result<&Fields> {
+    match input.data {
+        Data::Struct(ref data) => Ok(&data.fields),
+        _ => Err(syn::Error::new_spanned(
+            &input.ident,
+            "binary derives only support structs",
+        )),
+    }
+}
+
+fn is_skipped(field: &Field) -> syn::Result<bool> {
+    let mut skip = false;
+
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("binary")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported binary attribute"))
+            }
+        })?;
+    }
+
+    Ok(skip)
+}
+
+fn add_bounds(mut generics: Generics, bound: TokenStream2) -> Generics {
+    for param in generics.params.iter_mut() {
+        if let GenericParam::Type(ref mut ty) = *param {
+            ty.bounds.push(parse_quote!(#bound));
+        }
+    }
+
+    generics
+}
+
+fn expand_readable(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = struct_fields(&input)?;
+
+    let body = match fields {
+        Fields::Named(ref named) => {
+            let mut inits = Vec::new();
+            for field in named.named.iter() {
+                let ident = &field.ident;
+                if is_skipped(field)? {
+                    inits.push(quote! { #ident: ::core::default::Default::default() });
+                } else {
+                    inits.push(quote! { #ident: ::binary_rw::BinaryReadable::read_from(reader)? });
+                }
+            }
+            quote! { #name { #(#inits,)* } }
+        }
+        Fields::Unnamed(ref unnamed) => {
+            let mut inits = Vec::new();
+            for field in unnamed.unnamed.iter() {
+                if is_skipped(field)? {
+                    inits.push(quote! { ::core::default::Default::default() });
+                } else {
+                    inits.push(quote! { ::binary_rw::BinaryReadable::read_from(reader)? });
+                }
+            }
+            quote! { #name ( #(#inits,)* ) }
+        }
+        Fields::Unit => quote! { { let _ = reader; #name } },
+    };
+
+    let generics = add_bounds(input.generics.clone(), quote!(::binary_rw::BinaryReadable));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::binary_rw::BinaryReadable for #name #ty_generics #where_clause {
+            fn read_from(
+                reader: &mut ::binary_rw::BinaryReader,
+            ) -> ::std::result::Result<Self, ::binary_rw::BinaryError> {
+                ::std::result::Result::Ok(#body)
+            }
+        }
+    })
+}
+
+fn expand_writable(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let fields = struct_fields(&input)?;
+
+    let mut writes = Vec::new();
+    match fields {
+        Fields::Named(ref named) => {
+            for field in named.named.iter() {
+                if !is_skipped(field)? {
+                    let ident = &field.ident;
+                    writes.push(quote! { &self.#ident });
+                }
+            }
+        }
+        Fields::Unnamed(ref unnamed) => {
+            for (i, field) in unnamed.unnamed.iter().enumerate() {
+                if !is_skipped(field)? {
+                    let index = Index::from(i);
+                    writes.push(quote! { &self.#index });
+                }
+            }
+        }
+        Fields::Unit => {}
+    }
+
+    let generics = add_bounds(input.generics.clone(), quote!(::binary_rw::BinaryWritable));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::binary_rw::BinaryWritable for #name #ty_generics #where_clause {
+            fn write_to(
+                &self,
+                writer: &mut ::binary_rw::BinaryWriter,
+            ) -> ::std::result::Result<usize, ::binary_rw::BinaryError> {
+                let _ = &writer;
+                let written = 0;
+                #(let written = written + ::binary_rw::BinaryWritable::write_to(#writes, writer)?;)*
+                ::std::result::Result::Ok(written)
+            }
+        }
+    })
+}
@@ -0,0 +1,367 @@
+use crate::{offset_to_usize, BinaryError, Endian, LengthPrefix, StreamError};
+use bincode::{deserialize, serialize};
+use std::convert::TryFrom;
+use std::future::Future;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub trait AsyncStream: Send {
+    fn write(&mut self, bytes: &[u8]) -> impl Future<Output = Result<usize, StreamError>> + Send;
+    fn read(
+        &mut self,
+        buffer: &mut [u8],
+    ) -> impl Future<Output = Result<usize, StreamError>> + Send;
+    fn seek(&mut self, to: usize) -> impl Future<Output = Result<usize, StreamError>> + Send;
+    fn tell(&mut self) -> impl Future<Output = Result<usize, StreamError>> + Send;
+
+    fn flush(&mut self) -> impl Future<Output = Result<(), StreamError>> + Send {
+        async { Ok(()) }
+    }
+}
+
+impl AsyncStream for TcpStream {
+    async fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        match self.write_all(bytes).await {
+            Ok(_) => Ok(bytes.len()),
+            Err(_) => Err(StreamError::WriteError),
+        }
+    }
+
+    async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        match AsyncReadExt::read(self, buffer).await {
+            Ok(res) => Ok(res),
+            Err(_) => Err(StreamError::ReadError),
+        }
+    }
+
+    async fn seek(&mut self, _to: usize) -> Result<usize, StreamError> {
+        Err(StreamError::SeekError)
+    }
+
+    async fn tell(&mut self) -> Result<usize, StreamError> {
+        Err(StreamError::TellError)
+    }
+
+    async fn flush(&mut self) -> Result<(), StreamError> {
+        match AsyncWriteExt::flush(self).await {
+            Ok(_) => Ok(()),
+            Err(_) => Err(StreamError::WriteError),
+        }
+    }
+}
+
+/// Reads the same layout as `BinaryReader`: `usize` and `isize` are always
+/// eight bytes, and strings carry a length in the configured `LengthPrefix`.
+pub struct AsyncBinaryReader<'a, S: AsyncStream> {
+    stream: &'a mut S,
+    endian: Endian,
+    length_prefix: LengthPrefix,
+    max_alloc: usize,
+}
+
+macro_rules! async_read_num {
+    ($($name:ident => $ty:ty;)*) => {
+        $(
+            pub async fn $name(&mut self) -> Result<$ty, BinaryError> {
+                let mut buffer = [0u8; std::mem::size_of::<$ty>()];
+
+                self.read_exact_into(&mut buffer).await?;
+                self.endian.apply(&mut buffer);
+
+                let value = deserialize(&buffer)?;
+                Ok(value)
+            }
+        )*
+    };
+}
+
+impl<'a, S: AsyncStream> AsyncBinaryReader<'a, S> {
+    pub fn new(stream: &'a mut S) -> AsyncBinaryReader<'a, S> {
+        AsyncBinaryReader::with_endian(stream, Endian::default())
+    }
+
+    pub fn with_endian(stream: &'a mut S, endian: Endian) -> AsyncBinaryReader<'a, S> {
+        AsyncBinaryReader {
+            stream,
+            endian,
+            length_prefix: LengthPrefix::default(),
+            max_alloc: 64 * 1024 * 1024,
+        }
+    }
+
+    pub fn set_endian(&mut self, endian: Endian) {
+        self.endian = endian;
+    }
+
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    pub fn set_length_prefix(&mut self, length_prefix: LengthPrefix) {
+        self.length_prefix = length_prefix;
+    }
+
+    pub fn length_prefix(&self) -> LengthPrefix {
+        self.length_prefix
+    }
+
+    pub fn set_max_alloc(&mut self, max_alloc: usize) {
+        self.max_alloc = max_alloc;
+    }
+
+    pub fn max_alloc(&self) -> usize {
+        self.max_alloc
+    }
+
+    pub async fn seek_to(&mut self, position: usize) -> Result<usize, BinaryError> {
+        Ok(self.stream.seek(position).await?)
+    }
+
+    pub async fn get_cur_pos(&mut self) -> Result<usize, BinaryError> {
+        Ok(self.stream.tell().await?)
+    }
+
+    pub async fn read_exact_into(&mut self, buf: &mut [u8]) -> Result<(), BinaryError> {
+        let expected = buf.len();
+        let mut got = 0;
+
+        while got < expected {
+            let read = self.stream.read(&mut buf[got..]).await?;
+
+            if read == 0 {
+                return Err(BinaryError::UnexpectedEof { expected, got });
+            }
+
+            got += read;
+        }
+
+        Ok(())
+    }
+
+    pub async fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, BinaryError> {
+        if length > self.max_alloc {
+            return Err(BinaryError::AllocationTooLarge {
+                requested: length,
+                limit: self.max_alloc,
+            });
+        }
+
+        let mut buffer = vec![0; length];
+        self.read_exact_into(&mut buffer).await?;
+
+        Ok(buffer)
+    }
+
+    pub async fn read_string(&mut self) -> Result<String, BinaryError> {
+        let str_len = self.read_length().await?;
+        let chars = self.read_bytes(str_len).await?;
+
+        let string = String::from_utf8(chars)?;
+        Ok(string)
+    }
+
+    pub async fn read_usize(&mut self) -> Result<usize, BinaryError> {
+        let value = self.read_u64().await?;
+        offset_to_usize(value)
+    }
+
+    pub async fn read_isize(&mut self) -> Result<isize, BinaryError> {
+        let value = self.read_i64().await?;
+
+        match isize::try_from(value) {
+            Ok(value) => Ok(value),
+            Err(_) => Err(BinaryError::InvalidData(format!("{} does not fit in an isize", value))),
+        }
+    }
+
+    pub async fn read_varint_u64(&mut self) -> Result<u64, BinaryError> {
+        let mut value = 0u64;
+
+        for i in 0..10 {
+            let byte = self.read_u8().await?;
+            // The tenth byte only has room for the top bit of a u64.
+            if i == 9 && byte > 1 {
+                return Err(BinaryError::VarintTooLong);
+            }
+
+            value |= u64::from(byte & 0x7F) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(BinaryError::VarintTooLong)
+    }
+
+    async fn read_length(&mut self) -> Result<usize, BinaryError> {
+        let len = match self.length_prefix {
+            LengthPrefix::U8 => self.read_u8().await? as u64,
+            LengthPrefix::U16 => self.read_u16().await? as u64,
+            LengthPrefix::U32 => self.read_u32().await? as u64,
+            LengthPrefix::U64 => self.read_u64().await?,
+            LengthPrefix::Varint => self.read_varint_u64().await?,
+        };
+
+        offset_to_usize(len)
+    }
+
+    pub async fn read_bool(&mut self) -> Result<bool, BinaryError> {
+        match self.read_u8().await? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(BinaryError::InvalidBool(value)),
+        }
+    }
+
+    async_read_num! {
+        read_f32 => f32;
+        read_f64 => f64;
+        read_u128 => u128;
+        read_i128 => i128;
+        read_u64 => u64;
+        read_i64 => i64;
+        read_u32 => u32;
+        read_i32 => i32;
+        read_u16 => u16;
+        read_i16 => i16;
+        read_u8 => u8;
+        read_i8 => i8;
+    }
+}
+
+/// Writes the same layout as `BinaryWriter`; see `AsyncBinaryReader`.
+pub struct AsyncBinaryWriter<'a, S: AsyncStream> {
+    stream: &'a mut S,
+    endian: Endian,
+    length_prefix: LengthPrefix,
+}
+
+macro_rules! async_write_num {
+    ($($name:ident => $ty:ty;)*) => {
+        $(
+            pub async fn $name(&mut self, value: $ty) -> Result<usize, BinaryError> {
+                let mut data = serialize(&value)?;
+                self.endian.apply(&mut data);
+
+                self.write_bytes(&data).await
+            }
+        )*
+    };
+}
+
+impl<'a, S: AsyncStream> AsyncBinaryWriter<'a, S> {
+    pub fn new(stream: &'a mut S) -> AsyncBinaryWriter<'a, S> {
+        AsyncBinaryWriter::with_endian(stream, Endian::default())
+    }
+
+    pub fn with_endian(stream: &'a mut S, endian: Endian) -> AsyncBinaryWriter<'a, S> {
+        AsyncBinaryWriter {
+            stream,
+            endian,
+            length_prefix: LengthPrefix::default(),
+        }
+    }
+
+    pub fn set_endian(&mut self, endian: Endian) {
+        self.endian = endian;
+    }
+
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    pub fn set_length_prefix(&mut self, length_prefix: LengthPrefix) {
+        self.length_prefix = length_prefix;
+    }
+
+    pub fn length_prefix(&self) -> LengthPrefix {
+        self.length_prefix
+    }
+
+    pub async fn seek_to(&mut self, position: usize) -> Result<usize, BinaryError> {
+        Ok(self.stream.seek(position).await?)
+    }
+
+    pub async fn get_cur_pos(&mut self) -> Result<usize, BinaryError> {
+        Ok(self.stream.tell().await?)
+    }
+
+    pub async fn flush(&mut self) -> Result<(), BinaryError> {
+        Ok(self.stream.flush().await?)
+    }
+
+    pub async fn write_bytes(&mut self, data: &[u8]) -> Result<usize, BinaryError> {
+        Ok(self.stream.write(data).await?)
+    }
+
+    pub async fn write_string(&mut self, value: &str) -> Result<usize, BinaryError> {
+        let bytes = value.as_bytes();
+
+        self.write_length(bytes.len()).await?;
+        self.write_bytes(bytes).await
+    }
+
+    pub async fn write_usize(&mut self, value: usize) -> Result<usize, BinaryError> {
+        self.write_u64(value as u64).await
+    }
+
+    pub async fn write_isize(&mut self, value: isize) -> Result<usize, BinaryError> {
+        self.write_i64(value as i64).await
+    }
+
+    pub async fn write_varint_u64(&mut self, value: u64) -> Result<usize, BinaryError> {
+        let mut data = Vec::with_capacity(10);
+        let mut remaining = value;
+
+        loop {
+            let byte = (remaining & 0x7F) as u8;
+            remaining >>= 7;
+            if remaining == 0 {
+                data.push(byte);
+                break;
+            }
+            data.push(byte | 0x80);
+        }
+
+        self.write_bytes(&data).await
+    }
+
+    async fn write_length(&mut self, len: usize) -> Result<usize, BinaryError> {
+        let max = match self.length_prefix {
+            LengthPrefix::U8 => u8::MAX as usize,
+            LengthPrefix::U16 => u16::MAX as usize,
+            LengthPrefix::U32 => u32::MAX as usize,
+            LengthPrefix::U64 | LengthPrefix::Varint => usize::MAX,
+        };
+        if len > max {
+            return Err(BinaryError::LengthOverflow { len, max });
+        }
+
+        match self.length_prefix {
+            LengthPrefix::U8 => self.write_u8(len as u8).await,
+            LengthPrefix::U16 => self.write_u16(len as u16).await,
+            LengthPrefix::U32 => self.write_u32(len as u32).await,
+            LengthPrefix::U64 => self.write_u64(len as u64).await,
+            LengthPrefix::Varint => self.write_varint_u64(len as u64).await,
+        }
+    }
+
+    pub async fn write_bool(&mut self, value: bool) -> Result<usize, BinaryError> {
+        self.write_u8(value as u8).await
+    }
+
+    async_write_num! {
+        write_f32 => f32;
+        write_f64 => f64;
+        write_u128 => u128;
+        write_i128 => i128;
+        write_u64 => u64;
+        write_i64 => i64;
+        write_u32 => u32;
+        write_i32 => i32;
+        write_u16 => u16;
+        write_i16 => i16;
+        write_u8 => u8;
+        write_i8 => i8;
+    }
+}
@@ -0,0 +1,72 @@
+use crate::{BinaryError, BinaryReader, BinaryWriter, Endian, LengthPrefix, Stream};
+
+/// Owns a stream and hands out short-lived readers and writers over it, so
+/// reads and writes can be interleaved against the same cursor without
+/// juggling borrows. Endianness and the length prefix are shared by both.
+pub struct BinaryStream<S: Stream> {
+    stream: S,
+    endian: Endian,
+    length_prefix: LengthPrefix,
+}
+
+impl<S: Stream> BinaryStream<S> {
+    pub fn new(stream: S) -> BinaryStream<S> {
+        BinaryStream::with_endian(stream, Endian::default())
+    }
+
+    pub fn with_endian(stream: S, endian: Endian) -> BinaryStream<S> {
+        BinaryStream {
+            stream,
+            endian,
+            length_prefix: LengthPrefix::default(),
+        }
+    }
+
+    pub fn set_endian(&mut self, endian: Endian) {
+        self.endian = endian;
+    }
+
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    pub fn set_length_prefix(&mut self, length_prefix: LengthPrefix) {
+        self.length_prefix = length_prefix;
+    }
+
+    pub fn length_prefix(&self) -> LengthPrefix {
+        self.length_prefix
+    }
+
+    pub fn reader(&mut self) -> BinaryReader<'_> {
+        let mut reader = BinaryReader::with_endian(&mut self.stream, self.endian);
+        reader.set_length_prefix(self.length_prefix);
+        reader
+    }
+
+    pub fn writer(&mut self) -> BinaryWriter<'_> {
+        let mut writer = BinaryWriter::with_endian(&mut self.stream, self.endian);
+        writer.set_length_prefix(self.length_prefix);
+        writer
+    }
+
+    pub fn seek_to(&mut self, to: usize) -> Result<usize, BinaryError> {
+        Ok(self.stream.seek(to)?)
+    }
+
+    pub fn get_cur_pos(&mut self) -> Result<usize, BinaryError> {
+        Ok(self.stream.tell()?)
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.stream
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    pub fn into_inner(self) -> S {
+        self.stream
+    }
+}
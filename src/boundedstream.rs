@@ -0,0 +1,89 @@
+use crate::{Stream, StreamError};
+
+/// Confines reads and writes to the `limit` bytes that follow the inner
+/// stream's position when it's wrapped, such as a single archive member.
+/// Going past the end of that region is an error rather than a short read or
+/// write. Positions are relative to the start of the region, seeks can't
+/// leave it, and `truncate` is refused, so a member parser can't reach the
+/// data around it.
+pub struct BoundedStream<S: Stream> {
+    inner: S,
+    start: usize,
+    limit: usize,
+    position: usize,
+}
+
+impl<S: Stream> BoundedStream<S> {
+    pub fn new(mut inner: S, limit: usize) -> BoundedStream<S> {
+        let start = inner.tell().unwrap_or(0);
+
+        BoundedStream {
+            inner,
+            start,
+            limit,
+            position: 0,
+        }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.limit - self.position
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Stream> Stream for BoundedStream<S> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        if bytes.len() > self.remaining() {
+            return Err(StreamError::WriteError);
+        }
+
+        let written = self.inner.write(bytes)?;
+        self.position += written;
+
+        Ok(written)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        if buffer.len() > self.remaining() {
+            return Err(StreamError::ReadError);
+        }
+
+        let read = self.inner.read(buffer)?;
+        self.position += read;
+
+        Ok(read)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        if to > self.limit {
+            return Err(StreamError::SeekError);
+        }
+
+        let target = self.start.checked_add(to).ok_or(StreamError::SeekError)?;
+        self.inner.seek(target)?;
+        self.position = to;
+
+        Ok(to)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        let len = self.stream_len()?;
+        self.seek(len)
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        let len = self.inner.stream_len()?;
+        Ok(len.saturating_sub(self.start).min(self.limit))
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        Ok(self.position)
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{Stream, StreamError};
+use alloc::vec;
+use alloc::vec::Vec;
+
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+pub struct BufferedStream<S: Stream> {
+    inner: S,
+    buffer: Vec<u8>,
+    pos: usize,
+    filled: usize,
+}
+
+impl<S: Stream> BufferedStream<S> {
+    pub fn new(inner: S) -> BufferedStream<S> {
+        BufferedStream::with_capacity(inner, DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(inner: S, capacity: usize) -> BufferedStream<S> {
+        BufferedStream {
+            inner,
+            buffer: vec![0; capacity.max(1)],
+            pos: 0,
+            filled: 0,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    /// Any bytes that were buffered but not yet read are discarded.
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn discard_buffer(&mut self) {
+        self.pos = 0;
+        self.filled = 0;
+    }
+
+    // Moves the inner stream back to the logical position so that it no
+    // longer runs ahead by the unread part of the buffer.
+    fn sync_inner(&mut self) -> Result<(), StreamError> {
+        if self.pos < self.filled {
+            let position = self.tell()?;
+            self.inner.seek(position)?;
+        }
+        self.discard_buffer();
+
+        Ok(())
+    }
+}
+
+impl<S: Stream> Stream for BufferedStream<S> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        self.sync_inner()?;
+        self.inner.write(bytes)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        if self.pos == self.filled {
+            if buffer.len() >= self.buffer.len() {
+                self.discard_buffer();
+                return self.inner.read(buffer);
+            }
+
+            self.filled = self.inner.read(&mut self.buffer)?;
+            self.pos = 0;
+        }
+
+        let available = &self.buffer[self.pos..self.filled];
+        let read = available.len().min(buffer.len());
+        buffer[..read].copy_from_slice(&available[..read]);
+
+        self.pos += read;
+
+        Ok(read)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        if self.filled > 0 {
+            let end = self.inner.tell()?;
+            let start = end - self.filled;
+            if to >= start && to <= end {
+                self.pos = to - start;
+                return Ok(to);
+            }
+        }
+
+        self.discard_buffer();
+        self.inner.seek(to)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.discard_buffer();
+        self.inner.seek_end()
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        self.inner.stream_len()
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        Ok(self.inner.tell()? - (self.filled - self.pos))
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.inner.flush()
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), StreamError> {
+        self.sync_inner()?;
+        self.inner.truncate(len)
+    }
+}
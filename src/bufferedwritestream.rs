@@ -0,0 +1,151 @@
+use crate::{Stream, StreamError};
+use alloc::vec::Vec;
+
+const DEFAULT_CAPACITY: usize = 8 * 1024;
+
+/// Collects small writes in memory and hands them to the inner stream in
+/// one call once the buffer fills, on `flush`, or before any read, seek or
+/// truncate. Dropping the stream flushes whatever is still buffered, but
+/// errors are lost that way; call `flush` to see them.
+pub struct BufferedWriteStream<S: Stream> {
+    // Only `None` once `into_inner` has taken it.
+    inner: Option<S>,
+    buffer: Vec<u8>,
+    capacity: usize,
+}
+
+impl<S: Stream> BufferedWriteStream<S> {
+    pub fn new(inner: S) -> BufferedWriteStream<S> {
+        BufferedWriteStream::with_capacity(inner, DEFAULT_CAPACITY)
+    }
+
+    pub fn with_capacity(inner: S, capacity: usize) -> BufferedWriteStream<S> {
+        let capacity = capacity.max(1);
+
+        BufferedWriteStream {
+            inner: Some(inner),
+            buffer: Vec::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of bytes written but not yet passed to the inner stream.
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn get_ref(&self) -> &S {
+        match self.inner.as_ref() {
+            Some(inner) => inner,
+            None => unreachable!(),
+        }
+    }
+
+    /// Flushes the buffer and returns the inner stream.
+    pub fn into_inner(mut self) -> Result<S, StreamError> {
+        self.flush_buffer()?;
+
+        match self.inner.take() {
+            Some(inner) => Ok(inner),
+            None => unreachable!(),
+        }
+    }
+
+    fn inner(&mut self) -> &mut S {
+        match self.inner.as_mut() {
+            Some(inner) => inner,
+            None => unreachable!(),
+        }
+    }
+
+    fn flush_buffer(&mut self) -> Result<(), StreamError> {
+        let inner = match self.inner.as_mut() {
+            Some(inner) => inner,
+            None => return Ok(()),
+        };
+
+        let mut written = 0;
+        let mut result = Ok(());
+        while written < self.buffer.len() {
+            match inner.write(&self.buffer[written..]) {
+                Ok(0) => {
+                    result = Err(StreamError::WriteError);
+                    break;
+                }
+                Ok(count) => written += count,
+                Err(e) => {
+                    result = Err(e);
+                    break;
+                }
+            }
+        }
+
+        // Keep whatever the inner stream did not take so a retry resumes there.
+        self.buffer.drain(..written);
+        result
+    }
+}
+
+impl<S: Stream> Stream for BufferedWriteStream<S> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        if self.buffer.len() + bytes.len() > self.capacity {
+            self.flush_buffer()?;
+        }
+
+        if bytes.len() >= self.capacity {
+            return self.inner().write(bytes);
+        }
+
+        self.buffer.extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        self.flush_buffer()?;
+        self.inner().read(buffer)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        self.flush_buffer()?;
+        self.inner().seek(to)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.flush_buffer()?;
+        self.inner().seek_end()
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        self.flush_buffer()?;
+        self.inner().stream_len()
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        let buffered = self.buffer.len();
+        Ok(self.inner().tell()? + buffered)
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.flush_buffer()?;
+        self.inner().flush()
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), StreamError> {
+        self.flush_buffer()?;
+        self.inner().truncate(len)
+    }
+
+    fn close(self) -> Result<(), StreamError> {
+        self.into_inner()?.close()
+    }
+}
+
+impl<S: Stream> Drop for BufferedWriteStream<S> {
+    fn drop(&mut self) {
+        let _ = self.flush_buffer();
+    }
+}
@@ -0,0 +1,45 @@
+use crate::Endian;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The byte order a `BinaryReader` or `BinaryWriter` is compiled for. With
+/// `Endian`, the default, the order is whatever `set_endian` last chose and
+/// is checked on every read and write. `LittleEndian` and `BigEndian` fix
+/// the order in the type instead, so the check is compiled away.
+pub trait ByteOrder: sealed::Sealed {
+    // Converts `bytes` between little-endian and this order in place.
+    // `runtime` is the reader or writer's `Endian` setting.
+    #[doc(hidden)]
+    fn apply(runtime: Endian, bytes: &mut [u8]);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LittleEndian;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BigEndian;
+
+impl sealed::Sealed for Endian {}
+impl sealed::Sealed for LittleEndian {}
+impl sealed::Sealed for BigEndian {}
+
+impl ByteOrder for Endian {
+    #[inline]
+    fn apply(runtime: Endian, bytes: &mut [u8]) {
+        runtime.apply(bytes);
+    }
+}
+
+impl ByteOrder for LittleEndian {
+    #[inline]
+    fn apply(_runtime: Endian, _bytes: &mut [u8]) {}
+}
+
+impl ByteOrder for BigEndian {
+    #[inline]
+    fn apply(_runtime: Endian, bytes: &mut [u8]) {
+        bytes.reverse();
+    }
+}
@@ -0,0 +1,101 @@
+use crate::{Stream, StreamError};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+
+/// Presents several streams back to back as one read-only stream.
+pub struct ChainStream {
+    segments: Vec<Box<dyn Stream>>,
+    // Logical offset at which each segment starts, plus the total length.
+    starts: Vec<usize>,
+    position: usize,
+}
+
+impl ChainStream {
+    /// Chains `segments` in order; their lengths are taken once, up front.
+    pub fn new(mut segments: Vec<Box<dyn Stream>>) -> Result<ChainStream, StreamError> {
+        let mut starts = Vec::with_capacity(segments.len() + 1);
+        let mut total = 0usize;
+
+        for segment in segments.iter_mut() {
+            starts.push(total);
+            total = total
+                .checked_add(segment.stream_len()?)
+                .ok_or(StreamError::SeekError)?;
+        }
+        starts.push(total);
+
+        Ok(ChainStream {
+            segments,
+            starts,
+            position: 0,
+        })
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    pub fn into_inner(self) -> Vec<Box<dyn Stream>> {
+        self.segments
+    }
+
+    fn len(&self) -> usize {
+        self.starts[self.segments.len()]
+    }
+
+    // Index of the segment holding `position`, skipping empty segments.
+    fn segment_at(&self, position: usize) -> usize {
+        self.starts[1..].partition_point(|&end| end <= position)
+    }
+}
+
+impl Stream for ChainStream {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        if bytes.is_empty() {
+            return Ok(0);
+        }
+
+        Err(StreamError::WriteError)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        let mut read = 0;
+
+        while read < buffer.len() && self.position < self.len() {
+            let index = self.segment_at(self.position);
+            let local = self.position - self.starts[index];
+            let available = self.starts[index + 1] - self.position;
+            let want = available.min(buffer.len() - read);
+
+            let segment = &mut self.segments[index];
+            segment.seek(local)?;
+            let n = segment.read(&mut buffer[read..read + want])?;
+            if n == 0 {
+                return Err(StreamError::ReadError);
+            }
+
+            read += n;
+            self.position += n;
+        }
+
+        Ok(read)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        self.position = to;
+        Ok(self.position)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.position = self.len();
+        Ok(self.position)
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        Ok(self.len())
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        Ok(self.position)
+    }
+}
@@ -0,0 +1,110 @@
+use crate::{Stream, StreamError};
+
+const CRC32_POLYNOMIAL: u32 = 0xEDB8_8320;
+
+fn crc32_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+
+    for (i, entry) in table.iter_mut().enumerate() {
+        let mut crc = i as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ CRC32_POLYNOMIAL
+            } else {
+                crc >> 1
+            };
+        }
+        *entry = crc;
+    }
+
+    table
+}
+
+fn crc32_update(table: &[u32; 256], mut crc: u32, bytes: &[u8]) -> u32 {
+    for &byte in bytes {
+        let index = ((crc ^ byte as u32) & 0xFF) as usize;
+        crc = (crc >> 8) ^ table[index];
+    }
+
+    crc
+}
+
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    !crc32_update(&crc32_table(), !0, bytes)
+}
+
+/// Computes a running CRC32 (IEEE) over every byte read from or written to
+/// the inner stream. Seeking does not reset the checksum, so skipped or
+/// re-read bytes make it meaningless; call `reset_checksum` at the start of
+/// each checksummed section.
+pub struct ChecksumStream<S: Stream> {
+    inner: S,
+    table: [u32; 256],
+    crc: u32,
+}
+
+impl<S: Stream> ChecksumStream<S> {
+    pub fn new(inner: S) -> ChecksumStream<S> {
+        ChecksumStream {
+            inner,
+            table: crc32_table(),
+            crc: !0,
+        }
+    }
+
+    pub fn checksum(&self) -> u32 {
+        !self.crc
+    }
+
+    pub fn reset_checksum(&mut self) {
+        self.crc = !0;
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn update(&mut self, bytes: &[u8]) {
+        self.crc = crc32_update(&self.table, self.crc, bytes);
+    }
+}
+
+impl<S: Stream> Stream for ChecksumStream<S> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        let written = self.inner.write(bytes)?;
+        self.update(&bytes[..written]);
+
+        Ok(written)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        let read = self.inner.read(buffer)?;
+        self.update(&buffer[..read]);
+
+        Ok(read)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        self.inner.seek(to)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.inner.seek_end()
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        self.inner.stream_len()
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        self.inner.tell()
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.inner.flush()
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), StreamError> {
+        self.inner.truncate(len)
+    }
+}
@@ -0,0 +1,91 @@
+use crate::streamio::StreamIo;
+use crate::{Stream, StreamError};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::{Read, Write};
+
+enum Mode<S: Stream> {
+    Compress(DeflateEncoder<StreamIo<S>>),
+    Decompress(DeflateDecoder<StreamIo<S>>),
+}
+
+/// Compresses bytes written to, or decompresses bytes read from, the inner
+/// stream using raw deflate. A stream only works in the direction it was
+/// created for, and since compressed data can't be addressed by its
+/// uncompressed offset, `seek` and `tell` always fail. Call `finish` once
+/// writing is done or the final block is never written.
+pub struct DeflateStream<S: Stream> {
+    mode: Mode<S>,
+}
+
+impl<S: Stream> DeflateStream<S> {
+    pub fn compress(inner: S) -> DeflateStream<S> {
+        DeflateStream::compress_with_level(inner, Compression::default())
+    }
+
+    pub fn compress_with_level(inner: S, level: Compression) -> DeflateStream<S> {
+        DeflateStream {
+            mode: Mode::Compress(DeflateEncoder::new(StreamIo::new(inner), level)),
+        }
+    }
+
+    pub fn decompress(inner: S) -> DeflateStream<S> {
+        DeflateStream {
+            mode: Mode::Decompress(DeflateDecoder::new(StreamIo::new(inner))),
+        }
+    }
+
+    /// Writes the final compressed block, if compressing, and hands back the
+    /// inner stream.
+    pub fn finish(self) -> Result<S, StreamError> {
+        match self.mode {
+            Mode::Compress(encoder) => match encoder.finish() {
+                Ok(io) => Ok(io.into_inner()),
+                Err(_) => Err(StreamError::WriteError),
+            },
+            Mode::Decompress(decoder) => Ok(decoder.into_inner().into_inner()),
+        }
+    }
+}
+
+impl<S: Stream> Stream for DeflateStream<S> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        match self.mode {
+            Mode::Compress(ref mut encoder) => {
+                encoder.write(bytes).map_err(|_| StreamError::WriteError)
+            }
+            Mode::Decompress(_) if bytes.is_empty() => Ok(0),
+            Mode::Decompress(_) => Err(StreamError::WriteError),
+        }
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        match self.mode {
+            Mode::Compress(_) if buffer.is_empty() => Ok(0),
+            Mode::Compress(_) => Err(StreamError::ReadError),
+            Mode::Decompress(ref mut decoder) => {
+                decoder.read(buffer).map_err(|_| StreamError::ReadError)
+            }
+        }
+    }
+
+    fn seek(&mut self, _to: usize) -> Result<usize, StreamError> {
+        Err(StreamError::SeekError)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        Err(StreamError::SeekError)
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        Err(StreamError::TellError)
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        match self.mode {
+            Mode::Compress(ref mut encoder) => encoder.flush().map_err(|_| StreamError::WriteError),
+            Mode::Decompress(_) => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{Stream, StreamError};
+use aes::cipher::{KeyIvInit, StreamCipher, StreamCipherSeek};
+use aes::Aes256;
+use alloc::vec::Vec;
+
+type Aes256Ctr = ctr::Ctr128BE<Aes256>;
+
+/// Encrypts everything written to the inner stream and decrypts everything
+/// read from it with AES-256 in CTR mode. The keystream is positioned by
+/// byte offset, so seeking and random access work as on a plain stream.
+/// A key and nonce pair must never be reused for different data.
+pub struct EncryptedStream<S: Stream> {
+    inner: S,
+    cipher: Aes256Ctr,
+    position: usize,
+}
+
+impl<S: Stream> EncryptedStream<S> {
+    pub fn new(
+        mut inner: S,
+        key: &[u8; 32],
+        nonce: &[u8; 16],
+    ) -> Result<EncryptedStream<S>, StreamError> {
+        let position = inner.tell()?;
+
+        Ok(EncryptedStream {
+            inner,
+            cipher: Aes256Ctr::new(key.into(), nonce.into()),
+            position,
+        })
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn apply_keystream(&mut self, bytes: &mut [u8]) {
+        self.cipher.seek(self.position as u64);
+        self.cipher.apply_keystream(bytes);
+    }
+}
+
+impl<S: Stream> Stream for EncryptedStream<S> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        let mut encrypted = Vec::from(bytes);
+        self.apply_keystream(&mut encrypted);
+
+        let written = self.inner.write(&encrypted)?;
+        self.position += written;
+
+        Ok(written)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        let read = self.inner.read(buffer)?;
+        self.apply_keystream(&mut buffer[..read]);
+        self.position += read;
+
+        Ok(read)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        self.position = self.inner.seek(to)?;
+        Ok(self.position)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.position = self.inner.seek_end()?;
+        Ok(self.position)
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        self.inner.stream_len()
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        Ok(self.position)
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.inner.flush()
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), StreamError> {
+        self.inner.truncate(len)
+    }
+}
@@ -8,39 +8,68 @@ pub enum OpenType {
     Open,
 }
 
+pub enum FileMode {
+    Read,
+    Write,
+    ReadWrite,
+    Append,
+    Truncate,
+}
+
 pub struct Filestream {
     file: fs::File,
 }
 
 impl Filestream {
     pub fn new(filepath: &str, open_type: OpenType) -> Result<Filestream, StreamError> {
-        let file;
-
-        match open_type {
-            OpenType::OpenAndCreate => file = fs::File::create(filepath),
-            OpenType::Open => file = fs::File::open(filepath),
-        }
+        let file = match open_type {
+            OpenType::OpenAndCreate => fs::File::create(filepath),
+            OpenType::Open => fs::File::open(filepath),
+        };
 
         match file {
             Ok(f) => Ok(Filestream { file: f }),
             Err(_) => Err(StreamError::OpenError),
         }
     }
+
+    pub fn open_with(filepath: &str, mode: FileMode) -> Result<Filestream, StreamError> {
+        let mut options = fs::OpenOptions::new();
+        match mode {
+            FileMode::Read => options.read(true),
+            FileMode::Write => options.write(true).create(true),
+            FileMode::ReadWrite => options.read(true).write(true).create(true),
+            FileMode::Append => options.append(true).create(true),
+            FileMode::Truncate => options.write(true).create(true).truncate(true),
+        };
+
+        let mut file = match options.open(filepath) {
+            Ok(f) => f,
+            Err(_) => return Err(StreamError::OpenError),
+        };
+
+        // Appends always land at the end, but the OS offset of a freshly
+        // opened file starts at 0 until the first write; move it up front so
+        // `tell` reports where the next write will go.
+        if let FileMode::Append = mode {
+            if file.seek(SeekFrom::End(0)).is_err() {
+                return Err(StreamError::SeekError);
+            }
+        }
+
+        Ok(Filestream { file })
+    }
 }
 
 impl Stream for Filestream {
-    fn write(&mut self, bytes: &Vec<u8>) -> Result<usize, StreamError> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
         match self.file.write(bytes) {
             Ok(res) => Ok(res),
             Err(_) => Err(StreamError::WriteError),
         }
     }
 
-    fn read(&mut self, buffer: &mut Vec<u8>) -> Result<usize, StreamError> {
-        if self.tell().unwrap() + buffer.len() > self.file.metadata().unwrap().len() as usize {
-            return Err(StreamError::ReadError);
-        }
-
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
         match self.file.read(buffer) {
             Ok(res) => Ok(res),
             Err(_) => Err(StreamError::ReadError),
@@ -54,10 +83,47 @@ impl Stream for Filestream {
         }
     }
 
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        match self.file.seek(SeekFrom::End(0)) {
+            Ok(res) => Ok(res as usize),
+            Err(_) => Err(StreamError::SeekError),
+        }
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        match self.file.metadata() {
+            Ok(metadata) => Ok(metadata.len() as usize),
+            Err(_) => Err(StreamError::SeekError),
+        }
+    }
+
     fn tell(&mut self) -> Result<usize, StreamError> {
-        match self.file.seek(SeekFrom::Current(0)) {
+        match self.file.stream_position() {
             Ok(res) => Ok(res as usize),
             Err(_) => Err(StreamError::TellError),
         }
     }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        match self.file.flush() {
+            Ok(_) => Ok(()),
+            Err(_) => Err(StreamError::WriteError),
+        }
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), StreamError> {
+        match self.file.set_len(len as u64) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(StreamError::WriteError),
+        }
+    }
+
+    fn close(mut self) -> Result<(), StreamError> {
+        self.flush()?;
+
+        match self.file.sync_all() {
+            Ok(_) => Ok(()),
+            Err(_) => Err(StreamError::WriteError),
+        }
+    }
 }
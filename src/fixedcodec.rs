@@ -0,0 +1,634 @@
+//! A serde codec with a fixed, documented byte layout, selected with
+//! `Codec::Fixed`. Unlike bincode's, this layout is part of the crate's
+//! format and only changes along with `FIXED_CODEC_VERSION`.
+//!
+//! Layout version 1, with every multi-byte value in the reader or writer's
+//! byte order:
+//!
+//! - `bool` is one byte, 0 or 1.
+//! - Integers are written at their full width, `u8` through `u128`.
+//! - `f32` and `f64` are their IEEE 754 bits.
+//! - `char` is its Unicode scalar value as a `u32`.
+//! - Strings and byte arrays are a `u64` byte length and the bytes.
+//! - `Option` is a 0 byte for `None`, or a 1 byte followed by the value.
+//! - Sequences and maps are a `u64` count and the elements or key-value pairs.
+//! - Tuples and structs are their fields in order, with no prefix.
+//! - Enums are the `u32` variant index followed by the variant's fields.
+//! - Unit values and unit structs take no bytes.
+//!
+//! The format is not self-describing, so types that deserialize through
+//! `deserialize_any` are not supported.
+
+use crate::byteorder::ByteOrder;
+use crate::{offset_to_usize, BinaryError, BinaryReader, BinaryWriter};
+use alloc::string::ToString;
+use core::fmt::Display;
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::ser::{self, Serialize};
+
+pub const FIXED_CODEC_VERSION: u32 = 1;
+
+impl ser::Error for BinaryError {
+    fn custom<T: Display>(msg: T) -> BinaryError {
+        BinaryError::InvalidData(msg.to_string())
+    }
+}
+
+impl de::Error for BinaryError {
+    fn custom<T: Display>(msg: T) -> BinaryError {
+        BinaryError::InvalidData(msg.to_string())
+    }
+}
+
+pub(crate) struct Serializer<'w, 'a, B> {
+    writer: &'w mut BinaryWriter<'a, B>,
+}
+
+impl<'w, 'a, B: ByteOrder> Serializer<'w, 'a, B> {
+    pub(crate) fn new(writer: &'w mut BinaryWriter<'a, B>) -> Serializer<'w, 'a, B> {
+        Serializer { writer }
+    }
+
+    fn write_len(&mut self, len: Option<usize>) -> Result<(), BinaryError> {
+        match len {
+            Some(len) => self.writer.write_u64(len as u64).map(|_| ()),
+            None => Err(BinaryError::InvalidData(
+                "the fixed codec needs sequence lengths up front".to_string(),
+            )),
+        }
+    }
+}
+
+impl<'s, 'w, 'a, B: ByteOrder> ser::Serializer for &'s mut Serializer<'w, 'a, B> {
+    type Ok = ();
+    type Error = BinaryError;
+    type SerializeSeq = Self;
+    type SerializeTuple = Self;
+    type SerializeTupleStruct = Self;
+    type SerializeTupleVariant = Self;
+    type SerializeMap = Self;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Self;
+
+    fn serialize_bool(self, v: bool) -> Result<(), BinaryError> {
+        self.writer.write_bool(v).map(|_| ())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), BinaryError> {
+        self.writer.write_i8(v).map(|_| ())
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), BinaryError> {
+        self.writer.write_i16(v).map(|_| ())
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), BinaryError> {
+        self.writer.write_i32(v).map(|_| ())
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), BinaryError> {
+        self.writer.write_i64(v).map(|_| ())
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), BinaryError> {
+        self.writer.write_i128(v).map(|_| ())
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), BinaryError> {
+        self.writer.write_u8(v).map(|_| ())
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), BinaryError> {
+        self.writer.write_u16(v).map(|_| ())
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), BinaryError> {
+        self.writer.write_u32(v).map(|_| ())
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), BinaryError> {
+        self.writer.write_u64(v).map(|_| ())
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), BinaryError> {
+        self.writer.write_u128(v).map(|_| ())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), BinaryError> {
+        self.writer.write_f32(v).map(|_| ())
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), BinaryError> {
+        self.writer.write_f64(v).map(|_| ())
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), BinaryError> {
+        self.writer.write_char(v).map(|_| ())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), BinaryError> {
+        self.serialize_bytes(v.as_bytes())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), BinaryError> {
+        self.write_len(Some(v.len()))?;
+        self.writer.write_bytes(v).map(|_| ())
+    }
+
+    fn serialize_none(self) -> Result<(), BinaryError> {
+        self.writer.write_u8(0).map(|_| ())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), BinaryError> {
+        self.writer.write_u8(1)?;
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), BinaryError> {
+        Ok(())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), BinaryError> {
+        Ok(())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+    ) -> Result<(), BinaryError> {
+        self.writer.write_u32(variant_index).map(|_| ())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), BinaryError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        value: &T,
+    ) -> Result<(), BinaryError> {
+        self.writer.write_u32(variant_index)?;
+        value.serialize(self)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self, BinaryError> {
+        self.write_len(len)?;
+        Ok(self)
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self, BinaryError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self, BinaryError> {
+        Ok(self)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, BinaryError> {
+        self.writer.write_u32(variant_index)?;
+        Ok(self)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self, BinaryError> {
+        self.write_len(len)?;
+        Ok(self)
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self, BinaryError> {
+        Ok(self)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self, BinaryError> {
+        self.writer.write_u32(variant_index)?;
+        Ok(self)
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+// Compound values are their elements back to back; any prefix was written
+// when the compound was started.
+macro_rules! impl_serialize_compound {
+    ($($trait:ident::$method:ident),*) => {
+        $(
+            impl<'s, 'w, 'a, B: ByteOrder> ser::$trait for &'s mut Serializer<'w, 'a, B> {
+                type Ok = ();
+                type Error = BinaryError;
+
+                fn $method<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), BinaryError> {
+                    value.serialize(&mut **self)
+                }
+
+                fn end(self) -> Result<(), BinaryError> {
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_serialize_compound! {
+    SerializeSeq::serialize_element,
+    SerializeTuple::serialize_element,
+    SerializeTupleStruct::serialize_field,
+    SerializeTupleVariant::serialize_field
+}
+
+impl<'s, 'w, 'a, B: ByteOrder> ser::SerializeMap for &'s mut Serializer<'w, 'a, B> {
+    type Ok = ();
+    type Error = BinaryError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), BinaryError> {
+        key.serialize(&mut **self)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), BinaryError> {
+        value.serialize(&mut **self)
+    }
+
+    fn end(self) -> Result<(), BinaryError> {
+        Ok(())
+    }
+}
+
+macro_rules! impl_serialize_struct {
+    ($($trait:ident),*) => {
+        $(
+            impl<'s, 'w, 'a, B: ByteOrder> ser::$trait for &'s mut Serializer<'w, 'a, B> {
+                type Ok = ();
+                type Error = BinaryError;
+
+                fn serialize_field<T: Serialize + ?Sized>(
+                    &mut self,
+                    _key: &'static str,
+                    value: &T,
+                ) -> Result<(), BinaryError> {
+                    value.serialize(&mut **self)
+                }
+
+                fn end(self) -> Result<(), BinaryError> {
+                    Ok(())
+                }
+            }
+        )*
+    };
+}
+
+impl_serialize_struct!(SerializeStruct, SerializeStructVariant);
+
+pub(crate) struct Deserializer<'r, 'a, B> {
+    reader: &'r mut BinaryReader<'a, B>,
+}
+
+impl<'r, 'a, B: ByteOrder> Deserializer<'r, 'a, B> {
+    pub(crate) fn new(reader: &'r mut BinaryReader<'a, B>) -> Deserializer<'r, 'a, B> {
+        Deserializer { reader }
+    }
+
+    fn read_len(&mut self) -> Result<usize, BinaryError> {
+        let len = offset_to_usize(self.reader.read_u64()?)?;
+        self.reader.check_alloc(len)?;
+
+        Ok(len)
+    }
+
+    // Runs `f` one level deeper, so nested sequences, maps, structs and enums
+    // count against the reader's `max_depth`.
+    fn nested<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, BinaryError>,
+    ) -> Result<T, BinaryError> {
+        self.reader.enter_nesting()?;
+        let result = f(self);
+        self.reader.exit_nesting();
+
+        result
+    }
+}
+
+fn not_self_describing() -> BinaryError {
+    BinaryError::InvalidData("the fixed codec is not self-describing".to_string())
+}
+
+impl<'de, 'd, 'r, 'a, B: ByteOrder> de::Deserializer<'de> for &'d mut Deserializer<'r, 'a, B> {
+    type Error = BinaryError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, BinaryError> {
+        Err(not_self_describing())
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_bool(self.reader.read_bool()?)
+    }
+
+    fn deserialize_i8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_i8(self.reader.read_i8()?)
+    }
+
+    fn deserialize_i16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_i16(self.reader.read_i16()?)
+    }
+
+    fn deserialize_i32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_i32(self.reader.read_i32()?)
+    }
+
+    fn deserialize_i64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_i64(self.reader.read_i64()?)
+    }
+
+    fn deserialize_i128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_i128(self.reader.read_i128()?)
+    }
+
+    fn deserialize_u8<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_u8(self.reader.read_u8()?)
+    }
+
+    fn deserialize_u16<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_u16(self.reader.read_u16()?)
+    }
+
+    fn deserialize_u32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_u32(self.reader.read_u32()?)
+    }
+
+    fn deserialize_u64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_u64(self.reader.read_u64()?)
+    }
+
+    fn deserialize_u128<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_u128(self.reader.read_u128()?)
+    }
+
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_f32(self.reader.read_f32()?)
+    }
+
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_f64(self.reader.read_f64()?)
+    }
+
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_char(self.reader.read_char()?)
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        self.deserialize_string(visitor)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        let len = self.read_len()?;
+        visitor.visit_string(self.reader.read_string_with_len(len)?)
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        self.deserialize_byte_buf(visitor)
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        let len = self.read_len()?;
+        visitor.visit_byte_buf(self.reader.read_bytes(len)?)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        match self.reader.read_u8()? {
+            0 => visitor.visit_none(),
+            1 => visitor.visit_some(self),
+            tag => Err(self.reader.error_at(1, BinaryError::InvalidDiscriminant(u32::from(tag)))),
+        }
+    }
+
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, BinaryError> {
+        visitor.visit_unit()
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, BinaryError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        self.nested(|de| {
+            let remaining = de.read_len()?;
+            visitor.visit_seq(Elements::counted(de, remaining))
+        })
+    }
+
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, BinaryError> {
+        visitor.visit_seq(Elements::fields(self, len))
+    }
+
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, BinaryError> {
+        self.deserialize_tuple(len, visitor)
+    }
+
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, BinaryError> {
+        self.nested(|de| {
+            let remaining = de.read_len()?;
+            visitor.visit_map(Elements::counted(de, remaining))
+        })
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, BinaryError> {
+        self.nested(|de| de.deserialize_tuple(fields.len(), visitor))
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, BinaryError> {
+        self.nested(|de| visitor.visit_enum(de))
+    }
+
+    fn deserialize_identifier<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, BinaryError> {
+        Err(not_self_describing())
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, BinaryError> {
+        Err(not_self_describing())
+    }
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+}
+
+// Hands out a known number of sequence elements, tuple fields or map entries.
+struct Elements<'d, 'r, 'a, B> {
+    de: &'d mut Deserializer<'r, 'a, B>,
+    remaining: usize,
+    // Set while a count read from the input still has to be checked against
+    // `max_alloc`, which waits for the first element to learn its size.
+    unchecked: bool,
+    key_size: usize,
+}
+
+impl<'d, 'r, 'a, B: ByteOrder> Elements<'d, 'r, 'a, B> {
+    fn counted(de: &'d mut Deserializer<'r, 'a, B>, remaining: usize) -> Self {
+        Elements {
+            de,
+            remaining,
+            unchecked: true,
+            key_size: 0,
+        }
+    }
+
+    fn fields(de: &'d mut Deserializer<'r, 'a, B>, remaining: usize) -> Self {
+        Elements {
+            de,
+            remaining,
+            unchecked: false,
+            key_size: 0,
+        }
+    }
+
+    // Checks the bytes `count` elements of `size` would take, once.
+    fn check_alloc(&mut self, count: usize, size: usize) -> Result<(), BinaryError> {
+        if self.unchecked {
+            self.unchecked = false;
+            self.de.reader.check_alloc(count.saturating_mul(size))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'de, 'd, 'r, 'a, B: ByteOrder> de::SeqAccess<'de> for Elements<'d, 'r, 'a, B> {
+    type Error = BinaryError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, BinaryError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        self.check_alloc(self.remaining, core::mem::size_of::<T::Value>())?;
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'de, 'd, 'r, 'a, B: ByteOrder> de::MapAccess<'de> for Elements<'d, 'r, 'a, B> {
+    type Error = BinaryError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, BinaryError> {
+        if self.remaining == 0 {
+            return Ok(None);
+        }
+
+        if self.unchecked {
+            self.key_size = core::mem::size_of::<K::Value>();
+        }
+        self.remaining -= 1;
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, BinaryError> {
+        let entry = self.key_size + core::mem::size_of::<V::Value>();
+        self.check_alloc(self.remaining + 1, entry)?;
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+}
+
+impl<'de, 'd, 'r, 'a, B: ByteOrder> de::EnumAccess<'de> for &'d mut Deserializer<'r, 'a, B> {
+    type Error = BinaryError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        self,
+        seed: V,
+    ) -> Result<(V::Value, Self), BinaryError> {
+        let index = self.reader.read_u32()?;
+        let variant = seed.deserialize(IntoDeserializer::<BinaryError>::into_deserializer(index))?;
+
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'd, 'r, 'a, B: ByteOrder> de::VariantAccess<'de> for &'d mut Deserializer<'r, 'a, B> {
+    type Error = BinaryError;
+
+    fn unit_variant(self) -> Result<(), BinaryError> {
+        Ok(())
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, BinaryError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, len: usize, visitor: V) -> Result<V::Value, BinaryError> {
+        de::Deserializer::deserialize_tuple(self, len, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, BinaryError> {
+        de::Deserializer::deserialize_tuple(self, fields.len(), visitor)
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{Stream, StreamError};
+use sha2::{Digest, Sha256};
+
+/// Computes a running SHA-256 digest over every byte read from or written to
+/// the inner stream, for storing alongside the data in a manifest. Like
+/// `ChecksumStream`, seeking doesn't touch the digest, so once the stream has
+/// been seeked over or back the digest no longer matches the data.
+pub struct HashingStream<S: Stream> {
+    inner: S,
+    hasher: Sha256,
+}
+
+impl<S: Stream> HashingStream<S> {
+    pub fn new(inner: S) -> HashingStream<S> {
+        HashingStream {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// The digest of everything read or written so far.
+    pub fn finalize(self) -> [u8; 32] {
+        self.hasher.finalize().into()
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: Stream> Stream for HashingStream<S> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        let written = self.inner.write(bytes)?;
+        self.hasher.update(&bytes[..written]);
+
+        Ok(written)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        let read = self.inner.read(buffer)?;
+        self.hasher.update(&buffer[..read]);
+
+        Ok(read)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        self.inner.seek(to)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.inner.seek_end()
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        self.inner.stream_len()
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        self.inner.tell()
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.inner.flush()
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), StreamError> {
+        self.inner.truncate(len)
+    }
+}
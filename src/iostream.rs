@@ -0,0 +1,68 @@
+use crate::{Stream, StreamError};
+use std::io::{Read, Seek, SeekFrom, Write};
+
+pub struct IoStream<T: Read + Write + Seek> {
+    inner: T,
+}
+
+impl<T: Read + Write + Seek> IoStream<T> {
+    pub fn new(inner: T) -> IoStream<T> {
+        IoStream { inner }
+    }
+
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+}
+
+impl<T: Read + Write + Seek> Stream for IoStream<T> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        match self.inner.write_all(bytes) {
+            Ok(_) => Ok(bytes.len()),
+            Err(_) => Err(StreamError::WriteError),
+        }
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        match self.inner.read(buffer) {
+            Ok(res) => Ok(res),
+            Err(_) => Err(StreamError::ReadError),
+        }
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        match self.inner.seek(SeekFrom::Start(to as u64)) {
+            Ok(res) => Ok(res as usize),
+            Err(_) => Err(StreamError::SeekError),
+        }
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        match self.inner.seek(SeekFrom::End(0)) {
+            Ok(res) => Ok(res as usize),
+            Err(_) => Err(StreamError::SeekError),
+        }
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        match self.inner.stream_position() {
+            Ok(res) => Ok(res as usize),
+            Err(_) => Err(StreamError::TellError),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        match self.inner.flush() {
+            Ok(_) => Ok(()),
+            Err(_) => Err(StreamError::WriteError),
+        }
+    }
+}
@@ -1,21 +1,257 @@
-extern crate bincode;
-
-use std::string::FromUtf8Error;
-
-use bincode::{deserialize, serialize};
+#![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate bincode;
+#[cfg(feature = "std")]
+extern crate serde;
+
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::{FromUtf16Error, FromUtf8Error, String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt::Write as _;
+use core::marker::PhantomData;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use core::num::{NonZeroU32, NonZeroU64, ParseIntError};
+use core::str::Utf8Error;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::ffi::OsString;
+#[cfg(feature = "std")]
+use std::hash::Hash;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+#[cfg(feature = "std")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "std")]
+use bincode::{deserialize_from, serialize, Options};
+#[cfg(feature = "std")]
+use serde::{de::DeserializeOwned, Serialize};
+
+use byteorder::ByteOrder;
+use encoding::StringEncoding;
+use memorystream::Memorystream;
+use nullstream::NullStream;
+use scopedreader::ScopedReader;
+#[cfg(feature = "std")]
+use slicestream::SliceStream;
+use num::{BinaryNum, Primitive};
+use owned::{OwnedBinaryReader, OwnedBinaryWriter};
+use positionguard::PositionGuard;
+use stringpool::{StringPool, StringTable};
+use tagged::TaggedValue;
+
+#[cfg(feature = "derive")]
+pub use binary_derive::{BinaryReadable, BinaryWritable};
+
+#[cfg(feature = "tokio")]
+pub mod asyncstream;
+pub mod binarystream;
+pub mod boundedstream;
+pub mod bufferedstream;
+pub mod bufferedwritestream;
+pub mod chainstream;
+pub mod byteorder;
+pub mod checksumstream;
+#[cfg(feature = "compression")]
+pub mod deflatestream;
+pub mod encoding;
+#[cfg(feature = "crypto")]
+pub mod encryptedstream;
+#[cfg(feature = "std")]
 pub mod filestream;
+#[cfg(feature = "std")]
+pub mod fixedcodec;
+#[cfg(feature = "hash")]
+pub mod hashingstream;
+#[cfg(feature = "std")]
+pub mod iostream;
 pub mod memorystream;
-
-pub struct BinaryReader<'a> {
+pub mod mockstream;
+pub mod nullstream;
+pub mod num;
+pub mod owned;
+pub mod positionguard;
+pub mod recordingstream;
+#[cfg(feature = "std")]
+pub mod retrystream;
+pub mod scopedreader;
+#[cfg(feature = "compression")]
+pub mod seekabledeflatestream;
+pub mod slicestream;
+#[cfg(feature = "std")]
+pub mod streamio;
+pub mod stringpool;
+pub mod tagged;
+#[cfg(feature = "std")]
+pub mod tcpstream;
+pub mod teestream;
+#[cfg(feature = "std")]
+pub mod throttledstream;
+#[cfg(feature = "log")]
+pub mod tracingstream;
+
+pub struct BinaryReader<'a, B = Endian> {
     stream: &'a mut dyn Stream,
+    depth: usize,
+    max_depth: usize,
+    endian: Endian,
+    max_alloc: usize,
+    byte_limit: usize,
+    consumed: usize,
+    length_prefix: LengthPrefix,
+    codec: Codec,
+    scratch: Vec<u8>,
+    order: PhantomData<B>,
 }
 
 #[derive(Debug)]
 pub enum BinaryError {
     StreamError(StreamError),
+    #[cfg(feature = "std")]
     BinCodeErr(Box<bincode::ErrorKind>),
+    #[cfg(feature = "json")]
+    JsonError(serde_json::Error),
     Utf8Error(FromUtf8Error),
+    Utf16Error(FromUtf16Error),
+    ParseIntError(ParseIntError),
+    InvalidData(String),
+    RecursionLimitExceeded,
+    CountMismatch { expected: usize, found: usize },
+    InvalidBool(u8),
+    InvalidChar(u32),
+    UnexpectedEof { expected: usize, got: usize },
+    AllocationTooLarge { requested: usize, limit: usize },
+    VarintTooLong,
+    InvalidAddressFamily(u8),
+    ShortWrite { expected: usize, written: usize },
+    OffsetOutOfRange(u64),
+    ChecksumMismatch { expected: u32, found: u32 },
+    BadMagic { expected: Vec<u8>, found: Vec<u8> },
+    UnexpectedZero,
+    InvalidDiscriminant(u32),
+    UnknownTag(u8),
+    LengthOverflow { len: usize, max: usize },
+    ByteBudgetExceeded { consumed: usize, limit: usize },
+    UnsupportedVersion { version: u16, min: u16, max: u16 },
+    TrailingBytes { consumed: usize, expected: usize },
+    InvalidUtf8(Utf8Error),
+    At { offset: usize, source: Box<BinaryError> },
+}
+
+// Wrapped bincode, JSON and UTF-16 errors carry no comparable state, so they
+// compare by their message and by variant respectively.
+impl PartialEq for BinaryError {
+    fn eq(&self, other: &BinaryError) -> bool {
+        match (self, other) {
+            (BinaryError::StreamError(a), BinaryError::StreamError(b)) => a == b,
+            #[cfg(feature = "std")]
+            (BinaryError::BinCodeErr(a), BinaryError::BinCodeErr(b)) => {
+                a.to_string() == b.to_string()
+            }
+            #[cfg(feature = "json")]
+            (BinaryError::JsonError(a), BinaryError::JsonError(b)) => a.to_string() == b.to_string(),
+            (BinaryError::Utf8Error(a), BinaryError::Utf8Error(b)) => a == b,
+            (BinaryError::Utf16Error(_), BinaryError::Utf16Error(_)) => true,
+            (BinaryError::ParseIntError(a), BinaryError::ParseIntError(b)) => a == b,
+            (BinaryError::InvalidData(a), BinaryError::InvalidData(b)) => a == b,
+            (BinaryError::RecursionLimitExceeded, BinaryError::RecursionLimitExceeded) => true,
+            (
+                BinaryError::CountMismatch { expected, found },
+                BinaryError::CountMismatch {
+                    expected: other_expected,
+                    found: other_found,
+                },
+            ) => expected == other_expected && found == other_found,
+            (BinaryError::InvalidBool(a), BinaryError::InvalidBool(b)) => a == b,
+            (BinaryError::InvalidChar(a), BinaryError::InvalidChar(b)) => a == b,
+            (
+                BinaryError::UnexpectedEof { expected, got },
+                BinaryError::UnexpectedEof {
+                    expected: other_expected,
+                    got: other_got,
+                },
+            ) => expected == other_expected && got == other_got,
+            (
+                BinaryError::AllocationTooLarge { requested, limit },
+                BinaryError::AllocationTooLarge {
+                    requested: other_requested,
+                    limit: other_limit,
+                },
+            ) => requested == other_requested && limit == other_limit,
+            (BinaryError::VarintTooLong, BinaryError::VarintTooLong) => true,
+            (BinaryError::InvalidAddressFamily(a), BinaryError::InvalidAddressFamily(b)) => a == b,
+            (
+                BinaryError::ShortWrite { expected, written },
+                BinaryError::ShortWrite {
+                    expected: other_expected,
+                    written: other_written,
+                },
+            ) => expected == other_expected && written == other_written,
+            (BinaryError::OffsetOutOfRange(a), BinaryError::OffsetOutOfRange(b)) => a == b,
+            (
+                BinaryError::ChecksumMismatch { expected, found },
+                BinaryError::ChecksumMismatch {
+                    expected: other_expected,
+                    found: other_found,
+                },
+            ) => expected == other_expected && found == other_found,
+            (
+                BinaryError::BadMagic { expected, found },
+                BinaryError::BadMagic {
+                    expected: other_expected,
+                    found: other_found,
+                },
+            ) => expected == other_expected && found == other_found,
+            (BinaryError::UnexpectedZero, BinaryError::UnexpectedZero) => true,
+            (BinaryError::InvalidDiscriminant(a), BinaryError::InvalidDiscriminant(b)) => a == b,
+            (BinaryError::UnknownTag(a), BinaryError::UnknownTag(b)) => a == b,
+            (
+                BinaryError::LengthOverflow { len, max },
+                BinaryError::LengthOverflow {
+                    len: other_len,
+                    max: other_max,
+                },
+            ) => len == other_len && max == other_max,
+            (
+                BinaryError::ByteBudgetExceeded { consumed, limit },
+                BinaryError::ByteBudgetExceeded {
+                    consumed: other_consumed,
+                    limit: other_limit,
+                },
+            ) => consumed == other_consumed && limit == other_limit,
+            (
+                BinaryError::UnsupportedVersion { version, min, max },
+                BinaryError::UnsupportedVersion {
+                    version: other_version,
+                    min: other_min,
+                    max: other_max,
+                },
+            ) => version == other_version && min == other_min && max == other_max,
+            (
+                BinaryError::TrailingBytes { consumed, expected },
+                BinaryError::TrailingBytes {
+                    consumed: other_consumed,
+                    expected: other_expected,
+                },
+            ) => consumed == other_consumed && expected == other_expected,
+            (BinaryError::InvalidUtf8(a), BinaryError::InvalidUtf8(b)) => a == b,
+            (
+                BinaryError::At { offset, source },
+                BinaryError::At {
+                    offset: other_offset,
+                    source: other_source,
+                },
+            ) => offset == other_offset && source == other_source,
+            _ => false,
+        }
+    }
 }
 
 impl From<FromUtf8Error> for BinaryError {
@@ -24,39 +260,275 @@ impl From<FromUtf8Error> for BinaryError {
     }
 }
 
+impl From<FromUtf16Error> for BinaryError {
+    fn from(error: FromUtf16Error) -> BinaryError {
+        BinaryError::Utf16Error(error)
+    }
+}
+
+impl From<ParseIntError> for BinaryError {
+    fn from(error: ParseIntError) -> BinaryError {
+        BinaryError::ParseIntError(error)
+    }
+}
+
+#[cfg(feature = "std")]
 impl From<Box<bincode::ErrorKind>> for BinaryError {
     fn from(error: Box<bincode::ErrorKind>) -> BinaryError {
         BinaryError::BinCodeErr(error)
     }
 }
 
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for BinaryError {
+    fn from(error: serde_json::Error) -> BinaryError {
+        BinaryError::JsonError(error)
+    }
+}
+
 impl From<StreamError> for BinaryError {
     fn from(error: StreamError) -> BinaryError {
         BinaryError::StreamError(error)
     }
 }
 
-impl std::fmt::Display for BinaryError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for BinaryError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         match *self {
             BinaryError::StreamError(..) => write!(f, "Encountered a stream error"),
+            #[cfg(feature = "std")]
             BinaryError::BinCodeErr(..)  => write!(f, "Encountered a bincode error with serialization/deserialization"),
+            #[cfg(feature = "json")]
+            BinaryError::JsonError(ref e) => write!(f, "Encountered a JSON error: {}", e),
             BinaryError::Utf8Error(..)   => write!(f, "Encountered a UTF-8 decoding error"),
+            BinaryError::Utf16Error(..)  => write!(f, "Encountered a UTF-16 decoding error"),
+            BinaryError::ParseIntError(..) => write!(f, "Encountered an error parsing an ASCII integer"),
+            BinaryError::InvalidData(ref msg) => write!(f, "Encountered invalid data: {}", msg),
+            BinaryError::RecursionLimitExceeded => write!(f, "Exceeded the maximum nesting depth"),
+            BinaryError::CountMismatch { expected, found } => {
+                write!(f, "Expected a count of {} but found {}", expected, found)
+            }
+            BinaryError::InvalidBool(value) => write!(f, "Encountered an invalid bool byte {}", value),
+            BinaryError::InvalidChar(value) => write!(f, "Encountered an invalid char {:#x}", value),
+            BinaryError::UnexpectedEof { expected, got } => {
+                write!(f, "Unexpected end of stream: expected {} bytes, got {}", expected, got)
+            }
+            BinaryError::AllocationTooLarge { requested, limit } => {
+                write!(f, "Refusing to allocate {} bytes, the limit is {}", requested, limit)
+            }
+            BinaryError::VarintTooLong => write!(f, "Encountered a varint longer than 10 bytes"),
+            BinaryError::InvalidAddressFamily(tag) => write!(f, "Encountered an invalid address family tag {}", tag),
+            BinaryError::ShortWrite { expected, written } => {
+                write!(f, "Stream stopped accepting data: expected to write {} bytes, wrote {}", expected, written)
+            }
+            BinaryError::OffsetOutOfRange(value) => write!(f, "Offset or length {} does not fit in a usize", value),
+            BinaryError::ChecksumMismatch { expected, found } => {
+                write!(f, "Checksum mismatch: expected {:#010x}, found {:#010x}", expected, found)
+            }
+            BinaryError::BadMagic { ref expected, ref found } => {
+                write!(f, "Bad magic: expected {:02x?}, found {:02x?}", expected, found)
+            }
+            BinaryError::UnexpectedZero => write!(f, "Encountered a zero where a nonzero value was required"),
+            BinaryError::InvalidDiscriminant(tag) => write!(f, "Encountered an invalid enum discriminant {}", tag),
+            BinaryError::UnknownTag(tag) => write!(f, "Encountered an unknown value type tag {:#04x}", tag),
+            BinaryError::LengthOverflow { len, max } => {
+                write!(f, "Length {} exceeds the length prefix maximum of {}", len, max)
+            }
+            BinaryError::ByteBudgetExceeded { consumed, limit } => write!(
+                f,
+                "Reading past {} bytes would exceed the byte budget of {}",
+                consumed, limit
+            ),
+            BinaryError::UnsupportedVersion { version, min, max } => write!(
+                f,
+                "Format version {} is outside the supported range {}..={}",
+                version, min, max
+            ),
+            BinaryError::TrailingBytes { consumed, expected } => write!(
+                f,
+                "Decoded value used {} of its {} bytes",
+                consumed, expected
+            ),
+            BinaryError::InvalidUtf8(..) => write!(f, "Encountered a UTF-8 decoding error"),
+            BinaryError::At { offset, ref source } => write!(f, "At offset {}: {}", offset, source),
         }
     }
 }
 
-impl std::error::Error for BinaryError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+impl core::error::Error for BinaryError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
         match *self {
             BinaryError::StreamError(ref e) => Some(e),
+            #[cfg(feature = "std")]
             BinaryError::BinCodeErr(ref e) => Some(e),
+            #[cfg(feature = "json")]
+            BinaryError::JsonError(ref e) => Some(e),
             BinaryError::Utf8Error(ref e) => Some(e),
+            BinaryError::Utf16Error(ref e) => Some(e),
+            BinaryError::ParseIntError(ref e) => Some(e),
+            BinaryError::InvalidData(..) => None,
+            BinaryError::RecursionLimitExceeded => None,
+            BinaryError::CountMismatch { .. } => None,
+            BinaryError::InvalidBool(..) => None,
+            BinaryError::InvalidChar(..) => None,
+            BinaryError::UnexpectedEof { .. } => None,
+            BinaryError::AllocationTooLarge { .. } => None,
+            BinaryError::VarintTooLong => None,
+            BinaryError::InvalidAddressFamily(..) => None,
+            BinaryError::ShortWrite { .. } => None,
+            BinaryError::OffsetOutOfRange(..) => None,
+            BinaryError::ChecksumMismatch { .. } => None,
+            BinaryError::BadMagic { .. } => None,
+            BinaryError::UnexpectedZero => None,
+            BinaryError::InvalidDiscriminant(..) => None,
+            BinaryError::UnknownTag(..) => None,
+            BinaryError::LengthOverflow { .. } => None,
+            BinaryError::ByteBudgetExceeded { .. } => None,
+            BinaryError::UnsupportedVersion { .. } => None,
+            BinaryError::TrailingBytes { .. } => None,
+            BinaryError::InvalidUtf8(ref e) => Some(e),
+            BinaryError::At { ref source, .. } => Some(source.as_ref()),
         }
     }
 }
 
-#[derive(Debug)]
+// The original error is kept as the `io::Error`'s inner error; offsets from
+// `At` wrappers are looked through when picking the kind.
+#[cfg(feature = "std")]
+impl From<BinaryError> for std::io::Error {
+    fn from(error: BinaryError) -> std::io::Error {
+        use std::io::ErrorKind;
+
+        let kind = match error.root() {
+            BinaryError::StreamError(..) => ErrorKind::Other,
+            BinaryError::UnexpectedEof { .. } => ErrorKind::UnexpectedEof,
+            BinaryError::ShortWrite { .. } => ErrorKind::WriteZero,
+            BinaryError::BinCodeErr(e) => match **e {
+                bincode::ErrorKind::Io(ref io) => io.kind(),
+                _ => ErrorKind::InvalidData,
+            },
+            _ => ErrorKind::InvalidData,
+        };
+
+        std::io::Error::new(kind, error)
+    }
+}
+
+// The predicates and accessors look through any `At` offset wrappers to the
+// error underneath.
+impl BinaryError {
+    /// The error with any `At` offset wrappers removed.
+    pub fn root(&self) -> &BinaryError {
+        match self {
+            BinaryError::At { source, .. } => source.root(),
+            other => other,
+        }
+    }
+
+    /// Whether the input ended before a value was complete.
+    pub fn is_eof(&self) -> bool {
+        matches!(self.root(), BinaryError::UnexpectedEof { .. })
+    }
+
+    /// Whether the input bytes did not form a valid value. Stream failures,
+    /// running out of input and exceeding configured limits are not decode
+    /// errors.
+    pub fn is_decode_error(&self) -> bool {
+        match self.root() {
+            #[cfg(feature = "std")]
+            BinaryError::BinCodeErr(..) => true,
+            #[cfg(feature = "json")]
+            BinaryError::JsonError(..) => true,
+            BinaryError::Utf8Error(..)
+            | BinaryError::Utf16Error(..)
+            | BinaryError::ParseIntError(..)
+            | BinaryError::InvalidData(..)
+            | BinaryError::CountMismatch { .. }
+            | BinaryError::InvalidBool(..)
+            | BinaryError::InvalidChar(..)
+            | BinaryError::VarintTooLong
+            | BinaryError::InvalidAddressFamily(..)
+            | BinaryError::OffsetOutOfRange(..)
+            | BinaryError::ChecksumMismatch { .. }
+            | BinaryError::BadMagic { .. }
+            | BinaryError::UnexpectedZero
+            | BinaryError::InvalidDiscriminant(..)
+            | BinaryError::UnknownTag(..)
+            | BinaryError::UnsupportedVersion { .. }
+            | BinaryError::TrailingBytes { .. }
+            | BinaryError::InvalidUtf8(..) => true,
+            BinaryError::StreamError(..)
+            | BinaryError::RecursionLimitExceeded
+            | BinaryError::UnexpectedEof { .. }
+            | BinaryError::AllocationTooLarge { .. }
+            | BinaryError::ShortWrite { .. }
+            | BinaryError::LengthOverflow { .. }
+            | BinaryError::ByteBudgetExceeded { .. }
+            | BinaryError::At { .. } => false,
+        }
+    }
+
+    /// Whether the underlying stream failed.
+    pub fn is_stream_error(&self) -> bool {
+        self.as_stream_error().is_some()
+    }
+
+    pub fn as_stream_error(&self) -> Option<&StreamError> {
+        match self.root() {
+            BinaryError::StreamError(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn as_bincode_error(&self) -> Option<&bincode::ErrorKind> {
+        match self.root() {
+            BinaryError::BinCodeErr(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    pub fn as_utf8_error(&self) -> Option<&FromUtf8Error> {
+        match self.root() {
+            BinaryError::Utf8Error(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// The stream offset recorded by the outermost `At` wrapper, if any.
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            BinaryError::At { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+}
+
+fn pow10(scale: u8) -> f64 {
+    (0..scale).fold(1.0, |acc, _| acc * 10.0)
+}
+
+const MAX_RETAINED_SCRATCH: usize = 64 * 1024;
+
+const CHECKED_BLOCK_MAGIC: [u8; 4] = *b"BRWC";
+
+// Leading byte of a stored path, naming the platform encoding that follows:
+// raw `OsStr` bytes on Unix, UTF-16 code units on Windows.
+#[cfg(all(feature = "std", unix))]
+const PATH_TAG_UNIX: u8 = 0;
+#[cfg(all(feature = "std", windows))]
+const PATH_TAG_WINDOWS: u8 = 1;
+
+// Offsets and lengths are always stored as 64 bits, which can be out of
+// range for the platform usize on 32-bit targets.
+pub(crate) fn offset_to_usize(value: u64) -> Result<usize, BinaryError> {
+    match usize::try_from(value) {
+        Ok(value) => Ok(value),
+        Err(_) => Err(BinaryError::OffsetOutOfRange(value)),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StreamError {
     OpenError,
     WriteError,
@@ -65,8 +537,8 @@ pub enum StreamError {
     TellError,
 }
 
-impl std::fmt::Display for StreamError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl core::fmt::Display for StreamError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         match *self {
             StreamError::OpenError  => write!(f, "Encountered a stream error trying to open"),
             StreamError::WriteError => write!(f, "Encountered a stream error trying to write"),
@@ -77,397 +549,3547 @@ impl std::fmt::Display for StreamError {
     }
 }
 
-impl std::error::Error for StreamError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+impl core::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
         None
     }
 }
 
-pub trait Stream {
-    fn write(&mut self, bytes: &Vec<u8>) -> Result<usize, StreamError>;
-    fn read(&mut self, buffer: &mut Vec<u8>) -> Result<usize, StreamError>;
-    fn seek(&mut self, to: usize) -> Result<usize, StreamError>;
-    fn tell(&mut self) -> Result<usize, StreamError>;
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Endian {
+    #[default]
+    Little,
+    Big,
+    Native,
 }
 
-impl<'a> BinaryReader<'a> {
-    pub fn new(stream: &'a mut impl Stream) -> BinaryReader {
-        BinaryReader { stream }
-    }
-
-    pub fn seek_to(&mut self, to: usize) -> Result<usize, BinaryError> {
-        let result = self.stream.seek(to);
+impl Endian {
+    fn apply(self, bytes: &mut [u8]) {
+        let big = match self {
+            Endian::Little => false,
+            Endian::Big => true,
+            Endian::Native => cfg!(target_endian = "big"),
+        };
 
-        match result {
-            Ok(r) => Ok(r),
-            Err(e) => Err(BinaryError::StreamError(e)),
+        if big {
+            bytes.reverse();
         }
     }
+}
 
-    pub fn get_cur_pos(&mut self) -> Result<usize, BinaryError> {
-        let result = self.stream.tell();
+/// The encoding of the length written before strings, byte vectors and
+/// sequences. Fixed-width prefixes use the same byte order as the reader or
+/// writer they belong to, so a big-endian file has big-endian lengths.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LengthPrefix {
+    U8,
+    U16,
+    U32,
+    #[default]
+    U64,
+    Varint,
+}
 
-        match result {
-            Ok(r) => Ok(r),
-            Err(e) => Err(BinaryError::StreamError(e)),
+/// How the serde-based methods such as `read_value` and `write_vec` encode
+/// values. `Bincode` uses bincode's own layout; `Fixed` uses the stable,
+/// documented layout described in `fixedcodec`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Codec {
+    #[default]
+    Bincode,
+    Fixed,
+}
+
+/// Settings shared by `BinaryReader` and `BinaryWriter`. Readers ignore
+/// `pad_byte` and writers ignore `max_alloc` and `max_depth`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BinaryConfig {
+    pub endian: Endian,
+    pub length_prefix: LengthPrefix,
+    pub max_alloc: usize,
+    pub max_depth: usize,
+    pub pad_byte: u8,
+    pub codec: Codec,
+}
+
+impl Default for BinaryConfig {
+    fn default() -> BinaryConfig {
+        BinaryConfig {
+            endian: Endian::default(),
+            length_prefix: LengthPrefix::default(),
+            max_alloc: 64 * 1024 * 1024,
+            max_depth: 128,
+            pad_byte: 0,
+            codec: Codec::default(),
         }
     }
+}
 
-    pub fn read_string(&mut self) -> Result<String, BinaryError> {
-        let str_len = self.read_usize()?;
+impl BinaryConfig {
+    pub fn builder() -> BinaryConfigBuilder {
+        BinaryConfigBuilder::default()
+    }
+}
 
-        let mut chars: Vec<u8> = vec![0; str_len];
-        self.stream.read(&mut chars)?;
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BinaryConfigBuilder {
+    config: BinaryConfig,
+}
 
-        let string = String::from_utf8(chars)?;
-        Ok(string)
+impl BinaryConfigBuilder {
+    pub fn endian(mut self, endian: Endian) -> BinaryConfigBuilder {
+        self.config.endian = endian;
+        self
     }
 
-    pub fn read_f32(&mut self) -> Result<f32, BinaryError> {
-        let mut buffer: Vec<u8> = vec![0; 4];
+    pub fn length_prefix(mut self, length_prefix: LengthPrefix) -> BinaryConfigBuilder {
+        self.config.length_prefix = length_prefix;
+        self
+    }
 
-        self.stream.read(&mut buffer)?;
+    pub fn max_alloc(mut self, max_alloc: usize) -> BinaryConfigBuilder {
+        self.config.max_alloc = max_alloc;
+        self
+    }
 
-        let value = deserialize(&buffer);
+    pub fn max_depth(mut self, max_depth: usize) -> BinaryConfigBuilder {
+        self.config.max_depth = max_depth;
+        self
+    }
 
-        match value {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::BinCodeErr(e)),
-        }
+    pub fn pad_byte(mut self, pad_byte: u8) -> BinaryConfigBuilder {
+        self.config.pad_byte = pad_byte;
+        self
     }
 
-    pub fn read_f64(&mut self) -> Result<f64, BinaryError> {
-        let mut buffer: Vec<u8> = vec![0; 8];
+    pub fn codec(mut self, codec: Codec) -> BinaryConfigBuilder {
+        self.config.codec = codec;
+        self
+    }
 
-        self.stream.read(&mut buffer)?;
+    pub fn build(self) -> BinaryConfig {
+        self.config
+    }
+}
 
-        let value = deserialize(&buffer);
+/// The sign of a big integer written by `write_bigint`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sign {
+    Minus,
+    NoSign,
+    Plus,
+}
 
-        match value {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::BinCodeErr(e)),
+impl Sign {
+    fn to_byte(self) -> u8 {
+        match self {
+            Sign::NoSign => 0,
+            Sign::Plus => 1,
+            Sign::Minus => 2,
         }
     }
+}
 
-    pub fn read_isize(&mut self) -> Result<isize, BinaryError> {
-        let mut buffer: Vec<u8> = vec![0; 8];
-
-        self.stream.read(&mut buffer)?;
-
-        let value = deserialize(&buffer);
-
-        match value {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::BinCodeErr(e)),
+#[cfg(feature = "bigint")]
+impl From<num_bigint::Sign> for Sign {
+    fn from(sign: num_bigint::Sign) -> Sign {
+        match sign {
+            num_bigint::Sign::Minus => Sign::Minus,
+            num_bigint::Sign::NoSign => Sign::NoSign,
+            num_bigint::Sign::Plus => Sign::Plus,
         }
     }
+}
 
-    pub fn read_usize(&mut self) -> Result<usize, BinaryError> {
-        let mut buffer: Vec<u8> = vec![0; 8];
-
-        self.stream.read(&mut buffer)?;
-
-        let value = deserialize(&buffer);
-
-        match value {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::BinCodeErr(e)),
+#[cfg(feature = "bigint")]
+impl From<Sign> for num_bigint::Sign {
+    fn from(sign: Sign) -> num_bigint::Sign {
+        match sign {
+            Sign::Minus => num_bigint::Sign::Minus,
+            Sign::NoSign => num_bigint::Sign::NoSign,
+            Sign::Plus => num_bigint::Sign::Plus,
         }
     }
+}
 
-    pub fn read_u64(&mut self) -> Result<u64, BinaryError> {
-        let mut buffer: Vec<u8> = vec![0; 8];
+/// The magic, format version and flags that start a file, written by
+/// `write_header` and checked by `read_header`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileHeader {
+    pub magic: [u8; 4],
+    pub version: u16,
+    pub flags: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekOrigin {
+    Start,
+    Current,
+    End,
+}
 
-        self.stream.read(&mut buffer)?;
+fn resolve_seek(
+    stream: &mut dyn Stream,
+    from: SeekOrigin,
+    offset: i64,
+) -> Result<usize, StreamError> {
+    let base = match from {
+        SeekOrigin::Start => 0,
+        SeekOrigin::Current => stream.tell()?,
+        SeekOrigin::End => stream.stream_len()?,
+    };
+
+    let target = (base as i64).checked_add(offset);
+    match target {
+        Some(t) if t >= 0 => stream.seek(t as usize),
+        _ => Err(StreamError::SeekError),
+    }
+}
 
-        let value = deserialize(&buffer);
+pub trait Stream {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError>;
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError>;
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError>;
+    fn tell(&mut self) -> Result<usize, StreamError>;
 
-        match value {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::BinCodeErr(e)),
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        let mut buffer = [0u8; 4096];
+        // Counted from the current position, or from the start for streams
+        // that can't tell where they are.
+        let mut position = self.tell().unwrap_or(0);
+
+        loop {
+            match self.read(&mut buffer)? {
+                0 => return Ok(position),
+                read => position += read,
+            }
         }
     }
 
-    pub fn read_i64(&mut self) -> Result<i64, BinaryError> {
-        let mut buffer: Vec<u8> = vec![0; 8];
-
-        self.stream.read(&mut buffer)?;
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        // Measuring by reading to the end is only worth it if the stream can
+        // seek back afterwards, so check that first rather than drain it.
+        let position = self.tell()?;
+        self.seek(position)?;
 
-        let value = deserialize(&buffer);
+        let len = self.seek_end()?;
+        self.seek(position)?;
 
-        match value {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::BinCodeErr(e)),
-        }
+        Ok(len)
     }
 
-    pub fn read_u32(&mut self) -> Result<u32, BinaryError> {
-        let mut buffer: Vec<u8> = vec![0; 4];
+    fn flush(&mut self) -> Result<(), StreamError> {
+        Ok(())
+    }
 
-        self.stream.read(&mut buffer)?;
+    fn truncate(&mut self, _len: usize) -> Result<(), StreamError> {
+        Err(StreamError::WriteError)
+    }
 
-        let value = deserialize(&buffer);
+    /// Flushes and releases the stream, reporting errors that dropping it
+    /// would swallow.
+    fn close(mut self) -> Result<(), StreamError>
+    where
+        Self: Sized,
+    {
+        self.flush()
+    }
 
-        match value {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::BinCodeErr(e)),
-        }
+    /// The stream's in-memory buffer, if it has one readers can borrow from.
+    fn as_byte_source(&mut self) -> Option<&mut dyn ByteSource> {
+        None
     }
+}
 
-    pub fn read_i32(&mut self) -> Result<i32, BinaryError> {
-        let mut buffer: Vec<u8> = vec![0; 4];
+/// A stream whose whole contents sit in one buffer, letting readers borrow
+/// values out of it instead of copying.
+pub trait ByteSource {
+    fn buffer(&self) -> &[u8];
+}
 
-        self.stream.read(&mut buffer)?;
+/// Compares two streams from the start, returning the offset of the first
+/// differing byte, or `None` if their contents are identical. When one stream
+/// is a prefix of the other the offset is the shorter stream's length.
+pub fn diff(a: &mut dyn Stream, b: &mut dyn Stream) -> Result<Option<usize>, StreamError> {
+    a.seek(0)?;
+    b.seek(0)?;
 
-        let value = deserialize(&buffer);
+    let mut chunk_a = [0u8; 4096];
+    let mut chunk_b = [0u8; 4096];
+    let mut offset = 0;
 
-        match value {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::BinCodeErr(e)),
+    loop {
+        let read_a = fill(a, &mut chunk_a)?;
+        let read_b = fill(b, &mut chunk_b)?;
+        let common = read_a.min(read_b);
+
+        if let Some(i) = (0..common).find(|&i| chunk_a[i] != chunk_b[i]) {
+            return Ok(Some(offset + i));
+        }
+        if read_a != read_b {
+            return Ok(Some(offset + common));
         }
+        if read_a < chunk_a.len() {
+            return Ok(None);
+        }
+
+        offset += common;
     }
+}
 
-    pub fn read_u16(&mut self) -> Result<u16, BinaryError> {
-        let mut buffer: Vec<u8> = vec![0; 2];
+// Reads until `buffer` is full or the stream runs dry, so short reads from
+// one side don't misalign the comparison.
+fn fill(stream: &mut dyn Stream, buffer: &mut [u8]) -> Result<usize, StreamError> {
+    let mut filled = 0;
 
-        self.stream.read(&mut buffer)?;
+    while filled < buffer.len() {
+        match stream.read(&mut buffer[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
 
-        let value = deserialize(&buffer);
+    Ok(filled)
+}
 
-        match value {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::BinCodeErr(e)),
+/// Moves `n` bytes from `reader` to `writer` through a fixed-size buffer,
+/// without decoding them, and returns the number of bytes copied. Fails with
+/// `UnexpectedEof` if the reader runs out first; whatever was read before
+/// that has already been written.
+pub fn copy<R: ByteOrder, W: ByteOrder>(
+    reader: &mut BinaryReader<'_, R>,
+    writer: &mut BinaryWriter<'_, W>,
+    n: usize,
+) -> Result<usize, BinaryError> {
+    let mut buffer = [0u8; 4096];
+    let mut copied = 0;
+
+    while copied < n {
+        let chunk = (n - copied).min(buffer.len());
+        let read = reader.read_some(&mut buffer[..chunk])?;
+        if read == 0 {
+            return Err(BinaryError::UnexpectedEof { expected: n, got: copied });
         }
-    }
 
-    pub fn read_i16(&mut self) -> Result<i16, BinaryError> {
-        let mut buffer: Vec<u8> = vec![0; 2];
+        writer.write_all(&buffer[..read])?;
+        copied += read;
+    }
 
-        self.stream.read(&mut buffer)?;
+    Ok(copied)
+}
 
-        let value = deserialize(&buffer);
+pub trait BinaryReadable: Sized {
+    fn read_from(reader: &mut BinaryReader) -> Result<Self, BinaryError>;
+}
 
-        match value {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::BinCodeErr(e)),
-        }
-    }
+pub trait BinaryWritable {
+    fn write_to(&self, writer: &mut BinaryWriter) -> Result<usize, BinaryError>;
+}
 
-    pub fn read_u8(&mut self) -> Result<u8, BinaryError> {
-        let mut buffer: Vec<u8> = vec![0; 1];
+macro_rules! impl_binary_readwrite {
+    ($($ty:ty => $read:ident, $write:ident;)*) => {
+        $(
+            impl BinaryReadable for $ty {
+                fn read_from(reader: &mut BinaryReader) -> Result<Self, BinaryError> {
+                    reader.$read()
+                }
+            }
+
+            impl BinaryWritable for $ty {
+                fn write_to(&self, writer: &mut BinaryWriter) -> Result<usize, BinaryError> {
+                    writer.$write(*self)
+                }
+            }
+        )*
+    };
+}
 
-        self.stream.read(&mut buffer)?;
+impl_binary_readwrite! {
+    u8 => read_u8, write_u8;
+    i8 => read_i8, write_i8;
+    u16 => read_u16, write_u16;
+    i16 => read_i16, write_i16;
+    u32 => read_u32, write_u32;
+    i32 => read_i32, write_i32;
+    u64 => read_u64, write_u64;
+    i64 => read_i64, write_i64;
+    u128 => read_u128, write_u128;
+    i128 => read_i128, write_i128;
+    usize => read_usize, write_usize;
+    isize => read_isize, write_isize;
+    f32 => read_f32, write_f32;
+    f64 => read_f64, write_f64;
+    bool => read_bool, write_bool;
+    char => read_char, write_char;
+}
 
-        let value = deserialize(&buffer);
+impl BinaryReadable for String {
+    fn read_from(reader: &mut BinaryReader) -> Result<Self, BinaryError> {
+        reader.read_string()
+    }
+}
 
-        match value {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::BinCodeErr(e)),
-        }
+impl BinaryWritable for String {
+    fn write_to(&self, writer: &mut BinaryWriter) -> Result<usize, BinaryError> {
+        Ok(writer.write_length(self.len())? + writer.write_bytes(self.as_bytes())?)
     }
+}
 
-    pub fn read_i8(&mut self) -> Result<i8, BinaryError> {
-        let mut buffer: Vec<u8> = vec![0; 1];
+impl<T: BinaryReadable> BinaryReadable for Vec<T> {
+    fn read_from(reader: &mut BinaryReader) -> Result<Self, BinaryError> {
+        let count = reader.read_length()?;
+        reader.check_alloc(count.saturating_mul(core::mem::size_of::<T>().max(1)))?;
 
-        self.stream.read(&mut buffer)?;
+        let mut items = Vec::with_capacity(count);
+        for _ in 0..count {
+            items.push(T::read_from(reader)?);
+        }
 
-        let value = deserialize(&buffer);
+        Ok(items)
+    }
+}
 
-        match value {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::BinCodeErr(e)),
+impl<T: BinaryWritable> BinaryWritable for Vec<T> {
+    fn write_to(&self, writer: &mut BinaryWriter) -> Result<usize, BinaryError> {
+        let mut written = writer.write_length(self.len())?;
+        for item in self {
+            written += item.write_to(writer)?;
         }
+
+        Ok(written)
     }
+}
 
-    pub fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, BinaryError> {
-        let mut buffer: Vec<u8> = vec![0; length];
-        let bytes = self.stream.read(&mut buffer);
+impl<T: BinaryReadable> BinaryReadable for Option<T> {
+    fn read_from(reader: &mut BinaryReader) -> Result<Self, BinaryError> {
+        if reader.read_bool()? {
+            Ok(Some(T::read_from(reader)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
 
-        match bytes {
-            Ok(_) => Ok(buffer),
-            Err(e) => Err(BinaryError::StreamError(e)),
+impl<T: BinaryWritable> BinaryWritable for Option<T> {
+    fn write_to(&self, writer: &mut BinaryWriter) -> Result<usize, BinaryError> {
+        match self {
+            Some(v) => Ok(writer.write_bool(true)? + v.write_to(writer)?),
+            None => writer.write_bool(false),
         }
     }
 }
 
-pub struct BinaryWriter<'a> {
-    stream: &'a mut dyn Stream,
+macro_rules! impl_binary_tuple {
+    ($(($($name:ident),+))*) => {
+        $(
+            impl<$($name: BinaryReadable),+> BinaryReadable for ($($name,)+) {
+                fn read_from(reader: &mut BinaryReader) -> Result<Self, BinaryError> {
+                    Ok(($($name::read_from(reader)?,)+))
+                }
+            }
+
+            impl<$($name: BinaryWritable),+> BinaryWritable for ($($name,)+) {
+                #[allow(non_snake_case)]
+                fn write_to(&self, writer: &mut BinaryWriter) -> Result<usize, BinaryError> {
+                    let ($($name,)+) = self;
+                    let mut written = 0;
+                    $(written += $name.write_to(writer)?;)+
+
+                    Ok(written)
+                }
+            }
+        )*
+    };
 }
 
-impl<'a> BinaryWriter<'a> {
-    pub fn new(stream: &'a mut impl Stream) -> BinaryWriter {
-        BinaryWriter { stream }
-    }
+// Tuple elements are written back-to-back in order, with no count prefix.
+impl_binary_tuple! {
+    (A)
+    (A, B)
+    (A, B, C)
+    (A, B, C, D)
+    (A, B, C, D, E)
+    (A, B, C, D, E, F)
+}
 
-    pub fn seek_to(&mut self, to: usize) -> Result<usize, BinaryError> {
-        let result = self.stream.seek(to);
+macro_rules! read_slices {
+    ($($name:ident => $ty:ty;)*) => {
+        $(
+            pub fn $name(&mut self, count: usize) -> Result<Vec<$ty>, BinaryError> {
+                const SIZE: usize = core::mem::size_of::<$ty>();
+
+                let len = count.checked_mul(SIZE).ok_or(BinaryError::AllocationTooLarge {
+                    requested: usize::MAX,
+                    limit: self.max_alloc,
+                })?;
+                self.with_scratch(len, |reader, data| {
+                    let values = data
+                        .chunks_exact(SIZE)
+                        .map(|chunk| {
+                            let mut raw = [0u8; SIZE];
+                            raw.copy_from_slice(chunk);
+                            B::apply(reader.endian, &mut raw);
+                            <$ty>::from_le_bytes(raw)
+                        })
+                        .collect();
+
+                    Ok(values)
+                })
+            }
+        )*
+    };
+}
 
-        match result {
-            Ok(r) => Ok(r),
-            Err(e) => Err(BinaryError::StreamError(e)),
-        }
+macro_rules! write_slices {
+    ($($name:ident => $ty:ty;)*) => {
+        $(
+            pub fn $name(&mut self, values: &[$ty]) -> Result<usize, BinaryError> {
+                let mut data = Vec::with_capacity(values.len() * core::mem::size_of::<$ty>());
+
+                for value in values {
+                    let mut raw = value.to_le_bytes();
+                    B::apply(self.endian, &mut raw);
+                    data.extend_from_slice(&raw);
+                }
+
+                self.write_bytes(&data)
+            }
+        )*
+    };
+}
+
+impl<'a> BinaryReader<'a> {
+    pub fn new(stream: &'a mut impl Stream) -> BinaryReader<'a> {
+        BinaryReader::with_config(stream, BinaryConfig::default())
     }
 
-    pub fn get_cur_pos(&mut self) -> Result<usize, BinaryError> {
-        let result = self.stream.tell();
+    pub fn with_endian(stream: &'a mut impl Stream, endian: Endian) -> BinaryReader<'a> {
+        BinaryReader::with_config(stream, BinaryConfig { endian, ..BinaryConfig::default() })
+    }
 
-        match result {
-            Ok(pos) => Ok(pos),
-            Err(e) => Err(BinaryError::StreamError(e)),
-        }
+    pub fn with_config(stream: &'a mut impl Stream, config: BinaryConfig) -> BinaryReader<'a> {
+        BinaryReader::from_parts(stream, config)
     }
 
-    pub fn write_string(&mut self, value: String) -> Result<usize, BinaryError> {
-        let bytes = value.as_bytes();
+    /// A reader over `bytes` that owns its stream, e.g.
+    /// `BinaryReader::from_bytes(&data).reader().read_u32()`.
+    pub fn from_bytes(bytes: &[u8]) -> OwnedBinaryReader<'_> {
+        OwnedBinaryReader::new(bytes)
+    }
 
-        self.write_usize(bytes.len())?;
+    pub fn set_endian(&mut self, endian: Endian) {
+        self.endian = endian;
+    }
 
-        let result = self.stream.write(&bytes.to_vec());
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
 
-        match result {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::StreamError(e)),
+    pub fn scoped(&mut self, len: usize) -> Result<ScopedReader<'_>, BinaryError> {
+        let start = self.get_cur_pos()?;
+        if start.checked_add(len).is_none() {
+            return Err(BinaryError::StreamError(StreamError::SeekError));
         }
-    }
+        self.charge(len)?;
 
-    pub fn write_f32(&mut self, value: f32) -> Result<usize, BinaryError> {
-        let data = serialize(&value)?;
+        Ok(ScopedReader::new(self, start, len))
+    }
 
-        let result = self.stream.write(&data);
+    /// Reads a variant written by `write_variant`, passing its tag and a
+    /// reader limited to its body to `f`. The stream is left at the end of
+    /// the body however much of it `f` read, so unknown tags can be skipped
+    /// by returning without reading.
+    pub fn read_variant<T, F>(&mut self, f: F) -> Result<T, BinaryError>
+    where
+        F: FnOnce(u32, &mut BinaryReader<'_>) -> Result<T, BinaryError>,
+    {
+        let tag = self.read_u32()?;
+        let len = self.read_u32()? as usize;
+
+        let mut scope = self.scoped(len)?;
+        f(tag, &mut scope.reader())
+    }
 
-        match result {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::StreamError(e)),
-        }
+    pub fn read_num<T: BinaryNum>(&mut self) -> Result<T, BinaryError> {
+        T::read_from(self)
     }
 
-    pub fn write_f64(&mut self, value: f64) -> Result<usize, BinaryError> {
-        let data = serialize(&value)?;
+    /// The same as `read_num`.
+    pub fn read_number<T: Primitive>(&mut self) -> Result<T, BinaryError> {
+        self.read_num()
+    }
+}
 
-        let result = self.stream.write(&data);
+impl<'a, B: ByteOrder> BinaryReader<'a, B> {
+    /// A reader whose byte order is fixed by `B`, e.g.
+    /// `BinaryReader::<BigEndian>::with_byte_order(&mut stream)`.
+    pub fn with_byte_order(stream: &'a mut impl Stream) -> BinaryReader<'a, B> {
+        BinaryReader::from_parts(stream, BinaryConfig::default())
+    }
 
-        match result {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::StreamError(e)),
+    fn from_parts(stream: &'a mut dyn Stream, config: BinaryConfig) -> BinaryReader<'a, B> {
+        BinaryReader {
+            stream,
+            depth: 0,
+            max_depth: config.max_depth,
+            endian: config.endian,
+            max_alloc: config.max_alloc,
+            byte_limit: usize::MAX,
+            consumed: 0,
+            length_prefix: config.length_prefix,
+            codec: config.codec,
+            scratch: Vec::new(),
+            order: PhantomData,
         }
     }
 
-    pub fn write_isize(&mut self, value: isize) -> Result<usize, BinaryError> {
-        let data = serialize(&value)?;
+    pub fn set_max_alloc(&mut self, max_alloc: usize) {
+        self.max_alloc = max_alloc;
+    }
 
-        let result = self.stream.write(&data);
+    pub fn max_alloc(&self) -> usize {
+        self.max_alloc
+    }
 
-        match result {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::StreamError(e)),
+    /// Caps the total number of bytes this reader may consume from now on,
+    /// however many separate reads they are spread over, as a backstop to
+    /// `max_alloc` against inputs made of many small values. Skipped bytes
+    /// and whole scopes count against the budget; reads that would go past
+    /// it fail with `ByteBudgetExceeded` without touching the stream.
+    pub fn limit(&mut self, max_total: usize) {
+        self.byte_limit = max_total;
+        self.consumed = 0;
+    }
+
+    // Counts `n` bytes against the byte budget, failing if they don't fit.
+    fn charge(&mut self, n: usize) -> Result<(), BinaryError> {
+        match self.consumed.checked_add(n) {
+            Some(total) if total <= self.byte_limit => {
+                self.consumed = total;
+                Ok(())
+            }
+            _ => Err(BinaryError::ByteBudgetExceeded {
+                consumed: self.consumed,
+                limit: self.byte_limit,
+            }),
         }
     }
 
-    pub fn write_usize(&mut self, value: usize) -> Result<usize, BinaryError> {
-        let data = serialize(&value)?;
+    // Reads from the stream after checking the whole of `buf` fits the byte
+    // budget, charging only what was actually read.
+    fn read_some(&mut self, buf: &mut [u8]) -> Result<usize, BinaryError> {
+        if buf.len() > self.byte_limit - self.consumed {
+            return Err(BinaryError::ByteBudgetExceeded {
+                consumed: self.consumed,
+                limit: self.byte_limit,
+            });
+        }
 
-        let result = self.stream.write(&data);
+        let read = self.stream.read(buf)?;
+        self.consumed += read;
 
-        match result {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::StreamError(e)),
+        Ok(read)
+    }
+
+    fn check_alloc(&self, requested: usize) -> Result<(), BinaryError> {
+        if requested > self.max_alloc {
+            return Err(BinaryError::AllocationTooLarge {
+                requested,
+                limit: self.max_alloc,
+            });
         }
+
+        Ok(())
     }
 
-    pub fn write_u64(&mut self, value: u64) -> Result<usize, BinaryError> {
-        let data = serialize(&value)?;
+    fn read_length(&mut self) -> Result<usize, BinaryError> {
+        let len = match self.length_prefix {
+            LengthPrefix::U8 => self.read_u8()? as u64,
+            LengthPrefix::U16 => self.read_u16()? as u64,
+            LengthPrefix::U32 => self.read_u32()? as u64,
+            LengthPrefix::U64 => self.read_u64()?,
+            LengthPrefix::Varint => self.read_varint_u64()?,
+        };
 
-        let result = self.stream.write(&data);
+        offset_to_usize(len)
+    }
 
-        match result {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::StreamError(e)),
+    // Wraps a decode failure with the offset where the offending bytes
+    // started, given how many bytes have been consumed since then.
+    fn error_at(&mut self, consumed: usize, error: BinaryError) -> BinaryError {
+        match self.stream.tell() {
+            Ok(position) => BinaryError::At {
+                offset: position.saturating_sub(consumed),
+                source: Box::new(error),
+            },
+            Err(_) => error,
         }
     }
 
-    pub fn write_i64(&mut self, value: i64) -> Result<usize, BinaryError> {
-        let data = serialize(&value)?;
-
-        let result = self.stream.write(&data);
-
-        match result {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::StreamError(e)),
+    fn decode_utf8(&mut self, chars: Vec<u8>, consumed: usize) -> Result<String, BinaryError> {
+        match String::from_utf8(chars) {
+            Ok(string) => Ok(string),
+            Err(e) => {
+                let bad = consumed.saturating_sub(e.utf8_error().valid_up_to());
+                Err(self.error_at(bad, e.into()))
+            }
         }
     }
 
-    pub fn write_u32(&mut self, value: u32) -> Result<usize, BinaryError> {
-        let data = serialize(&value)?;
+    pub fn set_length_prefix(&mut self, length_prefix: LengthPrefix) {
+        self.length_prefix = length_prefix;
+    }
 
-        let result = self.stream.write(&data);
+    pub fn length_prefix(&self) -> LengthPrefix {
+        self.length_prefix
+    }
 
-        match result {
-            Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::StreamError(e)),
+    pub fn set_codec(&mut self, codec: Codec) {
+        self.codec = codec;
+    }
+
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn enter_nesting(&mut self) -> Result<(), BinaryError> {
+        if self.depth >= self.max_depth {
+            return Err(BinaryError::RecursionLimitExceeded);
         }
+
+        self.depth += 1;
+        Ok(())
     }
 
-    pub fn write_i32(&mut self, value: i32) -> Result<usize, BinaryError> {
-        let data = serialize(&value)?;
+    pub fn exit_nesting(&mut self) {
+        self.depth = self.depth.saturating_sub(1);
+    }
 
-        let result = self.stream.write(&data);
+    pub fn seek_to(&mut self, to: usize) -> Result<usize, BinaryError> {
+        let result = self.stream.seek(to);
 
         match result {
-            Ok(v) => Ok(v),
+            Ok(r) => Ok(r),
             Err(e) => Err(BinaryError::StreamError(e)),
         }
     }
 
-    pub fn write_u16(&mut self, value: u16) -> Result<usize, BinaryError> {
-        let data = serialize(&value)?;
-
-        let result = self.stream.write(&data);
+    pub fn seek(&mut self, from: SeekOrigin, offset: i64) -> Result<usize, BinaryError> {
+        let result = resolve_seek(&mut *self.stream, from, offset);
 
         match result {
-            Ok(v) => Ok(v),
+            Ok(r) => Ok(r),
             Err(e) => Err(BinaryError::StreamError(e)),
         }
     }
 
-    pub fn write_i16(&mut self, value: i16) -> Result<usize, BinaryError> {
-        let data = serialize(&value)?;
-
-        let result = self.stream.write(&data);
+    pub fn seek_to_end(&mut self) -> Result<usize, BinaryError> {
+        let result = self.stream.seek_end();
 
         match result {
-            Ok(v) => Ok(v),
+            Ok(r) => Ok(r),
             Err(e) => Err(BinaryError::StreamError(e)),
         }
     }
 
-    pub fn write_u8(&mut self, value: u8) -> Result<usize, BinaryError> {
-        let data = serialize(&value)?;
-
-        let result = self.stream.write(&data);
+    pub fn get_cur_pos(&mut self) -> Result<usize, BinaryError> {
+        let result = self.stream.tell();
 
         match result {
-            Ok(v) => Ok(v),
+            Ok(r) => Ok(r),
             Err(e) => Err(BinaryError::StreamError(e)),
         }
     }
 
-    pub fn write_i8(&mut self, value: i8) -> Result<usize, BinaryError> {
-        let data = serialize(&value)?;
-
-        let result = self.stream.write(&data);
+    pub fn len(&mut self) -> Result<usize, BinaryError> {
+        let result = self.stream.stream_len();
 
         match result {
-            Ok(v) => Ok(v),
+            Ok(len) => Ok(len),
             Err(e) => Err(BinaryError::StreamError(e)),
         }
     }
 
-    pub fn write_bytes(&mut self, data: Vec<u8>) -> Result<usize, BinaryError> {
-        let result = self.stream.write(&data);
+    pub fn is_empty(&mut self) -> Result<bool, BinaryError> {
+        Ok(self.len()? == 0)
+    }
 
-        match result {
+    pub fn remaining(&mut self) -> Result<usize, BinaryError> {
+        let position = self.get_cur_pos()?;
+        let len = self.len()?;
+
+        Ok(len.saturating_sub(position))
+    }
+
+    /// Returns `(position, total_length)` for progress reporting, leaving the
+    /// cursor where it was. Streams that can't report a length, such as
+    /// sockets, fail with their stream error rather than guessing a total.
+    pub fn progress(&mut self) -> Result<(usize, usize), BinaryError> {
+        let position = self.get_cur_pos()?;
+        let len = self.len()?;
+
+        // Streams relying on the default `seek_end` measure by reading, so
+        // make sure the cursor ends up back where it started.
+        if self.get_cur_pos()? != position {
+            self.seek_to(position)?;
+        }
+
+        Ok((position, len))
+    }
+
+    pub fn skip(&mut self, n: usize) -> Result<usize, BinaryError> {
+        let position = self.get_cur_pos()?;
+
+        match position.checked_add(n) {
+            Some(target) => self.seek_to(target),
+            None => Err(BinaryError::StreamError(StreamError::SeekError)),
+        }
+    }
+
+    /// Moves past `n` bytes of data, failing with `UnexpectedEof` if fewer
+    /// remain. Seekable streams are seeked over; streams that can't report
+    /// their length are read through a small buffer and the bytes discarded.
+    pub fn skip_bytes(&mut self, n: usize) -> Result<(), BinaryError> {
+        if let Ok(remaining) = self.remaining() {
+            if n > remaining {
+                return Err(BinaryError::UnexpectedEof { expected: n, got: remaining });
+            }
+
+            self.charge(n)?;
+            return self.skip(n).map(|_| ());
+        }
+
+        let mut buffer = [0u8; 512];
+        let mut skipped = 0;
+        while skipped < n {
+            let chunk = (n - skipped).min(buffer.len());
+            match self.read_some(&mut buffer[..chunk])? {
+                0 => return Err(BinaryError::UnexpectedEof { expected: n, got: skipped }),
+                read => skipped += read,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Moves forward to the absolute offset `absolute_offset`, such as the
+    /// end of a record whose length was read from its header, skipping any
+    /// fields in between that this reader doesn't know about. Moving
+    /// backwards is an error.
+    pub fn skip_to(&mut self, absolute_offset: usize) -> Result<(), BinaryError> {
+        let position = self.get_cur_pos()?;
+        if absolute_offset < position {
+            return Err(BinaryError::InvalidData(format!(
+                "cannot skip backwards from offset {} to {}",
+                position, absolute_offset
+            )));
+        }
+
+        self.skip_bytes(absolute_offset - position)
+    }
+
+    /// Moves past whatever is left of the current scope and returns how many
+    /// bytes were skipped. On a reader from `ScopedReader::reader` this is
+    /// the unread tail of the region; on any other reader it is the rest of
+    /// the stream.
+    pub fn skip_remaining_in_scope(&mut self) -> Result<usize, BinaryError> {
+        let remaining = self.remaining()?;
+        self.skip_bytes(remaining)?;
+
+        Ok(remaining)
+    }
+
+    /// Moves past a length-prefixed string without reading it, for checking
+    /// that data is well formed. The bytes are not checked to be UTF-8.
+    pub fn skip_string(&mut self) -> Result<(), BinaryError> {
+        let len = self.read_length()?;
+        self.skip_bytes(len)
+    }
+
+    pub fn skip_byte_vec(&mut self) -> Result<(), BinaryError> {
+        let len = self.read_length()?;
+        self.skip_bytes(len)
+    }
+
+    /// Moves past a length-prefixed sequence of `element_size`-byte elements,
+    /// such as one written by `write_vec` with a fixed-size element type.
+    pub fn skip_vec(&mut self, element_size: usize) -> Result<(), BinaryError> {
+        let count = self.read_length()?;
+
+        match count.checked_mul(element_size) {
+            Some(len) => self.skip_bytes(len),
+            None => Err(self.error_at(
+                0,
+                BinaryError::InvalidData(format!("{} elements overflow the stream", count)),
+            )),
+        }
+    }
+
+    fn peek_with<T, F>(&mut self, f: F) -> Result<T, BinaryError>
+    where
+        F: FnOnce(&mut Self) -> Result<T, BinaryError>,
+    {
+        let position = self.get_cur_pos()?;
+        let consumed = self.consumed;
+        let result = f(self);
+        self.seek_to(position)?;
+        self.consumed = consumed;
+
+        result
+    }
+
+    /// Runs `f` and, if it fails, moves the cursor back to where it was
+    /// before returning the error, so another interpretation can be tried.
+    pub fn try_read<T, F>(&mut self, f: F) -> Result<T, BinaryError>
+    where
+        F: FnOnce(&mut Self) -> Result<T, BinaryError>,
+    {
+        let position = self.get_cur_pos()?;
+        let consumed = self.consumed;
+
+        match f(self) {
+            Ok(value) => Ok(value),
+            Err(e) => {
+                // The read's own error is the useful one even if rewinding fails.
+                let _ = self.seek_to(position);
+                self.consumed = consumed;
+                Err(e)
+            }
+        }
+    }
+
+    pub fn align_to(&mut self, alignment: usize) -> Result<usize, BinaryError> {
+        if !alignment.is_power_of_two() {
+            return Err(BinaryError::InvalidData(format!(
+                "alignment {} is not a power of two",
+                alignment
+            )));
+        }
+
+        let position = self.get_cur_pos()?;
+        match position.checked_next_multiple_of(alignment) {
+            Some(target) if target == position => Ok(position),
+            Some(target) => self.seek_to(target),
+            None => Err(BinaryError::StreamError(StreamError::SeekError)),
+        }
+    }
+
+    pub fn position_guard(&mut self) -> Result<PositionGuard<'_, Self>, BinaryError> {
+        let position = self.get_cur_pos()?;
+
+        Ok(PositionGuard::new(self, position, |reader, to| {
+            let _ = reader.seek_to(to);
+        }))
+    }
+
+    pub fn peek_u8(&mut self) -> Result<u8, BinaryError> {
+        self.peek_with(|r| r.read_u8())
+    }
+
+    pub fn peek_u32(&mut self) -> Result<u32, BinaryError> {
+        self.peek_with(|r| r.read_u32())
+    }
+
+    pub fn peek_bytes(&mut self, n: usize) -> Result<Vec<u8>, BinaryError> {
+        self.peek_with(|r| r.read_bytes(n))
+    }
+
+    /// Formats the next `length` bytes like `xxd`: an offset column, sixteen
+    /// bytes per line in two-byte hex groups, then the printable ASCII. The
+    /// cursor is left where it was.
+    pub fn hexdump(&mut self, length: usize) -> Result<String, BinaryError> {
+        let start = self.get_cur_pos()?;
+        let bytes = self.peek_bytes(length)?;
+
+        let mut dump = String::new();
+        for (line, chunk) in bytes.chunks(16).enumerate() {
+            let mut hex = String::with_capacity(39);
+            for (i, byte) in chunk.iter().enumerate() {
+                if i > 0 && i % 2 == 0 {
+                    hex.push(' ');
+                }
+                let _ = write!(hex, "{:02x}", byte);
+            }
+
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+                .collect();
+
+            let _ = writeln!(dump, "{:08x}: {:<39}  {}", start + line * 16, hex, ascii);
+        }
+
+        Ok(dump)
+    }
+
+    pub fn expect_magic(&mut self, expected: &[u8]) -> Result<(), BinaryError> {
+        let found = self.read_bytes(expected.len())?;
+
+        if found != expected {
+            return Err(self.error_at(
+                found.len(),
+                BinaryError::BadMagic {
+                    expected: expected.to_vec(),
+                    found,
+                },
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Reads a `FileHeader`, failing with `BadMagic` if it doesn't start with
+    /// `expected_magic` and with `UnsupportedVersion` if its version is
+    /// outside `min_version..=max_version`.
+    pub fn read_header(
+        &mut self,
+        expected_magic: &[u8; 4],
+        min_version: u16,
+        max_version: u16,
+    ) -> Result<FileHeader, BinaryError> {
+        self.expect_magic(expected_magic)?;
+
+        let version = self.read_u16()?;
+        if version < min_version || version > max_version {
+            return Err(self.error_at(
+                2,
+                BinaryError::UnsupportedVersion {
+                    version,
+                    min: min_version,
+                    max: max_version,
+                },
+            ));
+        }
+
+        Ok(FileHeader {
+            magic: *expected_magic,
+            version,
+            flags: self.read_u16()?,
+        })
+    }
+
+    /// Reads exactly `n` bytes, calling the stream as many times as it takes,
+    /// or fails with `UnexpectedEof` saying how many arrived. The typed read
+    /// methods are built on this guarantee.
+    pub fn read_exact(&mut self, n: usize) -> Result<Vec<u8>, BinaryError> {
+        self.read_bytes(n)
+    }
+
+    /// Fills `buf` completely, as `read_exact` does, without allocating.
+    pub fn read_exact_into(&mut self, buf: &mut [u8]) -> Result<(), BinaryError> {
+        let expected = buf.len();
+        let mut got = 0;
+
+        while got < expected {
+            let read = self.read_some(&mut buf[got..])?;
+
+            if read == 0 {
+                return Err(BinaryError::UnexpectedEof { expected, got });
+            }
+
+            got += read;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_string(&mut self) -> Result<String, BinaryError> {
+        let str_len = self.read_length()?;
+        self.read_string_with_len(str_len)
+    }
+
+    pub fn read_string_with_len(&mut self, len: usize) -> Result<String, BinaryError> {
+        self.check_alloc(len)?;
+
+        let mut chars: Vec<u8> = vec![0; len];
+        self.read_exact_into(&mut chars)?;
+
+        self.decode_utf8(chars, len)
+    }
+
+    /// Reads a length-prefixed string borrowed straight out of the stream's
+    /// buffer. Only works on streams that implement `ByteSource`.
+    pub fn read_str_borrowed(&mut self) -> Result<&str, BinaryError> {
+        let len = self.read_length()?;
+        let start = self.get_cur_pos()?;
+
+        let available = self.byte_source()?.buffer().len().saturating_sub(start);
+        if available < len {
+            return Err(BinaryError::UnexpectedEof {
+                expected: len,
+                got: available,
+            });
+        }
+
+        let end = start + len;
+        self.charge(len)?;
+        self.seek_to(end)?;
+
+        let bytes = &self.byte_source()?.buffer()[start..end];
+        match core::str::from_utf8(bytes) {
+            Ok(string) => Ok(string),
+            // The string is still borrowed here, so `error_at` can't be used;
+            // the offset is the one it would give with the cursor at `end`.
+            Err(e) => Err(BinaryError::At {
+                offset: start + e.valid_up_to(),
+                source: Box::new(BinaryError::InvalidUtf8(e)),
+            }),
+        }
+    }
+
+    fn byte_source(&mut self) -> Result<&mut dyn ByteSource, BinaryError> {
+        match self.stream.as_byte_source() {
+            Some(source) => Ok(source),
+            None => Err(BinaryError::InvalidData(
+                "stream does not expose a buffer to borrow from".to_string(),
+            )),
+        }
+    }
+
+    pub fn read_string_utf16(&mut self) -> Result<String, BinaryError> {
+        let unit_len = self.read_u16()? as usize;
+        self.check_alloc(unit_len * 2)?;
+
+        let mut units = Vec::with_capacity(unit_len);
+        for _ in 0..unit_len {
+            units.push(self.read_u16()?);
+        }
+
+        match String::from_utf16(&units) {
+            Ok(string) => Ok(string),
+            Err(e) => Err(self.error_at(unit_len * 2, e.into())),
+        }
+    }
+
+    /// Reads `byte_len` bytes of text, decoding by its byte-order mark
+    /// (UTF-8, UTF-16LE or UTF-16BE) and dropping the mark. Text without a
+    /// mark is read as UTF-8.
+    pub fn read_string_bom(&mut self, byte_len: usize) -> Result<String, BinaryError> {
+        let bytes = self.read_bytes(byte_len)?;
+
+        let (body, little_endian) = match bytes.as_slice() {
+            [0xEF, 0xBB, 0xBF, ..] => return self.decode_utf8(bytes[3..].to_vec(), byte_len),
+            [0xFF, 0xFE, body @ ..] => (body, true),
+            [0xFE, 0xFF, body @ ..] => (body, false),
+            _ => return self.decode_utf8(bytes, byte_len),
+        };
+
+        if body.len() % 2 != 0 {
+            return Err(self.error_at(
+                byte_len,
+                BinaryError::InvalidData(format!(
+                    "UTF-16 text has an odd byte length of {}",
+                    body.len()
+                )),
+            ));
+        }
+
+        let units: Vec<u16> = body
+            .chunks_exact(2)
+            .map(|pair| {
+                if little_endian {
+                    u16::from_le_bytes([pair[0], pair[1]])
+                } else {
+                    u16::from_be_bytes([pair[0], pair[1]])
+                }
+            })
+            .collect();
+
+        match String::from_utf16(&units) {
+            Ok(string) => Ok(string),
+            Err(e) => Err(self.error_at(byte_len, e.into())),
+        }
+    }
+
+    pub fn read_cstring(&mut self) -> Result<String, BinaryError> {
+        let mut chars = Vec::new();
+
+        loop {
+            let byte = self.read_u8()?;
+            if byte == 0 {
+                break;
+            }
+
+            self.check_alloc(chars.len() + 1)?;
+            chars.push(byte);
+        }
+
+        let consumed = chars.len() + 1;
+        self.decode_utf8(chars, consumed)
+    }
+
+    pub fn read_until(&mut self, delimiter: u8) -> Result<Vec<u8>, BinaryError> {
+        let mut bytes = Vec::new();
+        let mut byte = [0u8; 1];
+
+        loop {
+            if self.read_some(&mut byte)? == 0 {
+                if bytes.is_empty() {
+                    return Err(BinaryError::UnexpectedEof {
+                        expected: 1,
+                        got: 0,
+                    });
+                }
+                break;
+            }
+
+            self.check_alloc(bytes.len() + 1)?;
+            bytes.push(byte[0]);
+
+            if byte[0] == delimiter {
+                break;
+            }
+        }
+
+        Ok(bytes)
+    }
+
+    pub fn read_fixed_string(&mut self, width: usize, trim: u8) -> Result<String, BinaryError> {
+        let mut chars = self.read_bytes(width)?;
+
+        let len = chars.iter().rposition(|&b| b != trim).map_or(0, |i| i + 1);
+        chars.truncate(len);
+
+        self.decode_utf8(chars, width)
+    }
+
+    /// Reads a u32 tag and maps it to a value with `f`, failing with
+    /// `InvalidDiscriminant` if `f` does not recognize it.
+    pub fn read_enum<T, F>(&mut self, f: F) -> Result<T, BinaryError>
+    where
+        F: FnOnce(u32) -> Option<T>,
+    {
+        let tag = self.read_u32()?;
+
+        match f(tag) {
+            Some(value) => Ok(value),
+            None => Err(self.error_at(4, BinaryError::InvalidDiscriminant(tag))),
+        }
+    }
+
+    /// Reads one value of the self-describing encoding written by the
+    /// `write_tagged_*` methods.
+    pub fn read_tagged(&mut self) -> Result<TaggedValue, BinaryError> {
+        let value = match self.read_u8()? {
+            tagged::BOOL => TaggedValue::Bool(self.read_bool()?),
+            tagged::U8 => TaggedValue::U8(self.read_u8()?),
+            tagged::U32 => TaggedValue::U32(self.read_u32()?),
+            tagged::U64 => TaggedValue::U64(self.read_u64()?),
+            tagged::I32 => TaggedValue::I32(self.read_i32()?),
+            tagged::I64 => TaggedValue::I64(self.read_i64()?),
+            tagged::F32 => TaggedValue::F32(self.read_f32()?),
+            tagged::F64 => TaggedValue::F64(self.read_f64()?),
+            tagged::STR => TaggedValue::Str(self.read_string()?),
+            tagged::BYTES => TaggedValue::Bytes(self.read_byte_vec()?),
+            tag => return Err(self.error_at(1, BinaryError::UnknownTag(tag))),
+        };
+
+        Ok(value)
+    }
+
+    pub fn read_enum_by_name<E: core::str::FromStr>(&mut self) -> Result<E, BinaryError> {
+        let name = self.read_string()?;
+
+        match name.parse() {
             Ok(v) => Ok(v),
-            Err(e) => Err(BinaryError::StreamError(e)),
+            Err(_) => Err(BinaryError::InvalidData(format!("unknown variant name {:?}", name))),
+        }
+    }
+
+    pub fn read_string_bounded(&mut self, max: usize) -> Result<(String, bool), BinaryError> {
+        let str_len = self.read_length()?;
+        let truncated = str_len > max;
+
+        let mut chars = self.read_bytes(str_len.min(max))?;
+
+        if truncated {
+            self.skip_bytes(str_len - max)?;
+
+            if let Err(e) = core::str::from_utf8(&chars) {
+                if e.error_len().is_none() {
+                    chars.truncate(e.valid_up_to());
+                }
+            }
+        }
+
+        let string = self.decode_utf8(chars, str_len)?;
+        Ok((string, truncated))
+    }
+
+    pub fn read_pascal_string(&mut self, encoding: StringEncoding) -> Result<String, BinaryError> {
+        let len = self.read_u8()? as usize;
+        encoding.decode(self.read_bytes(len)?)
+    }
+
+    pub fn read_long_pascal_string(
+        &mut self,
+        encoding: StringEncoding,
+    ) -> Result<String, BinaryError> {
+        let len = self.read_u16()? as usize;
+        encoding.decode(self.read_bytes(len)?)
+    }
+
+    #[cfg(feature = "half")]
+    pub fn read_f16(&mut self) -> Result<f32, BinaryError> {
+        let mut buffer = [0u8; 2];
+
+        self.read_exact_into(&mut buffer)?;
+        B::apply(self.endian, &mut buffer);
+
+        Ok(half::f16::from_le_bytes(buffer).to_f32())
+    }
+
+    // Floats travel as their raw IEEE-754 bits, so NaN payloads and
+    // signaling NaNs come back exactly as written.
+    pub fn read_f32(&mut self) -> Result<f32, BinaryError> {
+        Ok(f32::from_bits(self.read_u32()?))
+    }
+
+    pub fn read_f64(&mut self) -> Result<f64, BinaryError> {
+        Ok(f64::from_bits(self.read_u64()?))
+    }
+
+    pub fn read_isize(&mut self) -> Result<isize, BinaryError> {
+        let mut buffer = [0u8; 8];
+
+        self.read_exact_into(&mut buffer)?;
+        B::apply(self.endian, &mut buffer);
+
+        let value = i64::from_le_bytes(buffer);
+        match isize::try_from(value) {
+            Ok(value) => Ok(value),
+            Err(_) => Err(self.error_at(
+                8,
+                BinaryError::InvalidData(format!("{} does not fit in an isize", value)),
+            )),
+        }
+    }
+
+    pub fn read_usize(&mut self) -> Result<usize, BinaryError> {
+        let value = self.read_u64()?;
+        offset_to_usize(value)
+    }
+
+    pub fn read_u128(&mut self) -> Result<u128, BinaryError> {
+        let mut buffer = [0u8; 16];
+
+        self.read_exact_into(&mut buffer)?;
+        B::apply(self.endian, &mut buffer);
+
+        Ok(u128::from_le_bytes(buffer))
+    }
+
+    pub fn read_i128(&mut self) -> Result<i128, BinaryError> {
+        let mut buffer = [0u8; 16];
+
+        self.read_exact_into(&mut buffer)?;
+        B::apply(self.endian, &mut buffer);
+
+        Ok(i128::from_le_bytes(buffer))
+    }
+
+    /// Reads a big integer written by `write_bigint`, returning its sign and
+    /// big-endian magnitude without leading zeros.
+    pub fn read_bigint(&mut self) -> Result<(Sign, Vec<u8>), BinaryError> {
+        let sign = match self.read_u8()? {
+            0 => Sign::NoSign,
+            1 => Sign::Plus,
+            2 => Sign::Minus,
+            tag => return Err(self.error_at(1, BinaryError::InvalidDiscriminant(u32::from(tag)))),
+        };
+        let magnitude = self.read_byte_vec()?;
+
+        let canonical = magnitude.first() != Some(&0);
+        if !canonical || magnitude.is_empty() != (sign == Sign::NoSign) {
+            return Err(self.error_at(
+                magnitude.len(),
+                BinaryError::InvalidData(format!("malformed {:?} big integer magnitude", sign)),
+            ));
+        }
+
+        Ok((sign, magnitude))
+    }
+
+    #[cfg(feature = "bigint")]
+    pub fn read_num_bigint(&mut self) -> Result<num_bigint::BigInt, BinaryError> {
+        let (sign, magnitude) = self.read_bigint()?;
+        Ok(num_bigint::BigInt::from_bytes_be(sign.into(), &magnitude))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, BinaryError> {
+        let mut buffer = [0u8; 8];
+
+        self.read_exact_into(&mut buffer)?;
+        B::apply(self.endian, &mut buffer);
+
+        Ok(u64::from_le_bytes(buffer))
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64, BinaryError> {
+        let mut buffer = [0u8; 8];
+
+        self.read_exact_into(&mut buffer)?;
+        B::apply(self.endian, &mut buffer);
+
+        Ok(i64::from_le_bytes(buffer))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, BinaryError> {
+        let mut buffer = [0u8; 4];
+
+        self.read_exact_into(&mut buffer)?;
+        B::apply(self.endian, &mut buffer);
+
+        Ok(u32::from_le_bytes(buffer))
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, BinaryError> {
+        let mut buffer = [0u8; 4];
+
+        self.read_exact_into(&mut buffer)?;
+        B::apply(self.endian, &mut buffer);
+
+        Ok(i32::from_le_bytes(buffer))
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, BinaryError> {
+        let mut buffer = [0u8; 2];
+
+        self.read_exact_into(&mut buffer)?;
+        B::apply(self.endian, &mut buffer);
+
+        Ok(u16::from_le_bytes(buffer))
+    }
+
+    pub fn read_i16(&mut self) -> Result<i16, BinaryError> {
+        let mut buffer = [0u8; 2];
+
+        self.read_exact_into(&mut buffer)?;
+        B::apply(self.endian, &mut buffer);
+
+        Ok(i16::from_le_bytes(buffer))
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, BinaryError> {
+        let mut buffer = [0u8; 1];
+
+        self.read_exact_into(&mut buffer)?;
+        B::apply(self.endian, &mut buffer);
+
+        Ok(u8::from_le_bytes(buffer))
+    }
+
+    pub fn read_i8(&mut self) -> Result<i8, BinaryError> {
+        let mut buffer = [0u8; 1];
+
+        self.read_exact_into(&mut buffer)?;
+        B::apply(self.endian, &mut buffer);
+
+        Ok(i8::from_le_bytes(buffer))
+    }
+
+    pub fn read_varint_u64(&mut self) -> Result<u64, BinaryError> {
+        let mut value = 0u64;
+
+        for i in 0..10 {
+            let byte = self.read_u8()?;
+            // The tenth byte only has room for the top bit of a u64.
+            if i == 9 && byte > 1 {
+                return Err(BinaryError::VarintTooLong);
+            }
+
+            value |= u64::from(byte & 0x7F) << (7 * i);
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(BinaryError::VarintTooLong)
+    }
+
+    pub fn read_varint_i64(&mut self) -> Result<i64, BinaryError> {
+        let value = self.read_varint_u64()?;
+
+        Ok((value >> 1) as i64 ^ -((value & 1) as i64))
+    }
+
+    /// Reads a length written by `write_adaptive_len`.
+    pub fn read_adaptive_len(&mut self) -> Result<usize, BinaryError> {
+        let first = self.read_u8()?;
+        let high = u64::from(first & 0x3F);
+
+        let len = match first >> 6 {
+            0 => high,
+            1 => high << 8 | u64::from(self.read_u8()?),
+            2 => {
+                let mut rest = [0u8; 3];
+                self.read_exact_into(&mut rest)?;
+                high << 24 | u64::from(rest[0]) << 16 | u64::from(rest[1]) << 8 | u64::from(rest[2])
+            }
+            _ if high == 0 => {
+                let mut rest = [0u8; 8];
+                self.read_exact_into(&mut rest)?;
+                u64::from_be_bytes(rest)
+            }
+            _ => {
+                return Err(self.error_at(
+                    1,
+                    BinaryError::InvalidData(format!("invalid adaptive length prefix {:#04x}", first)),
+                ))
+            }
+        };
+
+        offset_to_usize(len)
+    }
+
+    pub fn read_string_adaptive(&mut self) -> Result<String, BinaryError> {
+        let len = self.read_adaptive_len()?;
+        self.read_string_with_len(len)
+    }
+
+    /// Reads a string written by `write_string_varint`.
+    pub fn read_string_varint(&mut self) -> Result<String, BinaryError> {
+        let len = offset_to_usize(self.read_varint_u64()?)?;
+        self.read_string_with_len(len)
+    }
+
+    pub fn read_delta_block(
+        &mut self,
+        base: i64,
+        delta_width: usize,
+        count: usize,
+    ) -> Result<Vec<i64>, BinaryError> {
+        if delta_width == 0 || delta_width > 8 {
+            return Err(BinaryError::InvalidData(format!(
+                "unsupported delta width {}",
+                delta_width
+            )));
+        }
+
+        let len = delta_width.checked_mul(count).ok_or(BinaryError::AllocationTooLarge {
+            requested: usize::MAX,
+            limit: self.max_alloc,
+        })?;
+        self.with_scratch(len, |reader, bytes| {
+            let mut values = Vec::with_capacity(count);
+            let mut current = base;
+            for chunk in bytes.chunks(delta_width) {
+                let mut raw = [0u8; 8];
+                raw[..delta_width].copy_from_slice(chunk);
+                B::apply(reader.endian, &mut raw[..delta_width]);
+                let shift = 64 - 8 * delta_width as u32;
+                let delta = (i64::from_le_bytes(raw) << shift) >> shift;
+
+                current = match current.checked_add(delta) {
+                    Some(v) => v,
+                    None => {
+                        return Err(BinaryError::InvalidData(
+                            "delta accumulation overflowed".to_string(),
+                        ))
+                    }
+                };
+                values.push(current);
+            }
+
+            Ok(values)
+        })
+    }
+
+    pub fn read_record_array<T, F>(
+        &mut self,
+        max_records: usize,
+        max_record_len: usize,
+        mut parse: F,
+    ) -> Result<Vec<T>, BinaryError>
+    where
+        F: FnMut(&mut BinaryReader<'_, B>) -> Result<T, BinaryError>,
+    {
+        let count = self.read_length()?;
+        if count > max_records {
+            return Err(BinaryError::InvalidData(format!(
+                "record count {} exceeds maximum of {}",
+                count, max_records
+            )));
+        }
+
+        let mut records = Vec::with_capacity(count);
+        for _ in 0..count {
+            let record_len = self.read_usize()?;
+            if record_len > max_record_len {
+                return Err(BinaryError::InvalidData(format!(
+                    "record length {} exceeds maximum of {}",
+                    record_len, max_record_len
+                )));
+            }
+
+            let mut record = Memorystream::from_vec(self.read_bytes(record_len)?);
+            records.push(parse(&mut self.sub_reader(&mut record))?);
+        }
+
+        Ok(records)
+    }
+
+    pub fn read_checked_block<T, F>(&mut self, parse: F) -> Result<T, BinaryError>
+    where
+        F: FnOnce(&mut BinaryReader<'_, B>) -> Result<T, BinaryError>,
+    {
+        self.expect_magic(&CHECKED_BLOCK_MAGIC)?;
+
+        let len = self.read_u32()? as usize;
+        let body = self.read_bytes(len)?;
+
+        let expected = self.read_u32()?;
+        let found = checksumstream::crc32(&body);
+        if expected != found {
+            return Err(self.error_at(4, BinaryError::ChecksumMismatch { expected, found }));
+        }
+
+        let mut body = Memorystream::from_vec(body);
+        parse(&mut self.sub_reader(&mut body))
+    }
+
+    // A reader over a nested buffer that inherits this reader's settings.
+    fn sub_reader<'s>(&self, stream: &'s mut dyn Stream) -> BinaryReader<'s, B> {
+        let config = BinaryConfig {
+            endian: self.endian,
+            length_prefix: self.length_prefix,
+            max_alloc: self.max_alloc,
+            max_depth: self.max_depth,
+            codec: self.codec,
+            ..BinaryConfig::default()
+        };
+
+        let mut sub_reader = BinaryReader::from_parts(stream, config);
+        sub_reader.depth = self.depth;
+
+        sub_reader
+    }
+
+    pub fn read_bitmask_indices(&mut self, bit_count: usize) -> Result<Vec<usize>, BinaryError> {
+        self.with_scratch(bit_count.div_ceil(8), |_, mask| {
+            let indices = (0..bit_count)
+                .filter(|i| mask[i / 8] & (1 << (i % 8)) != 0)
+                .collect();
+
+            Ok(indices)
+        })
+    }
+
+    pub fn read_bool_slice(&mut self) -> Result<Vec<bool>, BinaryError> {
+        let count = self.read_length()?;
+        self.check_alloc(count)?;
+
+        self.with_scratch(count.div_ceil(8), |_, packed| {
+            let bits = (0..count)
+                .map(|i| packed[i / 8] & (1 << (i % 8)) != 0)
+                .collect();
+
+            Ok(bits)
+        })
+    }
+
+    pub fn read_result_with<T, E, OkF, ErrF>(
+        &mut self,
+        ok_f: OkF,
+        err_f: ErrF,
+    ) -> Result<Result<T, E>, BinaryError>
+    where
+        OkF: FnOnce(&mut Self) -> Result<T, BinaryError>,
+        ErrF: FnOnce(&mut Self) -> Result<E, BinaryError>,
+    {
+        match self.read_u8()? {
+            0 => Ok(Ok(ok_f(self)?)),
+            1 => Ok(Err(err_f(self)?)),
+            tag => Err(self.error_at(1, BinaryError::InvalidDiscriminant(u32::from(tag)))),
+        }
+    }
+
+    pub fn read_string_table(&mut self, len: usize) -> Result<StringTable, BinaryError> {
+        Ok(StringTable::from_bytes(self.read_bytes(len)?))
+    }
+
+    pub fn versioned<T, F>(&mut self, version: u32, f: F) -> Result<T, BinaryError>
+    where
+        F: FnOnce(u32, &mut Self) -> Result<T, BinaryError>,
+    {
+        f(version, self)
+    }
+
+    pub fn read_since<T, F>(
+        &mut self,
+        version: u32,
+        min_version: u32,
+        f: F,
+    ) -> Result<Option<T>, BinaryError>
+    where
+        F: FnOnce(&mut Self) -> Result<T, BinaryError>,
+    {
+        if version < min_version {
+            return Ok(None);
+        }
+
+        Ok(Some(f(self)?))
+    }
+
+    pub fn read_packed_10bit(&mut self, count: usize) -> Result<Vec<u16>, BinaryError> {
+        if count % 4 != 0 {
+            return Err(BinaryError::InvalidData(format!(
+                "10-bit packed sample count {} is not a multiple of 4",
+                count
+            )));
+        }
+
+        self.with_scratch(count / 4 * 5, |_, bytes| {
+            let mut samples = Vec::with_capacity(count);
+            for group in bytes.chunks(5) {
+                for (i, &high) in group[..4].iter().enumerate() {
+                    let low = (group[4] >> (2 * i)) & 0x03;
+                    samples.push(((high as u16) << 2) | low as u16);
+                }
+            }
+
+            Ok(samples)
+        })
+    }
+
+    pub fn read_packed_12bit(&mut self, count: usize) -> Result<Vec<u16>, BinaryError> {
+        if count % 2 != 0 {
+            return Err(BinaryError::InvalidData(format!(
+                "12-bit packed sample count {} is not a multiple of 2",
+                count
+            )));
+        }
+
+        self.with_scratch(count / 2 * 3, |_, bytes| {
+            let mut samples = Vec::with_capacity(count);
+            for group in bytes.chunks(3) {
+                samples.push(((group[0] as u16) << 4) | (group[2] & 0x0F) as u16);
+                samples.push(((group[1] as u16) << 4) | (group[2] >> 4) as u16);
+            }
+
+            Ok(samples)
+        })
+    }
+
+    pub fn read_exact_count<T, F>(&mut self, expected: usize, mut parse: F) -> Result<Vec<T>, BinaryError>
+    where
+        F: FnMut(&mut Self) -> Result<T, BinaryError>,
+    {
+        let found = self.read_length()?;
+        if found != expected {
+            return Err(BinaryError::CountMismatch { expected, found });
+        }
+
+        let mut items = Vec::with_capacity(expected);
+        for _ in 0..expected {
+            items.push(parse(self)?);
+        }
+
+        Ok(items)
+    }
+
+    // Stops cleanly when `f` runs out of input without having consumed any
+    // bytes; a record cut off part way through, even at a field boundary, is
+    // yielded as an error before stopping.
+    pub fn read_iter<T, F>(
+        &mut self,
+        mut f: F,
+    ) -> impl Iterator<Item = Result<T, BinaryError>> + use<'_, 'a, B, T, F>
+    where
+        F: FnMut(&mut BinaryReader<'_, B>) -> Result<T, BinaryError>,
+    {
+        let mut done = false;
+        core::iter::from_fn(move || {
+            if done {
+                return None;
+            }
+
+            let start = self.consumed;
+            match f(self) {
+                Ok(item) => Some(Ok(item)),
+                Err(e) if e.is_eof() && self.consumed == start => {
+                    done = true;
+                    None
+                }
+                Err(e) => {
+                    done = true;
+                    Some(Err(e))
+                }
+            }
+        })
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, BinaryError> {
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            value => Err(self.error_at(1, BinaryError::InvalidBool(value))),
+        }
+    }
+
+    pub fn read_char(&mut self) -> Result<char, BinaryError> {
+        let value = self.read_u32()?;
+
+        match core::char::from_u32(value) {
+            Some(c) => Ok(c),
+            None => Err(self.error_at(4, BinaryError::InvalidChar(value))),
+        }
+    }
+
+    pub fn read_duration(&mut self) -> Result<Duration, BinaryError> {
+        let secs = self.read_u64()?;
+        let nanos = self.read_u32()?;
+
+        if nanos >= 1_000_000_000 {
+            return Err(self.error_at(
+                12,
+                BinaryError::InvalidData(format!("duration nanos {} out of range", nanos)),
+            ));
+        }
+
+        Ok(Duration::new(secs, nanos))
+    }
+
+    #[cfg(feature = "std")]
+    pub fn read_system_time(&mut self) -> Result<SystemTime, BinaryError> {
+        let since_epoch = self.read_duration()?;
+
+        match UNIX_EPOCH.checked_add(since_epoch) {
+            Some(time) => Ok(time),
+            None => Err(self.error_at(
+                12,
+                BinaryError::InvalidData(format!(
+                    "{} seconds since the epoch does not fit in a SystemTime",
+                    since_epoch.as_secs()
+                )),
+            )),
+        }
+    }
+
+    pub fn read_ip_addr(&mut self) -> Result<IpAddr, BinaryError> {
+        match self.read_u8()? {
+            4 => Ok(IpAddr::V4(Ipv4Addr::from(self.read_array::<4>()?))),
+            6 => Ok(IpAddr::V6(Ipv6Addr::from(self.read_array::<16>()?))),
+            tag => Err(self.error_at(1, BinaryError::InvalidAddressFamily(tag))),
+        }
+    }
+
+    pub fn read_socket_addr(&mut self) -> Result<SocketAddr, BinaryError> {
+        let ip = self.read_ip_addr()?;
+        let port = self.read_u16()?;
+
+        Ok(SocketAddr::new(ip, port))
+    }
+
+    pub fn read_fixed_point(&mut self) -> Result<(i64, u8), BinaryError> {
+        let mantissa = self.read_i64()?;
+        let scale = self.read_u8()?;
+
+        Ok((mantissa, scale))
+    }
+
+    pub fn read_fixed_point_f64(&mut self) -> Result<f64, BinaryError> {
+        let (mantissa, scale) = self.read_fixed_point()?;
+
+        Ok(mantissa as f64 / pow10(scale))
+    }
+
+    pub fn read_nonzero_u32(&mut self) -> Result<NonZeroU32, BinaryError> {
+        match NonZeroU32::new(self.read_u32()?) {
+            Some(value) => Ok(value),
+            None => Err(self.error_at(4, BinaryError::UnexpectedZero)),
+        }
+    }
+
+    pub fn read_nonzero_u64(&mut self) -> Result<NonZeroU64, BinaryError> {
+        match NonZeroU64::new(self.read_u64()?) {
+            Some(value) => Ok(value),
+            None => Err(self.error_at(8, BinaryError::UnexpectedZero)),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn read_value<T: DeserializeOwned>(&mut self) -> Result<T, BinaryError> {
+        let len = self.read_usize()?;
+
+        self.with_scratch(len, |reader, buffer| match reader.decode(buffer) {
+            Ok(value) => Ok(value),
+            Err(e) => Err(reader.error_at(len, e)),
+        })
+    }
+
+    #[cfg(feature = "std")]
+    pub fn read_vec<T: DeserializeOwned>(&mut self) -> Result<Vec<T>, BinaryError> {
+        let count = self.read_length()?;
+        self.read_vec_n(count)
+    }
+
+    /// Reads `count` items with no count prefix, for counts that were read
+    /// elsewhere such as from a header.
+    #[cfg(feature = "std")]
+    pub fn read_vec_n<T: DeserializeOwned>(&mut self, count: usize) -> Result<Vec<T>, BinaryError> {
+        self.check_alloc(count.saturating_mul(core::mem::size_of::<T>().max(1)))?;
+
+        let mut items = Vec::with_capacity(count);
+        for _ in 0..count {
+            items.push(self.deserialize_item()?);
+        }
+
+        Ok(items)
+    }
+
+    /// Reads `element_size`-byte elements until the stream runs out, for data
+    /// with no count prefix. A trailing partial element is an error.
+    #[cfg(feature = "std")]
+    pub fn read_vec_until_eof<T: DeserializeOwned>(
+        &mut self,
+        element_size: usize,
+    ) -> Result<Vec<T>, BinaryError> {
+        if element_size == 0 {
+            return Err(BinaryError::InvalidData("element size must be non-zero".to_string()));
+        }
+        self.check_alloc(element_size)?;
+
+        let mut buffer = vec![0u8; element_size];
+        self.read_iter(|reader| {
+            reader.read_exact_into(&mut buffer)?;
+
+            match reader.decode(&buffer) {
+                Ok(item) => Ok(item),
+                Err(e) => Err(reader.error_at(element_size, e)),
+            }
+        })
+        .collect()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn read_map<K, V>(&mut self) -> Result<HashMap<K, V>, BinaryError>
+    where
+        K: DeserializeOwned + Eq + Hash,
+        V: DeserializeOwned,
+    {
+        let count = self.read_length()?;
+        let entry_size = core::mem::size_of::<(K, V)>().max(1);
+        self.check_alloc(count.saturating_mul(entry_size))?;
+
+        let mut map = HashMap::with_capacity(count);
+        for _ in 0..count {
+            let key = self.deserialize_item()?;
+            let value = self.deserialize_item()?;
+            map.insert(key, value);
+        }
+
+        Ok(map)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn read_option<T: DeserializeOwned>(&mut self) -> Result<Option<T>, BinaryError> {
+        if self.read_bool()? {
+            Ok(Some(self.deserialize_item()?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Reads a `Result` written by `write_result`.
+    #[cfg(feature = "std")]
+    pub fn read_result<T, E>(&mut self) -> Result<Result<T, E>, BinaryError>
+    where
+        T: DeserializeOwned,
+        E: DeserializeOwned,
+    {
+        match self.read_u8()? {
+            0 => Ok(Ok(self.deserialize_item()?)),
+            1 => Ok(Err(self.deserialize_item()?)),
+            tag => Err(self.error_at(1, BinaryError::InvalidDiscriminant(u32::from(tag)))),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn deserialize_item<T: DeserializeOwned>(&mut self) -> Result<T, BinaryError> {
+        if self.codec == Codec::Fixed {
+            let start = self.stream.tell().ok();
+
+            return match (T::deserialize(&mut fixedcodec::Deserializer::new(self)), start) {
+                (Ok(item), _) => Ok(item),
+                (Err(e @ BinaryError::At { .. }), _) | (Err(e), None) => Err(e),
+                (Err(e), Some(offset)) => Err(BinaryError::At {
+                    offset,
+                    source: Box::new(e),
+                }),
+            };
+        }
+
+        let options = bincode::DefaultOptions::new()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(self.max_alloc as u64);
+
+        let start = self.stream.tell().ok();
+        let mut source = StreamRead {
+            stream: &mut *self.stream,
+            budget: self.byte_limit - self.consumed,
+            read: 0,
+            exceeded: false,
+            missing: None,
+            error: None,
+        };
+        let item = options.deserialize_from(&mut source);
+        let StreamRead { read, exceeded, missing, error, .. } = source;
+        self.consumed += read;
+
+        if exceeded {
+            return Err(BinaryError::ByteBudgetExceeded {
+                consumed: self.consumed,
+                limit: self.byte_limit,
+            });
+        }
+
+        // Report running out of input and stream failures the same way the
+        // other readers do, rather than as bincode I/O errors.
+        let error = match (item, error, missing) {
+            (Ok(item), ..) => return Ok(item),
+            (Err(_), Some(e), _) => BinaryError::StreamError(e),
+            (Err(_), None, Some(missing)) => BinaryError::UnexpectedEof {
+                expected: read + missing,
+                got: read,
+            },
+            (Err(e), None, None) => e.into(),
+        };
+
+        match start {
+            Some(offset) => Err(BinaryError::At {
+                offset,
+                source: Box::new(error),
+            }),
+            None => Err(error),
+        }
+    }
+
+    // Reads `length` bytes into the reader's reusable scratch buffer and hands
+    // them to `f`, for callers that decode the bytes rather than keep them.
+    fn with_scratch<T, F>(&mut self, length: usize, f: F) -> Result<T, BinaryError>
+    where
+        F: FnOnce(&mut Self, &[u8]) -> Result<T, BinaryError>,
+    {
+        self.check_alloc(length)?;
+
+        let mut scratch = core::mem::take(&mut self.scratch);
+        scratch.clear();
+        scratch.resize(length, 0);
+
+        let result = match self.read_exact_into(&mut scratch) {
+            Ok(()) => f(self, &scratch),
+            Err(e) => Err(e),
+        };
+
+        // Keep the buffer for the next read unless a one-off large read grew it.
+        if scratch.capacity() <= MAX_RETAINED_SCRATCH {
+            self.scratch = scratch;
+        }
+
+        result
+    }
+
+    // Decodes a value already read into memory with this reader's codec. The
+    // value must use all of `bytes`; anything left over means the bytes were
+    // written as a different type, and the next read would start mid-value.
+    #[cfg(feature = "std")]
+    fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, BinaryError> {
+        let (value, consumed) = match self.codec {
+            Codec::Bincode => {
+                let mut rest = bytes;
+                let value = deserialize_from(&mut rest)?;
+                (value, bytes.len() - rest.len())
+            }
+            Codec::Fixed => {
+                let mut stream = SliceStream::new(bytes);
+                let mut reader = self.sub_reader(&mut stream);
+                let value = T::deserialize(&mut fixedcodec::Deserializer::new(&mut reader))?;
+                (value, reader.get_cur_pos()?)
+            }
+        };
+
+        if consumed != bytes.len() {
+            return Err(BinaryError::TrailingBytes {
+                consumed,
+                expected: bytes.len(),
+            });
+        }
+
+        Ok(value)
+    }
+
+    pub fn read_bytes(&mut self, length: usize) -> Result<Vec<u8>, BinaryError> {
+        self.check_alloc(length)?;
+
+        let mut buffer: Vec<u8> = vec![0; length];
+        self.read_exact_into(&mut buffer)?;
+
+        Ok(buffer)
+    }
+
+    pub fn read_byte_vec(&mut self) -> Result<Vec<u8>, BinaryError> {
+        let len = self.read_length()?;
+        self.read_bytes(len)
+    }
+
+    /// Reads a length-prefixed JSON document written by `write_json`.
+    /// Malformed JSON fails with `JsonError`.
+    #[cfg(feature = "json")]
+    pub fn read_json(&mut self) -> Result<serde_json::Value, BinaryError> {
+        let len = self.read_length()?;
+        let bytes = self.read_bytes(len)?;
+
+        match serde_json::from_slice(&bytes) {
+            Ok(value) => Ok(value),
+            Err(e) => Err(self.error_at(len, BinaryError::JsonError(e))),
+        }
+    }
+
+    pub fn read_blobs(&mut self) -> Result<Vec<Vec<u8>>, BinaryError> {
+        let count = self.read_length()?;
+        self.check_alloc(count.saturating_mul(core::mem::size_of::<Vec<u8>>()))?;
+
+        let mut blobs = Vec::with_capacity(count);
+        for _ in 0..count {
+            blobs.push(self.read_byte_vec()?);
+        }
+
+        Ok(blobs)
+    }
+
+    pub fn read_string_vec(&mut self) -> Result<Vec<String>, BinaryError> {
+        let count = self.read_length()?;
+        self.check_alloc(count.saturating_mul(core::mem::size_of::<String>()))?;
+
+        let mut strings = Vec::with_capacity(count);
+        for _ in 0..count {
+            strings.push(self.read_string()?);
+        }
+
+        Ok(strings)
+    }
+
+    /// Reads a path written by `write_path`. Fails if it was written on a
+    /// platform with a different path encoding.
+    #[cfg(feature = "std")]
+    pub fn read_path(&mut self) -> Result<PathBuf, BinaryError> {
+        let tag = self.read_u8()?;
+
+        #[cfg(unix)]
+        if tag == PATH_TAG_UNIX {
+            use std::os::unix::ffi::OsStringExt;
+
+            return Ok(PathBuf::from(OsString::from_vec(self.read_byte_vec()?)));
+        }
+
+        #[cfg(windows)]
+        if tag == PATH_TAG_WINDOWS {
+            use std::os::windows::ffi::OsStringExt;
+
+            let len = self.read_length()?;
+            let units = self.read_u16_slice(len)?;
+            return Ok(PathBuf::from(OsString::from_wide(&units)));
+        }
+
+        Err(self.error_at(
+            1,
+            BinaryError::InvalidData(format!(
+                "path tag {} cannot be decoded on this platform",
+                tag
+            )),
+        ))
+    }
+
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], BinaryError> {
+        let mut buffer = [0u8; N];
+        self.read_exact_into(&mut buffer)?;
+
+        Ok(buffer)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn read_array_of<T: DeserializeOwned, const N: usize>(
+        &mut self,
+    ) -> Result<[T; N], BinaryError> {
+        let mut items = Vec::with_capacity(N);
+        for _ in 0..N {
+            items.push(self.deserialize_item()?);
+        }
+
+        match <[T; N]>::try_from(items) {
+            Ok(array) => Ok(array),
+            Err(items) => Err(BinaryError::CountMismatch {
+                expected: N,
+                found: items.len(),
+            }),
+        }
+    }
+
+    read_slices! {
+        read_u16_slice => u16;
+        read_i16_slice => i16;
+        read_u32_slice => u32;
+        read_i32_slice => i32;
+        read_u64_slice => u64;
+        read_i64_slice => i64;
+        read_f32_slice => f32;
+        read_f64_slice => f64;
+    }
+
+    /// Reads `N` f32 values in one bulk read, e.g. `read_f32_array::<16>()`
+    /// for a 4x4 matrix.
+    pub fn read_f32_array<const N: usize>(&mut self) -> Result<[f32; N], BinaryError> {
+        let mut values = [0.0; N];
+        values.copy_from_slice(&self.read_f32_slice(N)?);
+
+        Ok(values)
+    }
+
+    pub fn read_to_end(&mut self) -> Result<Vec<u8>, BinaryError> {
+        let remaining = self.remaining()?;
+
+        self.read_bytes(remaining)
+    }
+
+    pub fn read_bytes_into(&mut self, buf: &mut [u8]) -> Result<usize, BinaryError> {
+        self.read_exact_into(buf)?;
+
+        Ok(buf.len())
+    }
+
+    pub fn read_ascii_int(&mut self, len: usize, radix: u32) -> Result<i64, BinaryError> {
+        if !(2..=36).contains(&radix) {
+            return Err(BinaryError::InvalidData(format!("unsupported radix {}", radix)));
+        }
+
+        let text = String::from_utf8(self.read_bytes(len)?)?;
+        let value = i64::from_str_radix(&text, radix)?;
+
+        Ok(value)
+    }
+
+    #[cfg(feature = "bumpalo")]
+    pub fn read_bytes_in<'b>(
+        &mut self,
+        arena: &'b bumpalo::Bump,
+        length: usize,
+    ) -> Result<&'b [u8], BinaryError> {
+        self.check_alloc(length)?;
+
+        let buffer = arena.alloc_slice_fill_copy(length, 0u8);
+        self.read_exact_into(buffer)?;
+
+        Ok(buffer)
+    }
+}
+
+pub struct BinaryWriter<'a, B = Endian> {
+    stream: &'a mut dyn Stream,
+    relocations: Vec<(usize, usize)>,
+    transactions: Vec<(usize, usize)>,
+    pad_byte: u8,
+    endian: Endian,
+    length_prefix: LengthPrefix,
+    codec: Codec,
+    order: PhantomData<B>,
+}
+
+#[derive(Debug)]
+pub struct Reloc {
+    position: usize,
+}
+
+impl Reloc {
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<'a> BinaryWriter<'a> {
+    pub fn new(stream: &'a mut impl Stream) -> BinaryWriter<'a> {
+        BinaryWriter::with_config(stream, BinaryConfig::default())
+    }
+
+    pub fn with_endian(stream: &'a mut impl Stream, endian: Endian) -> BinaryWriter<'a> {
+        BinaryWriter::with_config(stream, BinaryConfig { endian, ..BinaryConfig::default() })
+    }
+
+    pub fn with_config(stream: &'a mut impl Stream, config: BinaryConfig) -> BinaryWriter<'a> {
+        BinaryWriter::from_parts(stream, config)
+    }
+
+    /// A writer into a fresh in-memory buffer; `finish` returns the bytes.
+    pub fn to_vec() -> OwnedBinaryWriter {
+        OwnedBinaryWriter::new()
+    }
+
+    pub fn set_endian(&mut self, endian: Endian) {
+        self.endian = endian;
+    }
+
+    pub fn endian(&self) -> Endian {
+        self.endian
+    }
+
+    pub fn write_num<T: BinaryNum>(&mut self, value: T) -> Result<usize, BinaryError> {
+        value.write_to(self)
+    }
+
+    /// The same as `write_num`.
+    pub fn write_number<T: Primitive>(&mut self, value: T) -> Result<usize, BinaryError> {
+        self.write_num(value)
+    }
+
+    /// Scales `value` by `10^scale` and rounds half away from zero.
+    pub fn to_fixed_point(value: f64, scale: u8) -> Result<i64, BinaryError> {
+        let scaled = value * pow10(scale);
+
+        if !scaled.is_finite() || scaled >= i64::MAX as f64 || scaled < i64::MIN as f64 {
+            return Err(BinaryError::InvalidData(format!(
+                "{} does not fit in a fixed-point value with scale {}",
+                value, scale
+            )));
+        }
+
+        let rounded = if scaled >= 0.0 { scaled + 0.5 } else { scaled - 0.5 };
+        Ok(rounded as i64)
+    }
+}
+
+impl<'a, B: ByteOrder> BinaryWriter<'a, B> {
+    /// A writer whose byte order is fixed by `B`, e.g.
+    /// `BinaryWriter::<BigEndian>::with_byte_order(&mut stream)`.
+    pub fn with_byte_order(stream: &'a mut impl Stream) -> BinaryWriter<'a, B> {
+        BinaryWriter::from_parts(stream, BinaryConfig::default())
+    }
+
+    fn from_parts(stream: &'a mut dyn Stream, config: BinaryConfig) -> BinaryWriter<'a, B> {
+        BinaryWriter {
+            stream,
+            relocations: Vec::new(),
+            transactions: Vec::new(),
+            pad_byte: config.pad_byte,
+            endian: config.endian,
+            length_prefix: config.length_prefix,
+            codec: config.codec,
+            order: PhantomData,
+        }
+    }
+
+    pub fn set_length_prefix(&mut self, length_prefix: LengthPrefix) {
+        self.length_prefix = length_prefix;
+    }
+
+    pub fn length_prefix(&self) -> LengthPrefix {
+        self.length_prefix
+    }
+
+    pub fn set_codec(&mut self, codec: Codec) {
+        self.codec = codec;
+    }
+
+    pub fn codec(&self) -> Codec {
+        self.codec
+    }
+
+    pub fn seek_to(&mut self, to: usize) -> Result<usize, BinaryError> {
+        let result = self.stream.seek(to);
+
+        match result {
+            Ok(r) => Ok(r),
+            Err(e) => Err(BinaryError::StreamError(e)),
+        }
+    }
+
+    pub fn seek(&mut self, from: SeekOrigin, offset: i64) -> Result<usize, BinaryError> {
+        let result = resolve_seek(&mut *self.stream, from, offset);
+
+        match result {
+            Ok(r) => Ok(r),
+            Err(e) => Err(BinaryError::StreamError(e)),
+        }
+    }
+
+    pub fn get_cur_pos(&mut self) -> Result<usize, BinaryError> {
+        let result = self.stream.tell();
+
+        match result {
+            Ok(pos) => Ok(pos),
+            Err(e) => Err(BinaryError::StreamError(e)),
+        }
+    }
+
+    pub fn position_guard(&mut self) -> Result<PositionGuard<'_, Self>, BinaryError> {
+        let position = self.get_cur_pos()?;
+
+        Ok(PositionGuard::new(self, position, |writer, to| {
+            let _ = writer.seek_to(to);
+        }))
+    }
+
+    pub fn flush(&mut self) -> Result<(), BinaryError> {
+        let result = self.stream.flush();
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) => Err(BinaryError::StreamError(e)),
+        }
+    }
+
+    /// Records the current position and stream length so `rollback` can
+    /// undo what is written after this. Needs a stream that can report its
+    /// length.
+    pub fn begin_transaction(&mut self) -> Result<(), BinaryError> {
+        let position = self.get_cur_pos()?;
+        let len = self.stream.stream_len()?;
+        self.transactions.push((position, len));
+
+        Ok(())
+    }
+
+    pub fn commit(&mut self) -> Result<(), BinaryError> {
+        match self.transactions.pop() {
+            Some(_) => Ok(()),
+            None => Err(BinaryError::InvalidData("no transaction in progress".to_string())),
+        }
+    }
+
+    /// Moves back to where the transaction began and, if the stream grew
+    /// since then, truncates it to its length at the time. Data that was
+    /// there before the transaction is kept, but bytes the transaction
+    /// overwrote in place are not restored. If the stream can't truncate,
+    /// this fails before moving and the transaction stays open.
+    pub fn rollback(&mut self) -> Result<(), BinaryError> {
+        let (position, len) = match self.transactions.last() {
+            Some(&transaction) => transaction,
+            None => {
+                return Err(BinaryError::InvalidData("no transaction in progress".to_string()))
+            }
+        };
+
+        if self.stream.stream_len()? > len {
+            self.stream.truncate(len)?;
+        }
+        self.stream.seek(position)?;
+        self.transactions.pop();
+
+        Ok(())
+    }
+
+    pub fn set_pad_byte(&mut self, pad: u8) {
+        self.pad_byte = pad;
+    }
+
+    pub fn pad_byte(&self) -> u8 {
+        self.pad_byte
+    }
+
+    fn write_length(&mut self, len: usize) -> Result<usize, BinaryError> {
+        let max = match self.length_prefix {
+            LengthPrefix::U8 => u8::MAX as usize,
+            LengthPrefix::U16 => u16::MAX as usize,
+            LengthPrefix::U32 => u32::MAX as usize,
+            LengthPrefix::U64 | LengthPrefix::Varint => usize::MAX,
+        };
+        if len > max {
+            return Err(BinaryError::LengthOverflow { len, max });
+        }
+
+        match self.length_prefix {
+            LengthPrefix::U8 => self.write_u8(len as u8),
+            LengthPrefix::U16 => self.write_u16(len as u16),
+            LengthPrefix::U32 => self.write_u32(len as u32),
+            LengthPrefix::U64 => self.write_u64(len as u64),
+            LengthPrefix::Varint => self.write_varint_u64(len as u64),
+        }
+    }
+
+    /// Writes all of `bytes`, calling the stream as many times as it takes,
+    /// or fails with `ShortWrite` once the stream stops accepting data. The
+    /// typed write methods are built on this guarantee.
+    pub fn write_exact(&mut self, bytes: &[u8]) -> Result<(), BinaryError> {
+        self.write_all(bytes).map(|_| ())
+    }
+
+    // Streams may accept only part of a buffer per call, so keep writing
+    // until all of it lands or the stream stops making progress.
+    fn write_all(&mut self, bytes: &[u8]) -> Result<usize, BinaryError> {
+        let expected = bytes.len();
+        let mut written = 0;
+
+        while written < expected {
+            let count = self.stream.write(&bytes[written..])?;
+
+            if count == 0 {
+                return Err(BinaryError::ShortWrite { expected, written });
+            }
+
+            written += count;
+        }
+
+        Ok(written)
+    }
+
+    pub fn write_string<S: AsRef<str>>(&mut self, value: S) -> Result<usize, BinaryError> {
+        let value = value.as_ref();
+
+        self.write_length(value.len())?;
+
+        self.write_string_raw(value)
+    }
+
+    pub fn write_string_raw(&mut self, value: &str) -> Result<usize, BinaryError> {
+        self.write_all(value.as_bytes())
+    }
+
+    pub fn write_string_utf16(&mut self, value: &str) -> Result<usize, BinaryError> {
+        let units: Vec<u16> = value.encode_utf16().collect();
+        if units.len() > u16::MAX as usize {
+            return Err(BinaryError::InvalidData(format!(
+                "{} UTF-16 code units exceeds the maximum of {}",
+                units.len(),
+                u16::MAX
+            )));
+        }
+
+        let mut written = self.write_u16(units.len() as u16)?;
+        for unit in units {
+            written += self.write_u16(unit)?;
+        }
+
+        Ok(written)
+    }
+
+    pub fn write_cstring(&mut self, value: &str) -> Result<usize, BinaryError> {
+        if value.as_bytes().contains(&0) {
+            return Err(BinaryError::InvalidData(format!(
+                "string {:?} contains an interior NUL",
+                value
+            )));
+        }
+
+        Ok(self.write_bytes(value.as_bytes())? + self.write_u8(0)?)
+    }
+
+    pub fn write_fixed_string(
+        &mut self,
+        value: &str,
+        width: usize,
+        pad: u8,
+    ) -> Result<usize, BinaryError> {
+        let bytes = value.as_bytes();
+        if bytes.len() > width {
+            return Err(BinaryError::InvalidData(format!(
+                "{} bytes does not fit in a {}-byte field",
+                bytes.len(),
+                width
+            )));
+        }
+
+        Ok(self.write_bytes(bytes)? + self.write_padding_with(width - bytes.len(), pad)?)
+    }
+
+    pub fn write_tagged(&mut self, value: &TaggedValue) -> Result<usize, BinaryError> {
+        match value {
+            TaggedValue::Bool(v) => self.write_tagged_bool(*v),
+            TaggedValue::U8(v) => self.write_tagged_u8(*v),
+            TaggedValue::U32(v) => self.write_tagged_u32(*v),
+            TaggedValue::U64(v) => self.write_tagged_u64(*v),
+            TaggedValue::I32(v) => self.write_tagged_i32(*v),
+            TaggedValue::I64(v) => self.write_tagged_i64(*v),
+            TaggedValue::F32(v) => self.write_tagged_f32(*v),
+            TaggedValue::F64(v) => self.write_tagged_f64(*v),
+            TaggedValue::Str(v) => self.write_tagged_string(v),
+            TaggedValue::Bytes(v) => self.write_tagged_bytes(v),
+        }
+    }
+
+    pub fn write_tagged_bool(&mut self, value: bool) -> Result<usize, BinaryError> {
+        Ok(self.write_u8(tagged::BOOL)? + self.write_bool(value)?)
+    }
+
+    pub fn write_tagged_u8(&mut self, value: u8) -> Result<usize, BinaryError> {
+        Ok(self.write_u8(tagged::U8)? + self.write_u8(value)?)
+    }
+
+    pub fn write_tagged_u32(&mut self, value: u32) -> Result<usize, BinaryError> {
+        Ok(self.write_u8(tagged::U32)? + self.write_u32(value)?)
+    }
+
+    pub fn write_tagged_u64(&mut self, value: u64) -> Result<usize, BinaryError> {
+        Ok(self.write_u8(tagged::U64)? + self.write_u64(value)?)
+    }
+
+    pub fn write_tagged_i32(&mut self, value: i32) -> Result<usize, BinaryError> {
+        Ok(self.write_u8(tagged::I32)? + self.write_i32(value)?)
+    }
+
+    pub fn write_tagged_i64(&mut self, value: i64) -> Result<usize, BinaryError> {
+        Ok(self.write_u8(tagged::I64)? + self.write_i64(value)?)
+    }
+
+    pub fn write_tagged_f32(&mut self, value: f32) -> Result<usize, BinaryError> {
+        Ok(self.write_u8(tagged::F32)? + self.write_f32(value)?)
+    }
+
+    pub fn write_tagged_f64(&mut self, value: f64) -> Result<usize, BinaryError> {
+        Ok(self.write_u8(tagged::F64)? + self.write_f64(value)?)
+    }
+
+    pub fn write_tagged_string(&mut self, value: &str) -> Result<usize, BinaryError> {
+        Ok(self.write_u8(tagged::STR)? + self.write_byte_vec(value.as_bytes())?)
+    }
+
+    pub fn write_tagged_bytes(&mut self, value: &[u8]) -> Result<usize, BinaryError> {
+        Ok(self.write_u8(tagged::BYTES)? + self.write_byte_vec(value)?)
+    }
+
+    pub fn write_enum_by_name<E: AsRef<str>>(&mut self, value: &E) -> Result<usize, BinaryError> {
+        self.write_string(value.as_ref())
+    }
+
+    pub fn write_pascal_string(
+        &mut self,
+        value: &str,
+        encoding: StringEncoding,
+    ) -> Result<usize, BinaryError> {
+        let bytes = encoding.encode(value)?;
+        if bytes.len() > u8::MAX as usize {
+            return Err(BinaryError::InvalidData(format!(
+                "{} bytes exceeds the Pascal string maximum of {}",
+                bytes.len(),
+                u8::MAX
+            )));
+        }
+
+        Ok(self.write_u8(bytes.len() as u8)? + self.write_bytes(&bytes)?)
+    }
+
+    pub fn write_long_pascal_string(
+        &mut self,
+        value: &str,
+        encoding: StringEncoding,
+    ) -> Result<usize, BinaryError> {
+        let bytes = encoding.encode(value)?;
+        if bytes.len() > u16::MAX as usize {
+            return Err(BinaryError::InvalidData(format!(
+                "{} bytes exceeds the long Pascal string maximum of {}",
+                bytes.len(),
+                u16::MAX
+            )));
+        }
+
+        Ok(self.write_u16(bytes.len() as u16)? + self.write_bytes(&bytes)?)
+    }
+
+    /// Narrows `value` to a half-precision float, rounding to the nearest
+    /// representable value. Values outside the f16 range become infinity.
+    #[cfg(feature = "half")]
+    pub fn write_f16(&mut self, value: f32) -> Result<usize, BinaryError> {
+        let mut data = half::f16::from_f32(value).to_le_bytes();
+        B::apply(self.endian, &mut data);
+
+        self.write_bytes(&data)
+    }
+
+    pub fn write_f32(&mut self, value: f32) -> Result<usize, BinaryError> {
+        self.write_u32(value.to_bits())
+    }
+
+    pub fn write_f64(&mut self, value: f64) -> Result<usize, BinaryError> {
+        self.write_u64(value.to_bits())
+    }
+
+    pub fn write_isize(&mut self, value: isize) -> Result<usize, BinaryError> {
+        let mut data = (value as i64).to_le_bytes();
+        B::apply(self.endian, &mut data);
+
+        self.write_all(&data)
+    }
+
+    pub fn write_usize(&mut self, value: usize) -> Result<usize, BinaryError> {
+        let mut data = (value as u64).to_le_bytes();
+        B::apply(self.endian, &mut data);
+
+        self.write_all(&data)
+    }
+
+    pub fn write_u128(&mut self, value: u128) -> Result<usize, BinaryError> {
+        let mut data = value.to_le_bytes();
+        B::apply(self.endian, &mut data);
+
+        self.write_all(&data)
+    }
+
+    pub fn write_i128(&mut self, value: i128) -> Result<usize, BinaryError> {
+        let mut data = value.to_le_bytes();
+        B::apply(self.endian, &mut data);
+
+        self.write_all(&data)
+    }
+
+    /// Writes a sign byte (0 zero, 1 positive, 2 negative) and the big-endian
+    /// magnitude `bytes` as a byte vector, with leading zeros stripped. Zero
+    /// must be written with `Sign::NoSign` and other values without it.
+    pub fn write_bigint(&mut self, bytes: &[u8], sign: Sign) -> Result<usize, BinaryError> {
+        let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+        let magnitude = &bytes[start..];
+
+        if magnitude.is_empty() != (sign == Sign::NoSign) {
+            return Err(BinaryError::InvalidData(format!(
+                "{:?} does not match a {} magnitude",
+                sign,
+                if magnitude.is_empty() { "zero" } else { "non-zero" }
+            )));
+        }
+
+        Ok(self.write_u8(sign.to_byte())? + self.write_byte_vec(magnitude)?)
+    }
+
+    #[cfg(feature = "bigint")]
+    pub fn write_num_bigint(&mut self, value: &num_bigint::BigInt) -> Result<usize, BinaryError> {
+        let (sign, magnitude) = value.to_bytes_be();
+        self.write_bigint(&magnitude, sign.into())
+    }
+
+    pub fn write_u64(&mut self, value: u64) -> Result<usize, BinaryError> {
+        let mut data = value.to_le_bytes();
+        B::apply(self.endian, &mut data);
+
+        self.write_all(&data)
+    }
+
+    pub fn write_i64(&mut self, value: i64) -> Result<usize, BinaryError> {
+        let mut data = value.to_le_bytes();
+        B::apply(self.endian, &mut data);
+
+        self.write_all(&data)
+    }
+
+    pub fn write_u32(&mut self, value: u32) -> Result<usize, BinaryError> {
+        let mut data = value.to_le_bytes();
+        B::apply(self.endian, &mut data);
+
+        self.write_all(&data)
+    }
+
+    pub fn write_i32(&mut self, value: i32) -> Result<usize, BinaryError> {
+        let mut data = value.to_le_bytes();
+        B::apply(self.endian, &mut data);
+
+        self.write_all(&data)
+    }
+
+    pub fn write_u16(&mut self, value: u16) -> Result<usize, BinaryError> {
+        let mut data = value.to_le_bytes();
+        B::apply(self.endian, &mut data);
+
+        self.write_all(&data)
+    }
+
+    pub fn write_i16(&mut self, value: i16) -> Result<usize, BinaryError> {
+        let mut data = value.to_le_bytes();
+        B::apply(self.endian, &mut data);
+
+        self.write_all(&data)
+    }
+
+    pub fn write_u8(&mut self, value: u8) -> Result<usize, BinaryError> {
+        let mut data = value.to_le_bytes();
+        B::apply(self.endian, &mut data);
+
+        self.write_all(&data)
+    }
+
+    pub fn write_i8(&mut self, value: i8) -> Result<usize, BinaryError> {
+        let mut data = value.to_le_bytes();
+        B::apply(self.endian, &mut data);
+
+        self.write_all(&data)
+    }
+
+    pub fn write_varint_u64(&mut self, value: u64) -> Result<usize, BinaryError> {
+        let mut data = Vec::with_capacity(10);
+        let mut remaining = value;
+
+        loop {
+            let byte = (remaining & 0x7F) as u8;
+            remaining >>= 7;
+            if remaining == 0 {
+                data.push(byte);
+                break;
+            }
+            data.push(byte | 0x80);
+        }
+
+        self.write_bytes(&data)
+    }
+
+    pub fn write_varint_i64(&mut self, value: i64) -> Result<usize, BinaryError> {
+        self.write_varint_u64(((value << 1) ^ (value >> 63)) as u64)
+    }
+
+    /// Writes a length whose first byte says how wide it is: the top two
+    /// bits select 1, 2 or 4 big-endian bytes holding the length in the
+    /// remaining 6, 14 or 30 bits, or `0xC0` followed by a full 8-byte length.
+    pub fn write_adaptive_len(&mut self, len: usize) -> Result<usize, BinaryError> {
+        let len = len as u64;
+
+        match len {
+            0..=0x3F => self.write_u8(len as u8),
+            0x40..=0x3FFF => self.write_bytes(&(0x4000 | len as u16).to_be_bytes()),
+            0x4000..=0x3FFF_FFFF => self.write_bytes(&(0x8000_0000 | len as u32).to_be_bytes()),
+            _ => Ok(self.write_u8(0xC0)? + self.write_bytes(&len.to_be_bytes())?),
+        }
+    }
+
+    pub fn write_string_adaptive(&mut self, value: &str) -> Result<usize, BinaryError> {
+        Ok(self.write_adaptive_len(value.len())? + self.write_string_raw(value)?)
+    }
+
+    /// Writes the string's byte length as an unsigned LEB128 varint, as
+    /// `write_varint_u64` does, followed by its UTF-8 bytes.
+    pub fn write_string_varint(&mut self, value: &str) -> Result<usize, BinaryError> {
+        Ok(self.write_varint_u64(value.len() as u64)? + self.write_string_raw(value)?)
+    }
+
+    pub fn write_delta_block(
+        &mut self,
+        base: i64,
+        values: &[i64],
+        delta_width: usize,
+    ) -> Result<usize, BinaryError> {
+        if delta_width == 0 || delta_width > 8 {
+            return Err(BinaryError::InvalidData(format!(
+                "unsupported delta width {}",
+                delta_width
+            )));
+        }
+
+        let shift = 64 - 8 * delta_width as u32;
+        let mut data = Vec::with_capacity(delta_width * values.len());
+        let mut previous = base;
+        for &value in values {
+            let delta = match value.checked_sub(previous) {
+                Some(d) if (d << shift) >> shift == d => d,
+                _ => {
+                    return Err(BinaryError::InvalidData(format!(
+                        "delta from {} to {} does not fit in {} bytes",
+                        previous, value, delta_width
+                    )))
+                }
+            };
+
+            let mut raw = delta.to_le_bytes();
+            B::apply(self.endian, &mut raw[..delta_width]);
+            data.extend_from_slice(&raw[..delta_width]);
+            previous = value;
+        }
+
+        self.write_bytes(&data)
+    }
+
+    pub fn write_bool_slice(&mut self, bits: &[bool]) -> Result<usize, BinaryError> {
+        let mut packed = vec![0u8; bits.len().div_ceil(8)];
+
+        for (i, _) in bits.iter().enumerate().filter(|(_, &bit)| bit) {
+            packed[i / 8] |= 1 << (i % 8);
+        }
+
+        Ok(self.write_length(bits.len())? + self.write_bytes(&packed)?)
+    }
+
+    pub fn write_bitmask_from_indices(
+        &mut self,
+        indices: &[usize],
+        bit_count: usize,
+    ) -> Result<usize, BinaryError> {
+        let mut mask = vec![0u8; bit_count.div_ceil(8)];
+
+        for &index in indices {
+            if index >= bit_count {
+                return Err(BinaryError::InvalidData(format!(
+                    "bit index {} out of range for a {}-bit mask",
+                    index, bit_count
+                )));
+            }
+
+            mask[index / 8] |= 1 << (index % 8);
+        }
+
+        self.write_bytes(&mask)
+    }
+
+    pub fn write_result_with<T, E, OkF, ErrF>(
+        &mut self,
+        value: &Result<T, E>,
+        ok_f: OkF,
+        err_f: ErrF,
+    ) -> Result<usize, BinaryError>
+    where
+        OkF: FnOnce(&mut Self, &T) -> Result<usize, BinaryError>,
+        ErrF: FnOnce(&mut Self, &E) -> Result<usize, BinaryError>,
+    {
+        match value {
+            Ok(v) => Ok(self.write_u8(0)? + ok_f(self, v)?),
+            Err(e) => Ok(self.write_u8(1)? + err_f(self, e)?),
+        }
+    }
+
+    pub fn write_string_pool(&mut self, pool: &StringPool) -> Result<usize, BinaryError> {
+        self.write_bytes(pool.as_bytes())
+    }
+
+    pub fn write_packed_10bit(&mut self, samples: &[u16]) -> Result<usize, BinaryError> {
+        if samples.len() % 4 != 0 {
+            return Err(BinaryError::InvalidData(format!(
+                "10-bit packed sample count {} is not a multiple of 4",
+                samples.len()
+            )));
+        }
+
+        let mut data = Vec::with_capacity(samples.len() / 4 * 5);
+        for group in samples.chunks(4) {
+            let mut low = 0u8;
+            for (i, &sample) in group.iter().enumerate() {
+                if sample > 0x3FF {
+                    return Err(BinaryError::InvalidData(format!(
+                        "sample {} does not fit in 10 bits",
+                        sample
+                    )));
+                }
+
+                data.push((sample >> 2) as u8);
+                low |= ((sample & 0x03) as u8) << (2 * i);
+            }
+            data.push(low);
+        }
+
+        self.write_bytes(&data)
+    }
+
+    pub fn write_packed_12bit(&mut self, samples: &[u16]) -> Result<usize, BinaryError> {
+        if samples.len() % 2 != 0 {
+            return Err(BinaryError::InvalidData(format!(
+                "12-bit packed sample count {} is not a multiple of 2",
+                samples.len()
+            )));
+        }
+
+        let mut data = Vec::with_capacity(samples.len() / 2 * 3);
+        for group in samples.chunks(2) {
+            if let Some(sample) = group.iter().find(|&&s| s > 0xFFF) {
+                return Err(BinaryError::InvalidData(format!(
+                    "sample {} does not fit in 12 bits",
+                    sample
+                )));
+            }
+
+            data.push((group[0] >> 4) as u8);
+            data.push((group[1] >> 4) as u8);
+            data.push(((group[1] & 0x0F) << 4) as u8 | (group[0] & 0x0F) as u8);
+        }
+
+        self.write_bytes(&data)
+    }
+
+    pub fn write_bool(&mut self, value: bool) -> Result<usize, BinaryError> {
+        self.write_u8(value as u8)
+    }
+
+    pub fn write_char(&mut self, value: char) -> Result<usize, BinaryError> {
+        self.write_u32(value as u32)
+    }
+
+    pub fn write_duration(&mut self, value: Duration) -> Result<usize, BinaryError> {
+        Ok(self.write_u64(value.as_secs())? + self.write_u32(value.subsec_nanos())?)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_system_time(&mut self, value: SystemTime) -> Result<usize, BinaryError> {
+        match value.duration_since(UNIX_EPOCH) {
+            Ok(since_epoch) => self.write_duration(since_epoch),
+            Err(e) => Err(BinaryError::InvalidData(format!(
+                "system time is {:?} before the epoch",
+                e.duration()
+            ))),
+        }
+    }
+
+    pub fn write_ip_addr(&mut self, value: IpAddr) -> Result<usize, BinaryError> {
+        match value {
+            IpAddr::V4(ip) => Ok(self.write_u8(4)? + self.write_bytes(&ip.octets())?),
+            IpAddr::V6(ip) => Ok(self.write_u8(6)? + self.write_bytes(&ip.octets())?),
+        }
+    }
+
+    pub fn write_socket_addr(&mut self, value: SocketAddr) -> Result<usize, BinaryError> {
+        Ok(self.write_ip_addr(value.ip())? + self.write_u16(value.port())?)
+    }
+
+    pub fn write_fixed_point(&mut self, value: i64, scale: u8) -> Result<usize, BinaryError> {
+        Ok(self.write_i64(value)? + self.write_u8(scale)?)
+    }
+
+    pub fn write_fixed_point_f64(&mut self, value: f64, scale: u8) -> Result<usize, BinaryError> {
+        let mantissa = BinaryWriter::to_fixed_point(value, scale)?;
+        self.write_fixed_point(mantissa, scale)
+    }
+
+    pub fn write_nonzero_u32(&mut self, value: NonZeroU32) -> Result<usize, BinaryError> {
+        self.write_u32(value.get())
+    }
+
+    pub fn write_nonzero_u64(&mut self, value: NonZeroU64) -> Result<usize, BinaryError> {
+        self.write_u64(value.get())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_value<T: Serialize>(&mut self, value: &T) -> Result<usize, BinaryError> {
+        let data = self.encode(value)?;
+
+        Ok(self.write_usize(data.len())? + self.write_bytes(&data)?)
+    }
+
+    // Encodes a value with this writer's codec.
+    #[cfg(feature = "std")]
+    fn encode<T: Serialize + ?Sized>(&self, value: &T) -> Result<Vec<u8>, BinaryError> {
+        match self.codec {
+            Codec::Bincode => Ok(serialize(value)?),
+            Codec::Fixed => {
+                let mut buffer = Memorystream::new()?;
+                let config = BinaryConfig {
+                    endian: self.endian,
+                    length_prefix: self.length_prefix,
+                    pad_byte: self.pad_byte,
+                    codec: self.codec,
+                    ..BinaryConfig::default()
+                };
+                value.serialize(&mut fixedcodec::Serializer::new(
+                    &mut BinaryWriter::<B>::from_parts(&mut buffer, config),
+                ))?;
+
+                Ok(buffer.into_vec())
+            }
+        }
+    }
+
+    write_slices! {
+        write_u16_slice => u16;
+        write_i16_slice => i16;
+        write_u32_slice => u32;
+        write_i32_slice => i32;
+        write_u64_slice => u64;
+        write_i64_slice => i64;
+        write_f32_slice => f32;
+        write_f64_slice => f64;
+    }
+
+    pub fn write_f32_array<const N: usize>(
+        &mut self,
+        values: &[f32; N],
+    ) -> Result<usize, BinaryError> {
+        self.write_f32_slice(values)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_vec<T: Serialize>(&mut self, items: &[T]) -> Result<usize, BinaryError> {
+        let mut written = self.write_length(items.len())?;
+
+        for item in items {
+            written += self.write_bytes(&self.encode(item)?)?;
+        }
+
+        Ok(written)
+    }
+
+    /// Writes `count` and then each item as the iterator yields it, without
+    /// collecting first. Fails with `CountMismatch` if the iterator yields a
+    /// different number of items, after writing the ones it did yield.
+    #[cfg(feature = "std")]
+    pub fn write_iter<T, I>(&mut self, count: usize, items: I) -> Result<usize, BinaryError>
+    where
+        T: Serialize,
+        I: IntoIterator<Item = T>,
+    {
+        let mut written = self.write_length(count)?;
+        let mut items = items.into_iter();
+
+        for found in 0..count {
+            match items.next() {
+                Some(item) => written += self.write_bytes(&self.encode(&item)?)?,
+                None => return Err(BinaryError::CountMismatch { expected: count, found }),
+            }
+        }
+
+        // The rest of an overlong iterator is not drained, as it may never end.
+        if items.next().is_some() {
+            return Err(BinaryError::CountMismatch { expected: count, found: count + 1 });
+        }
+
+        Ok(written)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_map<K: Serialize, V: Serialize>(
+        &mut self,
+        map: &HashMap<K, V>,
+    ) -> Result<usize, BinaryError> {
+        let mut written = self.write_length(map.len())?;
+
+        for (key, value) in map {
+            written += self.write_bytes(&self.encode(key)?)?;
+            written += self.write_bytes(&self.encode(value)?)?;
+        }
+
+        Ok(written)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_option<T: Serialize>(&mut self, value: &Option<T>) -> Result<usize, BinaryError> {
+        match value {
+            Some(v) => Ok(self.write_bool(true)? + self.write_bytes(&self.encode(v)?)?),
+            None => self.write_bool(false),
+        }
+    }
+
+    /// Writes a discriminant byte, 0 for `Ok` and 1 for `Err`, followed by
+    /// the payload.
+    #[cfg(feature = "std")]
+    pub fn write_result<T: Serialize, E: Serialize>(
+        &mut self,
+        value: &Result<T, E>,
+    ) -> Result<usize, BinaryError> {
+        match value {
+            Ok(v) => Ok(self.write_u8(0)? + self.write_bytes(&self.encode(v)?)?),
+            Err(e) => Ok(self.write_u8(1)? + self.write_bytes(&self.encode(e)?)?),
+        }
+    }
+
+    pub fn write_array<const N: usize>(&mut self, data: &[u8; N]) -> Result<usize, BinaryError> {
+        self.write_bytes(data)
+    }
+
+    pub fn write_bytes(&mut self, data: &[u8]) -> Result<usize, BinaryError> {
+        self.write_slice(data)
+    }
+
+    pub fn write_slice(&mut self, data: &[u8]) -> Result<usize, BinaryError> {
+        self.write_all(data)
+    }
+
+    pub fn write_byte_vec(&mut self, data: &[u8]) -> Result<usize, BinaryError> {
+        Ok(self.write_length(data.len())? + self.write_slice(data)?)
+    }
+
+    /// Writes `value` as length-prefixed UTF-8 JSON, for embedding
+    /// schema-less data in an otherwise typed format.
+    #[cfg(feature = "json")]
+    pub fn write_json(&mut self, value: &serde_json::Value) -> Result<usize, BinaryError> {
+        let bytes = serde_json::to_vec(value)?;
+        self.write_byte_vec(&bytes)
+    }
+
+    pub fn write_blobs(&mut self, blobs: &[Vec<u8>]) -> Result<usize, BinaryError> {
+        let mut written = self.write_length(blobs.len())?;
+
+        for blob in blobs {
+            written += self.write_byte_vec(blob)?;
+        }
+
+        Ok(written)
+    }
+
+    /// Writes a count followed by each string as `write_string` does.
+    pub fn write_string_vec<S: AsRef<str>>(&mut self, strings: &[S]) -> Result<usize, BinaryError> {
+        let mut written = self.write_length(strings.len())?;
+
+        for string in strings {
+            let string = string.as_ref();
+            written += self.write_length(string.len())? + self.write_string_raw(string)?;
+        }
+
+        Ok(written)
+    }
+
+    /// Writes a path losslessly as a platform tag followed by the native
+    /// encoding, so non-UTF-8 paths survive. Only readable on a platform
+    /// with the same encoding.
+    #[cfg(feature = "std")]
+    pub fn write_path<P: AsRef<Path>>(&mut self, path: P) -> Result<usize, BinaryError> {
+        let path = path.as_ref().as_os_str();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+
+            Ok(self.write_u8(PATH_TAG_UNIX)? + self.write_byte_vec(path.as_bytes())?)
+        }
+
+        #[cfg(windows)]
+        {
+            use std::os::windows::ffi::OsStrExt;
+
+            let units: Vec<u16> = path.encode_wide().collect();
+            Ok(self.write_u8(PATH_TAG_WINDOWS)?
+                + self.write_length(units.len())?
+                + self.write_u16_slice(&units)?)
+        }
+
+        #[cfg(not(any(unix, windows)))]
+        Err(BinaryError::InvalidData(format!(
+            "paths cannot be encoded on this platform: {:?}",
+            path
+        )))
+    }
+
+    pub fn write_magic(&mut self, magic: &[u8]) -> Result<usize, BinaryError> {
+        self.write_bytes(magic)
+    }
+
+    pub fn write_header(&mut self, header: &FileHeader) -> Result<usize, BinaryError> {
+        Ok(self.write_magic(&header.magic)?
+            + self.write_u16(header.version)?
+            + self.write_u16(header.flags)?)
+    }
+
+    pub fn write_ascii_int(
+        &mut self,
+        value: i64,
+        width: usize,
+        radix: u32,
+    ) -> Result<usize, BinaryError> {
+        if !(2..=36).contains(&radix) {
+            return Err(BinaryError::InvalidData(format!("unsupported radix {}", radix)));
+        }
+
+        let mut digits = Vec::new();
+        let mut magnitude = value.unsigned_abs();
+        loop {
+            let digit = core::char::from_digit((magnitude % radix as u64) as u32, radix).unwrap();
+            digits.push(digit as u8);
+            magnitude /= radix as u64;
+
+            if magnitude == 0 {
+                break;
+            }
+        }
+
+        let sign_len = if value < 0 { 1 } else { 0 };
+        if digits.len() + sign_len > width {
+            return Err(BinaryError::InvalidData(format!(
+                "{} does not fit in {} ASCII digits",
+                value, width
+            )));
+        }
+
+        let mut field = Vec::with_capacity(width);
+        if value < 0 {
+            field.push(b'-');
+        }
+        field.resize(width - digits.len(), b'0');
+        field.extend(digits.iter().rev());
+
+        self.write_bytes(&field)
+    }
+
+    pub fn write_padding(&mut self, count: usize) -> Result<usize, BinaryError> {
+        self.write_padding_with(count, self.pad_byte)
+    }
+
+    pub fn write_padding_with(&mut self, count: usize, pad: u8) -> Result<usize, BinaryError> {
+        self.write_bytes(&vec![pad; count])
+    }
+
+    pub fn align(&mut self, alignment: usize) -> Result<usize, BinaryError> {
+        self.align_with(alignment, self.pad_byte)
+    }
+
+    pub fn align_with(&mut self, alignment: usize, pad: u8) -> Result<usize, BinaryError> {
+        if alignment == 0 {
+            return Err(BinaryError::InvalidData("alignment must be non-zero".to_string()));
+        }
+
+        let misalignment = self.get_cur_pos()? % alignment;
+        if misalignment == 0 {
+            return Ok(0);
+        }
+
+        self.write_padding_with(alignment - misalignment, pad)
+    }
+
+    pub fn pad_to(&mut self, alignment: usize, fill: u8) -> Result<usize, BinaryError> {
+        if !alignment.is_power_of_two() {
+            return Err(BinaryError::InvalidData(format!(
+                "alignment {} is not a power of two",
+                alignment
+            )));
+        }
+
+        self.align_with(alignment, fill)
+    }
+
+    pub fn write_sized_exact<F>(&mut self, expected_len: usize, f: F) -> Result<usize, BinaryError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), BinaryError>,
+    {
+        let start = self.get_cur_pos()?;
+        f(self)?;
+        let written = self.get_cur_pos()? - start;
+
+        if written != expected_len {
+            return Err(BinaryError::InvalidData(format!(
+                "expected to write {} bytes, wrote {}",
+                expected_len, written
+            )));
+        }
+
+        Ok(written)
+    }
+
+    pub fn write_padded_to<F>(&mut self, len: usize, f: F) -> Result<usize, BinaryError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), BinaryError>,
+    {
+        let start = self.get_cur_pos()?;
+        f(self)?;
+        let written = self.get_cur_pos()? - start;
+
+        if written > len {
+            return Err(BinaryError::InvalidData(format!(
+                "wrote {} bytes, exceeding the padded size of {}",
+                written, len
+            )));
+        }
+
+        self.write_padding(len - written)?;
+        Ok(len)
+    }
+
+    /// Runs `f` once against a `NullStream` to measure its output, writes that size as a
+    /// usize, then runs `f` again against the real stream. `f` must write identical bytes
+    /// on both passes.
+    pub fn two_pass<F>(&mut self, mut f: F) -> Result<usize, BinaryError>
+    where
+        F: FnMut(&mut BinaryWriter<'_, B>) -> Result<(), BinaryError>,
+    {
+        let data_start = self.get_cur_pos()? + 8;
+
+        let mut sizing = NullStream::new();
+        sizing.seek(data_start)?;
+        let config = BinaryConfig {
+            endian: self.endian,
+            length_prefix: self.length_prefix,
+            pad_byte: self.pad_byte,
+            codec: self.codec,
+            ..BinaryConfig::default()
+        };
+        let mut sizing_writer = BinaryWriter::from_parts(&mut sizing, config);
+        f(&mut sizing_writer)?;
+        let size = sizing.len().saturating_sub(data_start);
+
+        self.write_usize(size)?;
+        f(self)?;
+
+        let written = self.get_cur_pos()? - data_start;
+        if written != size {
+            return Err(BinaryError::InvalidData(format!(
+                "two-pass write measured {} bytes but wrote {}",
+                size, written
+            )));
+        }
+
+        Ok(size)
+    }
+
+    /// Writes the body produced by `f` framed as a magic tag, a u32 length,
+    /// the body bytes and a CRC32 of the body. The body is assembled in
+    /// memory first, so nothing reaches the stream if `f` fails.
+    pub fn write_checked_block<F>(&mut self, f: F) -> Result<usize, BinaryError>
+    where
+        F: FnOnce(&mut BinaryWriter<'_, B>) -> Result<(), BinaryError>,
+    {
+        let mut buffer = Memorystream::new()?;
+        let config = BinaryConfig {
+            endian: self.endian,
+            length_prefix: self.length_prefix,
+            pad_byte: self.pad_byte,
+            codec: self.codec,
+            ..BinaryConfig::default()
+        };
+        let mut buffer_writer = BinaryWriter::from_parts(&mut buffer, config);
+        f(&mut buffer_writer)?;
+        let body = buffer.into_vec();
+
+        if body.len() > u32::MAX as usize {
+            return Err(BinaryError::InvalidData(format!(
+                "checked block body of {} bytes exceeds the u32 length field",
+                body.len()
+            )));
+        }
+
+        Ok(self.write_magic(&CHECKED_BLOCK_MAGIC)?
+            + self.write_u32(body.len() as u32)?
+            + self.write_bytes(&body)?
+            + self.write_u32(checksumstream::crc32(&body))?)
+    }
+
+    pub fn write_length_prefixed_buffered<F>(&mut self, width: usize, f: F) -> Result<usize, BinaryError>
+    where
+        F: FnOnce(&mut BinaryWriter<'_, B>) -> Result<(), BinaryError>,
+    {
+        let mut buffer = Memorystream::new()?;
+        let config = BinaryConfig {
+            endian: self.endian,
+            length_prefix: self.length_prefix,
+            pad_byte: self.pad_byte,
+            codec: self.codec,
+            ..BinaryConfig::default()
+        };
+        let mut buffer_writer = BinaryWriter::from_parts(&mut buffer, config);
+        f(&mut buffer_writer)?;
+        let data = buffer.replace_buffer(Vec::new());
+
+        let len = data.len() as u64;
+        let fits = match width {
+            1 => len <= u8::MAX as u64,
+            2 => len <= u16::MAX as u64,
+            4 => len <= u32::MAX as u64,
+            8 => true,
+            _ => {
+                return Err(BinaryError::InvalidData(format!(
+                    "unsupported length prefix width {}",
+                    width
+                )))
+            }
+        };
+        if !fits {
+            return Err(BinaryError::InvalidData(format!(
+                "length {} does not fit in a {}-byte prefix",
+                len, width
+            )));
+        }
+
+        let mut raw = len.to_le_bytes();
+        let prefix = &mut raw[..width];
+        B::apply(self.endian, prefix);
+        self.write_bytes(prefix)?;
+        self.write_bytes(&data)?;
+
+        Ok(width + len as usize)
+    }
+
+    pub fn write_placeholder_u32(&mut self) -> Result<usize, BinaryError> {
+        let position = self.get_cur_pos()?;
+        self.write_u32(0)?;
+
+        Ok(position)
+    }
+
+    pub fn patch_u32(&mut self, pos: usize, value: u32) -> Result<(), BinaryError> {
+        let end = self.get_cur_pos()?;
+        self.seek_to(pos)?;
+        self.write_u32(value)?;
+        self.seek_to(end)?;
+
+        Ok(())
+    }
+
+    /// Writes `tag`, a u32 length and the body written by `body`, so readers
+    /// that don't know the tag can skip it. The length is backfilled once the
+    /// body is written, which needs a seekable stream.
+    pub fn write_variant<F>(&mut self, tag: u32, body: F) -> Result<usize, BinaryError>
+    where
+        F: FnOnce(&mut Self) -> Result<(), BinaryError>,
+    {
+        self.write_u32(tag)?;
+        let len_pos = self.write_placeholder_u32()?;
+        body(self)?;
+
+        let len = self.get_cur_pos()? - (len_pos + 4);
+        if len > u32::MAX as usize {
+            return Err(BinaryError::InvalidData(format!(
+                "variant body of {} bytes exceeds the u32 length field",
+                len
+            )));
+        }
+        self.patch_u32(len_pos, len as u32)?;
+
+        Ok(8 + len)
+    }
+
+    pub fn write_ptr_placeholder(&mut self) -> Result<Reloc, BinaryError> {
+        let position = self.get_cur_pos()?;
+        self.write_usize(0)?;
+
+        Ok(Reloc { position })
+    }
+
+    pub fn resolve(&mut self, reloc: Reloc) -> Result<usize, BinaryError> {
+        let target = self.get_cur_pos()?;
+        self.relocations.push((reloc.position, target));
+
+        Ok(target)
+    }
+
+    pub fn apply_relocations(&mut self) -> Result<(), BinaryError> {
+        let end = self.get_cur_pos()?;
+
+        for (position, target) in core::mem::take(&mut self.relocations) {
+            self.seek_to(position)?;
+            self.write_usize(target)?;
+        }
+
+        self.seek_to(end)?;
+        Ok(())
+    }
+}
+
+// `budget` is what's left of the reader's byte budget; reads that would go
+// over it set `exceeded` so the failure can be reported as such. Likewise
+// `missing` records how many bytes a read wanted when the stream ran dry, and
+// `error` the stream's own failure.
+#[cfg(feature = "std")]
+struct StreamRead<'s> {
+    stream: &'s mut dyn Stream,
+    budget: usize,
+    read: usize,
+    exceeded: bool,
+    missing: Option<usize>,
+    error: Option<StreamError>,
+}
+
+#[cfg(feature = "std")]
+impl<'s> std::io::Read for StreamRead<'s> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if buf.len() > self.budget - self.read {
+            self.exceeded = true;
+            return Err(std::io::Error::other("byte budget exceeded"));
+        }
+
+        match self.stream.read(buf) {
+            Ok(0) if !buf.is_empty() => {
+                self.missing = Some(buf.len());
+                Ok(0)
+            }
+            Ok(read) => {
+                self.read += read;
+                Ok(read)
+            }
+            Err(e) => {
+                self.error = Some(e);
+                Err(std::io::Error::other(e))
+            }
         }
     }
 }
@@ -1,5 +1,7 @@
-use crate::{Stream, StreamError};
+use crate::{ByteSource, Stream, StreamError};
+use alloc::vec::Vec;
 
+#[derive(Clone)]
 pub struct Memorystream {
     buffer: Vec<u8>,
     position: usize,
@@ -12,44 +14,108 @@ impl Memorystream {
             position: 0,
         })
     }
+
+    pub fn with_capacity(capacity: usize) -> Memorystream {
+        Memorystream {
+            buffer: Vec::with_capacity(capacity),
+            position: 0,
+        }
+    }
+
+    pub fn from_vec(data: Vec<u8>) -> Memorystream {
+        Memorystream {
+            buffer: data,
+            position: 0,
+        }
+    }
+
+    pub fn replace_buffer(&mut self, new: Vec<u8>) -> Vec<u8> {
+        self.position = 0;
+        core::mem::replace(&mut self.buffer, new)
+    }
+
+    pub fn rewind(&mut self) {
+        self.position = 0;
+    }
+
+    /// The cursor, the same value `tell` reports.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Moves the cursor exactly as `seek` does. Positions past the end are
+    /// allowed; reads there return nothing and writes zero-fill the gap.
+    pub fn set_position(&mut self, position: usize) {
+        self.position = position;
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.buffer
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    /// Shortens the buffer to `len` bytes, moving the cursor back to the new
+    /// end if it was past it. Has no effect if `len` is not shorter.
+    pub fn truncate(&mut self, len: usize) {
+        self.buffer.truncate(len);
+        self.position = self.position.min(self.buffer.len());
+    }
+
+    /// Empties the buffer and rewinds, keeping the allocation for reuse.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.position = 0;
+    }
 }
 
 impl Stream for Memorystream {
-    fn write(&mut self, bytes: &Vec<u8>) -> Result<usize, StreamError> {
-        let bytes_to_end = self.buffer.len() - self.position;
-        if bytes.len() > bytes_to_end {
-            let bytes_out_of_buffer = bytes.len() - bytes_to_end;
-
-            for _ in 0..bytes_out_of_buffer {
-                self.buffer.push(0);
-            }
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        // An empty write must not zero-fill up to a cursor past the end.
+        if bytes.is_empty() {
+            return Ok(0);
         }
 
-        let mut idx = 0;
-        for i in self.position..self.position + bytes.len() {
-            self.buffer[i] = bytes[idx];
-            idx += 1;
+        // The cursor may have been seeked past the end; like a file, the
+        // gap up to it is zero-filled.
+        let end = self.position.checked_add(bytes.len()).ok_or(StreamError::WriteError)?;
+        if end > self.buffer.len() {
+            self.buffer.resize(end, 0);
         }
 
-        self.position += bytes.len();
+        self.buffer[self.position..end].copy_from_slice(bytes);
+
+        self.position = end;
 
         Ok(bytes.len())
     }
 
-    fn read(&mut self, buffer: &mut Vec<u8>) -> Result<usize, StreamError> {
-        if self.position + buffer.len() > self.buffer.len() {
-            return Err(StreamError::ReadError);
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        if self.position >= self.buffer.len() {
+            return Ok(0);
         }
 
-        let mut idx = 0;
-        for i in self.position..self.position + buffer.len() {
-            buffer[idx] = self.buffer[i];
-            idx += 1;
-        }
+        let end = self.buffer.len().min(self.position + buffer.len());
+        let read = end - self.position;
+        buffer[..read].copy_from_slice(&self.buffer[self.position..end]);
 
-        self.position += buffer.len();
+        self.position = end;
 
-        Ok(buffer.len())
+        Ok(read)
     }
 
     fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
@@ -57,7 +123,31 @@ impl Stream for Memorystream {
         Ok(self.position)
     }
 
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.position = self.buffer.len();
+        Ok(self.position)
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        Ok(self.buffer.len())
+    }
+
     fn tell(&mut self) -> Result<usize, StreamError> {
         Ok(self.position)
     }
+
+    fn truncate(&mut self, len: usize) -> Result<(), StreamError> {
+        Memorystream::truncate(self, len);
+        Ok(())
+    }
+
+    fn as_byte_source(&mut self) -> Option<&mut dyn ByteSource> {
+        Some(self)
+    }
+}
+
+impl ByteSource for Memorystream {
+    fn buffer(&self) -> &[u8] {
+        &self.buffer
+    }
 }
@@ -0,0 +1,106 @@
+use crate::memorystream::Memorystream;
+use crate::{Stream, StreamError};
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+/// What a `MockStream` does on one read or write call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MockAction {
+    /// Behave like the underlying buffer.
+    Succeed,
+    /// Transfer at most this many bytes; meant for reads.
+    ShortRead(usize),
+    /// Transfer at most this many bytes; meant for writes.
+    AcceptPartial(usize),
+    /// Fail the call with the given error, leaving the buffer untouched.
+    Fail(StreamError),
+}
+
+/// An in-memory stream that follows a script of `MockAction`s, for
+/// reproducing short reads, partial writes and stream failures in tests.
+/// Each read or write call takes the next action from the script in order;
+/// once the script runs out every call succeeds. Seeking and the other
+/// operations are never scripted.
+pub struct MockStream {
+    inner: Memorystream,
+    script: VecDeque<MockAction>,
+}
+
+impl MockStream {
+    pub fn new(data: Vec<u8>) -> MockStream {
+        MockStream {
+            inner: Memorystream::from_vec(data),
+            script: VecDeque::new(),
+        }
+    }
+
+    pub fn with_script<I: IntoIterator<Item = MockAction>>(data: Vec<u8>, script: I) -> MockStream {
+        MockStream {
+            inner: Memorystream::from_vec(data),
+            script: script.into_iter().collect(),
+        }
+    }
+
+    pub fn push_action(&mut self, action: MockAction) {
+        self.script.push_back(action);
+    }
+
+    /// The number of scripted actions not yet used.
+    pub fn remaining_actions(&self) -> usize {
+        self.script.len()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        self.inner.as_slice()
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.inner.into_vec()
+    }
+
+    fn next_limit(&mut self, requested: usize) -> Result<usize, StreamError> {
+        match self.script.pop_front() {
+            None | Some(MockAction::Succeed) => Ok(requested),
+            Some(MockAction::ShortRead(n)) | Some(MockAction::AcceptPartial(n)) => {
+                Ok(requested.min(n))
+            }
+            Some(MockAction::Fail(e)) => Err(e),
+        }
+    }
+}
+
+impl Stream for MockStream {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        let limit = self.next_limit(bytes.len())?;
+        self.inner.write(&bytes[..limit])
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        let limit = self.next_limit(buffer.len())?;
+        self.inner.read(&mut buffer[..limit])
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        self.inner.seek(to)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.inner.seek_end()
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        self.inner.stream_len()
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        self.inner.tell()
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.inner.flush()
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), StreamError> {
+        Stream::truncate(&mut self.inner, len)
+    }
+}
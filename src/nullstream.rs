@@ -0,0 +1,73 @@
+use crate::{Stream, StreamError};
+
+#[derive(Default)]
+pub struct NullStream {
+    position: usize,
+    len: usize,
+    written: usize,
+}
+
+pub type SinkStream = NullStream;
+
+impl NullStream {
+    pub fn new() -> NullStream {
+        NullStream::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn bytes_written(&self) -> usize {
+        self.written
+    }
+}
+
+impl Stream for NullStream {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        if bytes.is_empty() {
+            return Ok(0);
+        }
+
+        self.position += bytes.len();
+        self.len = self.len.max(self.position);
+        self.written += bytes.len();
+
+        Ok(bytes.len())
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        if buffer.is_empty() {
+            return Ok(0);
+        }
+
+        Err(StreamError::ReadError)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        self.position = to;
+        Ok(self.position)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.position = self.len;
+        Ok(self.position)
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        Ok(self.len)
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        Ok(self.position)
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), StreamError> {
+        self.len = self.len.min(len);
+        Ok(())
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{BinaryError, BinaryReader, BinaryWriter};
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// The built-in integer and float types, each knowing its own width. The
+/// trait is sealed, so generic code bounded on it only ever sees the
+/// fixed-width encodings defined here.
+pub trait BinaryNum: Sized + sealed::Sealed {
+    const SIZE: usize;
+
+    fn read_from(reader: &mut BinaryReader) -> Result<Self, BinaryError>;
+    fn write_to(self, writer: &mut BinaryWriter) -> Result<usize, BinaryError>;
+}
+
+/// Another name for `BinaryNum`, used by `read_number` and `write_number`.
+pub use self::BinaryNum as Primitive;
+
+macro_rules! impl_binary_num {
+    ($($ty:ty => $read:ident, $write:ident;)*) => {
+        $(
+            impl BinaryNum for $ty {
+                const SIZE: usize = core::mem::size_of::<$ty>();
+
+                fn read_from(reader: &mut BinaryReader) -> Result<Self, BinaryError> {
+                    reader.$read()
+                }
+
+                fn write_to(self, writer: &mut BinaryWriter) -> Result<usize, BinaryError> {
+                    writer.$write(self)
+                }
+            }
+
+            impl sealed::Sealed for $ty {}
+        )*
+    };
+}
+
+impl_binary_num! {
+    u8 => read_u8, write_u8;
+    i8 => read_i8, write_i8;
+    u16 => read_u16, write_u16;
+    i16 => read_i16, write_i16;
+    u32 => read_u32, write_u32;
+    i32 => read_i32, write_i32;
+    u64 => read_u64, write_u64;
+    i64 => read_i64, write_i64;
+    u128 => read_u128, write_u128;
+    i128 => read_i128, write_i128;
+    usize => read_usize, write_usize;
+    isize => read_isize, write_isize;
+    f32 => read_f32, write_f32;
+    f64 => read_f64, write_f64;
+}
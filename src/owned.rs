@@ -0,0 +1,74 @@
+use crate::memorystream::Memorystream;
+use crate::slicestream::SliceStream;
+use crate::{BinaryConfig, BinaryReader, BinaryWriter, Endian};
+use alloc::vec::Vec;
+
+/// A reader that owns its stream over a byte slice, returned by
+/// `BinaryReader::from_bytes`. The position is kept between `reader` calls,
+/// so `bytes.reader().read_u32()` reads successive values.
+pub struct OwnedBinaryReader<'b> {
+    stream: SliceStream<'b>,
+    config: BinaryConfig,
+}
+
+impl<'b> OwnedBinaryReader<'b> {
+    pub(crate) fn new(bytes: &'b [u8]) -> OwnedBinaryReader<'b> {
+        OwnedBinaryReader {
+            stream: SliceStream::new(bytes),
+            config: BinaryConfig::default(),
+        }
+    }
+
+    pub fn with_endian(mut self, endian: Endian) -> OwnedBinaryReader<'b> {
+        self.config.endian = endian;
+        self
+    }
+
+    pub fn with_config(mut self, config: BinaryConfig) -> OwnedBinaryReader<'b> {
+        self.config = config;
+        self
+    }
+
+    pub fn reader(&mut self) -> BinaryReader<'_> {
+        BinaryReader::with_config(&mut self.stream, self.config)
+    }
+
+    /// The bytes not yet read.
+    pub fn remaining(&self) -> &'b [u8] {
+        self.stream.remaining()
+    }
+}
+
+/// A writer that owns a fresh in-memory buffer, returned by
+/// `BinaryWriter::to_vec`. `finish` hands back the bytes written.
+pub struct OwnedBinaryWriter {
+    stream: Memorystream,
+    config: BinaryConfig,
+}
+
+impl OwnedBinaryWriter {
+    pub(crate) fn new() -> OwnedBinaryWriter {
+        OwnedBinaryWriter {
+            stream: Memorystream::from_vec(Vec::new()),
+            config: BinaryConfig::default(),
+        }
+    }
+
+    pub fn with_endian(mut self, endian: Endian) -> OwnedBinaryWriter {
+        self.config.endian = endian;
+        self
+    }
+
+    pub fn with_config(mut self, config: BinaryConfig) -> OwnedBinaryWriter {
+        self.config = config;
+        self
+    }
+
+    pub fn writer(&mut self) -> BinaryWriter<'_> {
+        BinaryWriter::with_config(&mut self.stream, self.config)
+    }
+
+    pub fn finish(self) -> Vec<u8> {
+        self.stream.into_vec()
+    }
+}
@@ -0,0 +1,41 @@
+use core::ops::{Deref, DerefMut};
+
+/// Borrows a reader or writer and seeks it back to where it was when the
+/// guard was taken once the guard is dropped, including on early returns.
+/// The guard dereferences to the borrowed reader or writer.
+pub struct PositionGuard<'g, T> {
+    inner: &'g mut T,
+    position: usize,
+    restore: fn(&mut T, usize),
+}
+
+impl<'g, T> PositionGuard<'g, T> {
+    pub(crate) fn new(inner: &'g mut T, position: usize, restore: fn(&mut T, usize)) -> Self {
+        PositionGuard { inner, position, restore }
+    }
+
+    /// The position the cursor is restored to on drop.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<'g, T> Deref for PositionGuard<'g, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.inner
+    }
+}
+
+impl<'g, T> DerefMut for PositionGuard<'g, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.inner
+    }
+}
+
+impl<'g, T> Drop for PositionGuard<'g, T> {
+    fn drop(&mut self) {
+        (self.restore)(self.inner, self.position);
+    }
+}
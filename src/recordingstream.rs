@@ -0,0 +1,67 @@
+use crate::{Stream, StreamError};
+use alloc::vec::Vec;
+
+/// Passes every call through to the inner stream and keeps a copy of each
+/// byte read, in the order it was read. Wrapping `into_recording()` in a
+/// `SliceStream` replays the session: a fresh reader over it sees the same
+/// bytes as a parse that read straight through, without the source they came
+/// from. Writes are not recorded, and bytes read again after a seek are
+/// recorded again.
+pub struct RecordingStream<S: Stream> {
+    inner: S,
+    recording: Vec<u8>,
+}
+
+impl<S: Stream> RecordingStream<S> {
+    pub fn new(inner: S) -> RecordingStream<S> {
+        RecordingStream {
+            inner,
+            recording: Vec::new(),
+        }
+    }
+
+    pub fn recording(&self) -> &[u8] {
+        &self.recording
+    }
+
+    pub fn into_recording(self) -> Vec<u8> {
+        self.recording
+    }
+}
+
+impl<S: Stream> Stream for RecordingStream<S> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        self.inner.write(bytes)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        let read = self.inner.read(buffer)?;
+        self.recording.extend_from_slice(&buffer[..read]);
+
+        Ok(read)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        self.inner.seek(to)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.inner.seek_end()
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        self.inner.stream_len()
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        self.inner.tell()
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.inner.flush()
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), StreamError> {
+        self.inner.truncate(len)
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{Stream, StreamError};
+use std::thread;
+use std::time::Duration;
+
+/// Retries reads, writes and flushes of the inner stream that fail with a
+/// retryable error, sleeping between attempts with a doubling backoff. Other
+/// operations pass straight through. By default `ReadError` and `WriteError`
+/// are retryable and the first retry waits 10 ms.
+pub struct RetryStream<S: Stream> {
+    inner: S,
+    max_retries: u32,
+    backoff: Duration,
+    retryable: Vec<StreamError>,
+    retries: u64,
+}
+
+impl<S: Stream> RetryStream<S> {
+    pub fn new(inner: S, max_retries: u32) -> RetryStream<S> {
+        RetryStream {
+            inner,
+            max_retries,
+            backoff: Duration::from_millis(10),
+            retryable: vec![StreamError::ReadError, StreamError::WriteError],
+            retries: 0,
+        }
+    }
+
+    /// Sets the wait before the first retry; each further retry waits twice
+    /// as long as the one before.
+    pub fn with_backoff(mut self, backoff: Duration) -> RetryStream<S> {
+        self.backoff = backoff;
+        self
+    }
+
+    pub fn with_retryable(mut self, retryable: &[StreamError]) -> RetryStream<S> {
+        self.retryable = retryable.to_vec();
+        self
+    }
+
+    /// The total number of retries made so far.
+    pub fn retries(&self) -> u64 {
+        self.retries
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn retry<T, F>(&mut self, mut op: F) -> Result<T, StreamError>
+    where
+        F: FnMut(&mut S) -> Result<T, StreamError>,
+    {
+        let mut delay = self.backoff;
+        let mut attempt = 0;
+
+        loop {
+            match op(&mut self.inner) {
+                Err(e) if attempt < self.max_retries && self.retryable.contains(&e) => {
+                    attempt += 1;
+                    self.retries += 1;
+                    thread::sleep(delay);
+                    delay = delay.saturating_mul(2);
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl<S: Stream> Stream for RetryStream<S> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        self.retry(|inner| inner.write(bytes))
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        self.retry(|inner| inner.read(buffer))
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        self.inner.seek(to)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.inner.seek_end()
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        self.inner.stream_len()
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        self.inner.tell()
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.retry(|inner| inner.flush())
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), StreamError> {
+        self.inner.truncate(len)
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{BinaryReader, Codec, Endian, LengthPrefix, Stream, StreamError};
+
+// The parent's stream, restricted to the absolute range `start..end`.
+struct Region<'p> {
+    inner: &'p mut dyn Stream,
+    start: usize,
+    end: usize,
+    position: usize,
+}
+
+impl<'p> Stream for Region<'p> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        if bytes.is_empty() {
+            return Ok(0);
+        }
+
+        Err(StreamError::WriteError)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        let available = self.end.saturating_sub(self.position);
+        let len = buffer.len().min(available);
+
+        let read = self.inner.read(&mut buffer[..len])?;
+        self.position += read;
+
+        Ok(read)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        if to < self.start || to > self.end {
+            return Err(StreamError::SeekError);
+        }
+
+        self.position = self.inner.seek(to)?;
+        Ok(self.position)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.seek(self.end)
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        Ok(self.end)
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        Ok(self.position)
+    }
+}
+
+/// A read-only view of the next `len` bytes of a reader's stream. Reading
+/// past the end of the region fails with `UnexpectedEof`, and dropping the
+/// scope moves the parent to the end of the region however much of it was
+/// read. Positions are the parent's absolute offsets.
+pub struct ScopedReader<'p> {
+    region: Region<'p>,
+    endian: Endian,
+    length_prefix: LengthPrefix,
+    codec: Codec,
+    max_alloc: usize,
+    depth: usize,
+    max_depth: usize,
+}
+
+impl<'p> ScopedReader<'p> {
+    pub(crate) fn new(parent: &'p mut BinaryReader, start: usize, len: usize) -> ScopedReader<'p> {
+        ScopedReader {
+            endian: parent.endian,
+            length_prefix: parent.length_prefix,
+            codec: parent.codec,
+            max_alloc: parent.max_alloc,
+            depth: parent.depth,
+            max_depth: parent.max_depth,
+            region: Region {
+                inner: &mut *parent.stream,
+                start,
+                end: start + len,
+                position: start,
+            },
+        }
+    }
+
+    pub fn start(&self) -> usize {
+        self.region.start
+    }
+
+    pub fn end(&self) -> usize {
+        self.region.end
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.region.end - self.region.position
+    }
+
+    /// A reader over the region that shares the parent's settings.
+    pub fn reader(&mut self) -> BinaryReader<'_> {
+        let mut reader = BinaryReader::with_endian(&mut self.region, self.endian);
+        reader.length_prefix = self.length_prefix;
+        reader.codec = self.codec;
+        reader.max_alloc = self.max_alloc;
+        reader.depth = self.depth;
+        reader.max_depth = self.max_depth;
+
+        reader
+    }
+}
+
+impl<'p> Drop for ScopedReader<'p> {
+    fn drop(&mut self) {
+        let _ = self.region.inner.seek(self.region.end);
+    }
+}
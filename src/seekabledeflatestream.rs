@@ -0,0 +1,329 @@
+use crate::streamio::StreamIo;
+use crate::{Stream, StreamError};
+use flate2::read::DeflateDecoder;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::convert::{TryFrom, TryInto};
+use std::io::{Read, Write};
+
+const INDEX_MAGIC: [u8; 4] = *b"SDFX";
+
+// Block size (u32), uncompressed length (u64), block count (u64), magic.
+const TRAILER_LEN: usize = 4 + 8 + 8 + 4;
+
+// Compressed offset and length (u64 each) per block.
+const INDEX_ENTRY_LEN: usize = 16;
+
+#[derive(Clone, Copy)]
+struct Block {
+    offset: u64,
+    len: u64,
+}
+
+enum Mode {
+    Compress { pending: Vec<u8>, level: Compression, start: u64 },
+    Decompress { position: usize, cached: Option<(usize, Vec<u8>)> },
+}
+
+/// Deflate compression split into independent blocks of `block_size`
+/// uncompressed bytes, so reads can seek by uncompressed offset and only
+/// decompress the blocks they touch.
+///
+/// The layout is each block as raw deflate, one after the other, followed by
+/// an index of each block's compressed offset and length, then a trailer of
+/// the block size, the uncompressed length, the block count and the magic
+/// `SDFX`, all little-endian. Block offsets are absolute positions in the
+/// inner stream, so the blocks can follow a header of their own. Like
+/// `DeflateStream`, a stream only works in the direction it was created for,
+/// and `finish` must be called once writing is done or the last block and the
+/// index are never written.
+pub struct SeekableDeflateStream<S: Stream> {
+    inner: StreamIo<S>,
+    block_size: usize,
+    blocks: Vec<Block>,
+    len: usize,
+    decompressed: usize,
+    mode: Mode,
+}
+
+impl<S: Stream> SeekableDeflateStream<S> {
+    pub fn compress(inner: S, block_size: usize) -> Result<SeekableDeflateStream<S>, StreamError> {
+        SeekableDeflateStream::compress_with_level(inner, block_size, Compression::default())
+    }
+
+    pub fn compress_with_level(
+        mut inner: S,
+        block_size: usize,
+        level: Compression,
+    ) -> Result<SeekableDeflateStream<S>, StreamError> {
+        if block_size == 0 || block_size > u32::MAX as usize {
+            return Err(StreamError::WriteError);
+        }
+        let start = inner.tell().unwrap_or(0) as u64;
+
+        Ok(SeekableDeflateStream {
+            inner: StreamIo::new(inner),
+            block_size,
+            blocks: Vec::new(),
+            len: 0,
+            decompressed: 0,
+            mode: Mode::Compress {
+                pending: Vec::with_capacity(block_size),
+                level,
+                start,
+            },
+        })
+    }
+
+    /// Opens compressed data written by this type, reading the index from
+    /// the end of `inner`.
+    pub fn decompress(mut inner: S) -> Result<SeekableDeflateStream<S>, StreamError> {
+        let total = inner.stream_len()?;
+        if total < TRAILER_LEN {
+            return Err(StreamError::ReadError);
+        }
+
+        let mut inner = StreamIo::new(inner);
+        let mut trailer = [0u8; TRAILER_LEN];
+        inner.get_mut().seek(total - TRAILER_LEN)?;
+        inner.read_exact(&mut trailer).map_err(|_| StreamError::ReadError)?;
+        if trailer[20..] != INDEX_MAGIC {
+            return Err(StreamError::ReadError);
+        }
+
+        let block_size = u32::from_le_bytes(trailer[..4].try_into().unwrap()) as usize;
+        let len = u64::from_le_bytes(trailer[4..12].try_into().unwrap());
+        let count = u64::from_le_bytes(trailer[12..20].try_into().unwrap());
+
+        let index_len = usize::try_from(count)
+            .ok()
+            .and_then(|count| count.checked_mul(INDEX_ENTRY_LEN))
+            .filter(|&index_len| index_len <= total - TRAILER_LEN)
+            .ok_or(StreamError::ReadError)?;
+        let len = usize::try_from(len).map_err(|_| StreamError::ReadError)?;
+        if block_size == 0 || len.div_ceil(block_size) != index_len / INDEX_ENTRY_LEN {
+            return Err(StreamError::ReadError);
+        }
+
+        let index_start = total - TRAILER_LEN - index_len;
+        let mut index = vec![0u8; index_len];
+        inner.get_mut().seek(index_start)?;
+        inner.read_exact(&mut index).map_err(|_| StreamError::ReadError)?;
+
+        let blocks: Vec<Block> = index
+            .chunks_exact(INDEX_ENTRY_LEN)
+            .map(|entry| Block {
+                offset: u64::from_le_bytes(entry[..8].try_into().unwrap()),
+                len: u64::from_le_bytes(entry[8..].try_into().unwrap()),
+            })
+            .collect();
+
+        // The index comes from the input, so make sure the blocks follow one
+        // another and end before it does before trusting their lengths.
+        let mut next = blocks.first().map_or(0, |block| block.offset);
+        for block in &blocks {
+            if block.offset != next {
+                return Err(StreamError::ReadError);
+            }
+            next = block.offset.checked_add(block.len).ok_or(StreamError::ReadError)?;
+        }
+        if next > index_start as u64 {
+            return Err(StreamError::ReadError);
+        }
+
+        Ok(SeekableDeflateStream {
+            inner,
+            block_size,
+            blocks,
+            len,
+            decompressed: 0,
+            mode: Mode::Decompress {
+                position: 0,
+                cached: None,
+            },
+        })
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.block_size
+    }
+
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// How many times a block has been decompressed, for checking that reads
+    /// only touch the blocks they need.
+    pub fn blocks_decompressed(&self) -> usize {
+        self.decompressed
+    }
+
+    /// Compresses any buffered bytes as the last block, writes the index and
+    /// trailer, and hands back the inner stream.
+    pub fn finish(mut self) -> Result<S, StreamError> {
+        if let Mode::Compress { .. } = self.mode {
+            self.write_block()?;
+
+            let mut index = Vec::with_capacity(self.blocks.len() * INDEX_ENTRY_LEN + TRAILER_LEN);
+            for block in &self.blocks {
+                index.extend_from_slice(&block.offset.to_le_bytes());
+                index.extend_from_slice(&block.len.to_le_bytes());
+            }
+            index.extend_from_slice(&(self.block_size as u32).to_le_bytes());
+            index.extend_from_slice(&(self.len as u64).to_le_bytes());
+            index.extend_from_slice(&(self.blocks.len() as u64).to_le_bytes());
+            index.extend_from_slice(&INDEX_MAGIC);
+
+            self.inner.write_all(&index).map_err(|_| StreamError::WriteError)?;
+            self.inner.flush().map_err(|_| StreamError::WriteError)?;
+        }
+
+        Ok(self.inner.into_inner())
+    }
+
+    // Compresses the pending bytes, if any, as a block of their own.
+    fn write_block(&mut self) -> Result<(), StreamError> {
+        let (pending, level, start) = match self.mode {
+            Mode::Compress {
+                ref mut pending,
+                level,
+                start,
+            } if !pending.is_empty() => (pending, level, start),
+            _ => return Ok(()),
+        };
+
+        let mut encoder = DeflateEncoder::new(Vec::new(), level);
+        encoder.write_all(pending).map_err(|_| StreamError::WriteError)?;
+        let compressed = encoder.finish().map_err(|_| StreamError::WriteError)?;
+        pending.clear();
+
+        let offset = match self.blocks.last() {
+            Some(last) => last.offset + last.len,
+            None => start,
+        };
+        self.inner.write_all(&compressed).map_err(|_| StreamError::WriteError)?;
+        self.blocks.push(Block {
+            offset,
+            len: compressed.len() as u64,
+        });
+
+        Ok(())
+    }
+
+    // Decompresses block `number` into the cache unless it's already there.
+    fn load_block(&mut self, number: usize) -> Result<(), StreamError> {
+        if let Mode::Decompress { cached: Some((cached, _)), .. } = self.mode {
+            if cached == number {
+                return Ok(());
+            }
+        }
+
+        let block = self.blocks[number];
+        let offset = usize::try_from(block.offset).map_err(|_| StreamError::ReadError)?;
+        self.inner.get_mut().seek(offset)?;
+
+        // Neither size is allocated up front: the compressed bytes are only
+        // as many as the stream holds, and decompression stops one byte past
+        // the expected size.
+        let mut compressed = Vec::new();
+        (&mut self.inner)
+            .take(block.len)
+            .read_to_end(&mut compressed)
+            .map_err(|_| StreamError::ReadError)?;
+        if compressed.len() as u64 != block.len {
+            return Err(StreamError::ReadError);
+        }
+
+        let expected = self.block_size.min(self.len - number * self.block_size);
+        let mut data = Vec::new();
+        DeflateDecoder::new(&compressed[..])
+            .take(expected as u64 + 1)
+            .read_to_end(&mut data)
+            .map_err(|_| StreamError::ReadError)?;
+        if data.len() != expected {
+            return Err(StreamError::ReadError);
+        }
+
+        self.decompressed += 1;
+        if let Mode::Decompress { ref mut cached, .. } = self.mode {
+            *cached = Some((number, data));
+        }
+
+        Ok(())
+    }
+}
+
+impl<S: Stream> Stream for SeekableDeflateStream<S> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        let room = match self.mode {
+            Mode::Compress { ref pending, .. } => self.block_size - pending.len(),
+            Mode::Decompress { .. } if bytes.is_empty() => return Ok(0),
+            Mode::Decompress { .. } => return Err(StreamError::WriteError),
+        };
+
+        let len = bytes.len().min(room);
+        if let Mode::Compress { ref mut pending, .. } = self.mode {
+            pending.extend_from_slice(&bytes[..len]);
+            if pending.len() == self.block_size {
+                self.write_block()?;
+            }
+        }
+        self.len += len;
+
+        Ok(len)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        let position = match self.mode {
+            Mode::Compress { .. } if buffer.is_empty() => return Ok(0),
+            Mode::Compress { .. } => return Err(StreamError::ReadError),
+            Mode::Decompress { position, .. } => position,
+        };
+        if buffer.is_empty() || position >= self.len {
+            return Ok(0);
+        }
+
+        let number = position / self.block_size;
+        self.load_block(number)?;
+
+        if let Mode::Decompress { ref mut position, cached: Some((_, ref data)) } = self.mode {
+            let start = *position - number * self.block_size;
+            let len = buffer.len().min(data.len() - start);
+            buffer[..len].copy_from_slice(&data[start..start + len]);
+            *position += len;
+
+            return Ok(len);
+        }
+
+        Err(StreamError::ReadError)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        match self.mode {
+            Mode::Decompress { ref mut position, .. } if to <= self.len => {
+                *position = to;
+                Ok(to)
+            }
+            _ => Err(StreamError::SeekError),
+        }
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.seek(self.len)
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        Ok(self.len)
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        match self.mode {
+            Mode::Compress { .. } => Ok(self.len),
+            Mode::Decompress { position, .. } => Ok(position),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.inner.flush().map_err(|_| StreamError::WriteError)
+    }
+}
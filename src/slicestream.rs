@@ -0,0 +1,68 @@
+use crate::{ByteSource, Stream, StreamError};
+
+pub struct SliceStream<'a> {
+    buffer: &'a [u8],
+    position: usize,
+}
+
+impl<'a> SliceStream<'a> {
+    pub fn new(buffer: &'a [u8]) -> SliceStream<'a> {
+        SliceStream {
+            buffer,
+            position: 0,
+        }
+    }
+
+    pub fn remaining(&self) -> &'a [u8] {
+        let start = self.position.min(self.buffer.len());
+        &self.buffer[start..]
+    }
+}
+
+impl<'a> Stream for SliceStream<'a> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        if bytes.is_empty() {
+            return Ok(0);
+        }
+
+        Err(StreamError::WriteError)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        let available = self.remaining();
+        let read = available.len().min(buffer.len());
+        buffer[..read].copy_from_slice(&available[..read]);
+
+        self.position += read;
+
+        Ok(read)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        self.position = to;
+        Ok(self.position)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.position = self.buffer.len();
+        Ok(self.position)
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        Ok(self.buffer.len())
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        Ok(self.position)
+    }
+
+    fn as_byte_source(&mut self) -> Option<&mut dyn ByteSource> {
+        Some(self)
+    }
+}
+
+impl<'a> ByteSource for SliceStream<'a> {
+    fn buffer(&self) -> &[u8] {
+        self.buffer
+    }
+}
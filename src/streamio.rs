@@ -0,0 +1,44 @@
+use crate::{Stream, StreamError};
+use std::io;
+
+pub struct StreamIo<S: Stream> {
+    inner: S,
+}
+
+impl<S: Stream> StreamIo<S> {
+    pub fn new(inner: S) -> StreamIo<S> {
+        StreamIo { inner }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+fn to_io_error(error: StreamError) -> io::Error {
+    io::Error::other(error)
+}
+
+impl<S: Stream> io::Read for StreamIo<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf).map_err(to_io_error)
+    }
+}
+
+impl<S: Stream> io::Write for StreamIo<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write(buf).map_err(to_io_error)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush().map_err(to_io_error)
+    }
+}
@@ -0,0 +1,88 @@
+use crate::BinaryError;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+#[derive(Default)]
+pub struct StringPool {
+    data: Vec<u8>,
+    offsets: BTreeMap<String, usize>,
+}
+
+impl StringPool {
+    pub fn new() -> StringPool {
+        StringPool::default()
+    }
+
+    pub fn intern(&mut self, value: &str) -> Result<usize, BinaryError> {
+        if let Some(&offset) = self.offsets.get(value) {
+            return Ok(offset);
+        }
+
+        if value.as_bytes().contains(&0) {
+            return Err(BinaryError::InvalidData(format!(
+                "string {:?} contains an interior NUL",
+                value
+            )));
+        }
+
+        let offset = self.data.len();
+        self.data.extend_from_slice(value.as_bytes());
+        self.data.push(0);
+        self.offsets.insert(value.to_string(), offset);
+
+        Ok(offset)
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+pub struct StringTable {
+    data: Vec<u8>,
+}
+
+impl StringTable {
+    pub fn from_bytes(data: Vec<u8>) -> StringTable {
+        StringTable { data }
+    }
+
+    pub fn get(&self, offset: usize) -> Result<&str, BinaryError> {
+        if offset >= self.data.len() {
+            return Err(BinaryError::InvalidData(format!(
+                "string offset {} out of range for a {}-byte table",
+                offset,
+                self.data.len()
+            )));
+        }
+
+        let tail = &self.data[offset..];
+        let end = match tail.iter().position(|&b| b == 0) {
+            Some(end) => end,
+            None => {
+                return Err(BinaryError::InvalidData(format!(
+                    "string at offset {} is not NUL-terminated",
+                    offset
+                )))
+            }
+        };
+
+        match core::str::from_utf8(&tail[..end]) {
+            Ok(s) => Ok(s),
+            Err(_) => Err(BinaryError::InvalidData(format!(
+                "string at offset {} is not valid UTF-8",
+                offset
+            ))),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+// The type tag written before each value in the self-describing encoding.
+pub(crate) const BOOL: u8 = 0x01;
+pub(crate) const U8: u8 = 0x02;
+pub(crate) const U32: u8 = 0x03;
+pub(crate) const U64: u8 = 0x04;
+pub(crate) const I32: u8 = 0x05;
+pub(crate) const I64: u8 = 0x06;
+pub(crate) const F32: u8 = 0x07;
+pub(crate) const F64: u8 = 0x08;
+pub(crate) const STR: u8 = 0x09;
+pub(crate) const BYTES: u8 = 0x0A;
+
+/// A value read from the self-describing encoding, where each value is
+/// preceded by a one-byte type tag. Strings and bytes are length-prefixed
+/// with the reader's `LengthPrefix`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TaggedValue {
+    Bool(bool),
+    U8(u8),
+    U32(u32),
+    U64(u64),
+    I32(i32),
+    I64(i64),
+    F32(f32),
+    F64(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+}
+
+impl TaggedValue {
+    pub fn tag(&self) -> u8 {
+        match self {
+            TaggedValue::Bool(_) => BOOL,
+            TaggedValue::U8(_) => U8,
+            TaggedValue::U32(_) => U32,
+            TaggedValue::U64(_) => U64,
+            TaggedValue::I32(_) => I32,
+            TaggedValue::I64(_) => I64,
+            TaggedValue::F32(_) => F32,
+            TaggedValue::F64(_) => F64,
+            TaggedValue::Str(_) => STR,
+            TaggedValue::Bytes(_) => BYTES,
+        }
+    }
+}
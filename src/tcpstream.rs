@@ -0,0 +1,60 @@
+use crate::{Stream, StreamError};
+use std::io::prelude::*;
+use std::net::TcpStream;
+
+pub struct TcpStreamWrapper {
+    stream: TcpStream,
+}
+
+impl TcpStreamWrapper {
+    pub fn new(stream: TcpStream) -> TcpStreamWrapper {
+        TcpStreamWrapper { stream }
+    }
+
+    pub fn get_ref(&self) -> &TcpStream {
+        &self.stream
+    }
+
+    pub fn into_inner(self) -> TcpStream {
+        self.stream
+    }
+}
+
+impl Stream for TcpStreamWrapper {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        match self.stream.write_all(bytes) {
+            Ok(_) => Ok(bytes.len()),
+            Err(_) => Err(StreamError::WriteError),
+        }
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        match self.stream.read(buffer) {
+            Ok(res) => Ok(res),
+            Err(_) => Err(StreamError::ReadError),
+        }
+    }
+
+    fn seek(&mut self, _to: usize) -> Result<usize, StreamError> {
+        Err(StreamError::SeekError)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        Err(StreamError::SeekError)
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        Err(StreamError::SeekError)
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        Err(StreamError::TellError)
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        match self.stream.flush() {
+            Ok(_) => Ok(()),
+            Err(_) => Err(StreamError::WriteError),
+        }
+    }
+}
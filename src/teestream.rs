@@ -0,0 +1,71 @@
+use crate::{Stream, StreamError};
+
+pub struct TeeStream<A: Stream, B: Stream> {
+    primary: A,
+    secondary: B,
+}
+
+impl<A: Stream, B: Stream> TeeStream<A, B> {
+    pub fn new(primary: A, secondary: B) -> TeeStream<A, B> {
+        TeeStream { primary, secondary }
+    }
+
+    pub fn primary(&self) -> &A {
+        &self.primary
+    }
+
+    pub fn secondary(&self) -> &B {
+        &self.secondary
+    }
+
+    pub fn into_inner(self) -> (A, B) {
+        (self.primary, self.secondary)
+    }
+}
+
+impl<A: Stream, B: Stream> Stream for TeeStream<A, B> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        let written = self.primary.write(bytes)?;
+        if self.secondary.write(&bytes[..written])? != written {
+            return Err(StreamError::WriteError);
+        }
+
+        Ok(written)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        self.primary.read(buffer)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        let position = self.primary.seek(to)?;
+        self.secondary.seek(position)?;
+
+        Ok(position)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        let position = self.primary.seek_end()?;
+        self.secondary.seek(position)?;
+
+        Ok(position)
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        self.primary.stream_len()
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        self.primary.tell()
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.primary.flush()?;
+        self.secondary.flush()
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), StreamError> {
+        self.primary.truncate(len)?;
+        self.secondary.truncate(len)
+    }
+}
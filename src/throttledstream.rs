@@ -0,0 +1,90 @@
+use crate::{Stream, StreamError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Caps the combined read and write throughput of the inner stream by
+/// sleeping after each transfer until the bytes moved so far fit within
+/// `bytes_per_sec`. A rate of zero disables throttling.
+pub struct ThrottledStream<S: Stream> {
+    inner: S,
+    bytes_per_sec: u64,
+    started: Instant,
+    transferred: u64,
+}
+
+impl<S: Stream> ThrottledStream<S> {
+    pub fn new(inner: S, bytes_per_sec: u64) -> ThrottledStream<S> {
+        ThrottledStream {
+            inner,
+            bytes_per_sec,
+            started: Instant::now(),
+            transferred: 0,
+        }
+    }
+
+    pub fn bytes_per_sec(&self) -> u64 {
+        self.bytes_per_sec
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn throttle(&mut self, bytes: usize) {
+        if self.bytes_per_sec == 0 {
+            return;
+        }
+
+        self.transferred += bytes as u64;
+        let due = Duration::from_secs_f64(self.transferred as f64 / self.bytes_per_sec as f64);
+        let elapsed = self.started.elapsed();
+
+        if due > elapsed {
+            thread::sleep(due - elapsed);
+        }
+    }
+}
+
+impl<S: Stream> Stream for ThrottledStream<S> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        let written = self.inner.write(bytes)?;
+        self.throttle(written);
+
+        Ok(written)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        let read = self.inner.read(buffer)?;
+        self.throttle(read);
+
+        Ok(read)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        self.inner.seek(to)
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        self.inner.seek_end()
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        self.inner.stream_len()
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        self.inner.tell()
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.inner.flush()
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), StreamError> {
+        self.inner.truncate(len)
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{Stream, StreamError};
+use alloc::format;
+use alloc::string::{String, ToString};
+use log::{debug, log_enabled, Level};
+
+/// Logs every call at debug level with its byte count and the resulting
+/// position, then passes it through to the inner stream unchanged. The
+/// position comes from the inner stream's `tell`, and is only queried when
+/// debug logging is enabled.
+pub struct TracingStream<S: Stream> {
+    inner: S,
+}
+
+impl<S: Stream> TracingStream<S> {
+    pub fn new(inner: S) -> TracingStream<S> {
+        TracingStream { inner }
+    }
+
+    pub fn get_ref(&self) -> &S {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn position(&mut self) -> String {
+        match self.inner.tell() {
+            Ok(position) => position.to_string(),
+            Err(_) => "unknown".to_string(),
+        }
+    }
+
+    fn trace<T: core::fmt::Debug>(&mut self, op: &str, result: &Result<T, StreamError>) {
+        if !log_enabled!(Level::Debug) {
+            return;
+        }
+
+        match result {
+            Ok(value) => {
+                let position = self.position();
+                debug!("{} -> {:?}, position {}", op, value, position);
+            }
+            Err(e) => debug!("{} failed: {}", op, e),
+        }
+    }
+}
+
+impl<S: Stream> Stream for TracingStream<S> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        let result = self.inner.write(bytes);
+        self.trace(&format!("write({})", bytes.len()), &result);
+
+        result
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        let result = self.inner.read(buffer);
+        self.trace(&format!("read({})", buffer.len()), &result);
+
+        result
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        let result = self.inner.seek(to);
+        self.trace(&format!("seek({})", to), &result);
+
+        result
+    }
+
+    fn seek_end(&mut self) -> Result<usize, StreamError> {
+        let result = self.inner.seek_end();
+        self.trace("seek_end()", &result);
+
+        result
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        self.inner.stream_len()
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        let result = self.inner.tell();
+        self.trace("tell()", &result);
+
+        result
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        self.inner.flush()
+    }
+
+    fn truncate(&mut self, len: usize) -> Result<(), StreamError> {
+        self.inner.truncate(len)
+    }
+}
@@ -1,9 +1,42 @@
+#![cfg(feature = "std")]
+
 extern crate binary_rw;
+#[macro_use]
+extern crate serde;
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::num::{NonZeroU32, NonZeroU64};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use binary_rw::{
-    filestream::{Filestream, OpenType},
+    binarystream::BinaryStream,
+    boundedstream::BoundedStream,
+    bufferedstream::BufferedStream,
+    bufferedwritestream::BufferedWriteStream,
+    byteorder::{BigEndian, LittleEndian},
+    chainstream::ChainStream,
+    checksumstream::ChecksumStream,
+    encoding::StringEncoding,
+    filestream::{FileMode, Filestream, OpenType},
+    iostream::IoStream,
     memorystream::Memorystream,
-    BinaryReader, BinaryWriter,
+    mockstream::{MockAction, MockStream},
+    nullstream::SinkStream,
+    num::{BinaryNum, Primitive},
+    recordingstream::RecordingStream,
+    retrystream::RetryStream,
+    slicestream::SliceStream,
+    streamio::StreamIo,
+    stringpool::StringPool,
+    tagged::TaggedValue,
+    tcpstream::TcpStreamWrapper,
+    teestream::TeeStream,
+    throttledstream::ThrottledStream,
+    BinaryConfig, BinaryError, BinaryReadable, BinaryReader, BinaryWritable, BinaryWriter, Codec,
+    Endian, FileHeader, LengthPrefix, SeekOrigin, Sign, Stream, StreamError,
 };
 
 fn create_writer_stream(name: &str) -> Filestream {
@@ -27,7 +60,7 @@ fn seek_test() {
     let mut writer = BinaryWriter::new(&mut stream);
 
     writer
-        .write_bytes([16; 32].to_vec())
+        .write_bytes(&[16; 32])
         .expect("Failed to write bytes");
     writer.seek_to(seek_loc).expect("Writer seek error");
     assert_eq!(writer.get_cur_pos().expect("Failed to get pos"), seek_loc);
@@ -269,7 +302,7 @@ fn read_write_bytes() {
     let mut writer = BinaryWriter::new(&mut stream);
 
     writer
-        .write_bytes(temp.clone())
+        .write_bytes(&temp)
         .expect("Failed to write bytes");
 
     let mut stream = create_reader_stream("bytes");
@@ -313,7 +346,7 @@ fn read_write_string() {
     let mut writer = BinaryWriter::new(&mut stream);
 
     writer
-        .write_string(temp.to_string())
+        .write_string(temp)
         .expect("Failed to write string");
     let mut stream = create_reader_stream("out_of_range");
 
@@ -388,3 +421,5181 @@ fn write_to_filestream_overlapping() {
 
     cleanup("filestream_overlapping");
 }
+
+#[cfg(feature = "bumpalo")]
+#[test]
+fn read_bytes_into_arena() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer
+        .write_bytes(&[1, 2, 3, 4, 5])
+        .expect("Failed to write bytes");
+
+    let arena = bumpalo::Bump::new();
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let first = reader.read_bytes_in(&arena, 2).expect("Failed to read bytes");
+    let second = reader.read_bytes_in(&arena, 3).expect("Failed to read bytes");
+
+    assert_eq!(first, &[1, 2]);
+    assert_eq!(second, &[3, 4, 5]);
+}
+
+#[test]
+fn write_and_resolve_relocations() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    let first = writer.write_ptr_placeholder().expect("Failed to write placeholder");
+    let second = writer.write_ptr_placeholder().expect("Failed to write placeholder");
+    writer.write_u32(0xAAAA_AAAA).expect("Failed to write u32");
+
+    let second_target = writer.resolve(second).expect("Failed to resolve");
+    writer.write_u32(2).expect("Failed to write u32");
+    let first_target = writer.resolve(first).expect("Failed to resolve");
+    writer.write_u32(1).expect("Failed to write u32");
+
+    writer.apply_relocations().expect("Failed to apply relocations");
+    assert_eq!(writer.get_cur_pos().expect("Failed to get pos"), 28);
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_usize().expect("Failed to read usize"), first_target);
+    assert_eq!(reader.read_usize().expect("Failed to read usize"), second_target);
+
+    reader.seek_to(first_target).expect("Failed to seek");
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 1);
+    reader.seek_to(second_target).expect("Failed to seek");
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 2);
+}
+
+#[test]
+fn read_write_ascii_int() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer
+        .write_ascii_int(0o644, 7, 8)
+        .expect("Failed to write ascii int");
+    writer
+        .write_ascii_int(-42, 5, 10)
+        .expect("Failed to write ascii int");
+    writer
+        .write_bytes(b"1f2g")
+        .expect("Failed to write bytes");
+    assert!(writer.write_ascii_int(1000, 3, 10).is_err());
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_bytes(7).expect("Failed to read bytes"), b"0000644");
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_ascii_int(7, 8).expect("Failed to read ascii int"), 0o644);
+    assert_eq!(reader.read_ascii_int(5, 10).expect("Failed to read ascii int"), -42);
+    assert!(reader.read_ascii_int(4, 16).is_err());
+}
+
+#[test]
+fn record_and_replay_reads() {
+    let mut source = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut source);
+    writer.write_u32(7).expect("Failed to write u32");
+    writer
+        .write_string("recorded")
+        .expect("Failed to write string");
+    writer.write_f64(1.5).expect("Failed to write f64");
+    writer.seek_to(0).expect("Failed to seek");
+
+    let mut recorder = RecordingStream::new(source);
+    let mut reader = BinaryReader::new(&mut recorder);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 7);
+    assert_eq!(reader.read_string().expect("Failed to read string"), "recorded");
+
+    let recording = recorder.into_recording();
+    assert_eq!(recording.len(), 4 + 8 + 8);
+
+    let mut replay = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut replay);
+    writer.write_bytes(&recording).expect("Failed to write bytes");
+
+    let mut reader = BinaryReader::new(&mut replay);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 7);
+    assert_eq!(reader.read_string().expect("Failed to read string"), "recorded");
+    assert!(reader.read_f64().is_err());
+}
+
+#[test]
+fn read_string_bounded() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer
+        .write_string("short")
+        .expect("Failed to write string");
+    writer
+        .write_string("much too long")
+        .expect("Failed to write string");
+    writer
+        .write_string("caf\u{e9}")
+        .expect("Failed to write string");
+    writer.write_u32(99).expect("Failed to write u32");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+
+    let (value, truncated) = reader.read_string_bounded(8).expect("Failed to read string");
+    assert_eq!(value, "short");
+    assert!(!truncated);
+
+    let (value, truncated) = reader.read_string_bounded(8).expect("Failed to read string");
+    assert_eq!(value, "much too");
+    assert!(truncated);
+
+    let (value, truncated) = reader.read_string_bounded(4).expect("Failed to read string");
+    assert_eq!(value, "caf");
+    assert!(truncated);
+
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 99);
+}
+
+#[test]
+fn read_string_bounded_length_past_end() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u64(100).expect("Failed to write length");
+    writer.write_bytes(b"abc").expect("Failed to write bytes");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    match reader.read_string_bounded(2) {
+        Err(BinaryError::UnexpectedEof { expected: 98, got: 1 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+fn read_table<T: BinaryNum>(reader: &mut BinaryReader, count: usize) -> Vec<T> {
+    (0..count)
+        .map(|_| reader.read_num::<T>().expect("Failed to read num"))
+        .collect()
+}
+
+fn read_pair<T: Primitive>(reader: &mut BinaryReader) -> (T, T) {
+    let first = reader.read_number::<T>().expect("Failed to read number");
+    let second = reader.read_number::<T>().expect("Failed to read number");
+    (first, second)
+}
+
+#[test]
+fn read_write_primitive_number() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::with_endian(&mut stream, Endian::Big);
+    assert_eq!(writer.write_number(-7i16).expect("Failed to write i16"), 2);
+    writer.write_number(300i16).expect("Failed to write i16");
+    assert_eq!(writer.write_number(0.5f32).expect("Failed to write f32"), 4);
+    writer.write_number(-8.0f32).expect("Failed to write f32");
+
+    let mut reader = BinaryReader::with_endian(&mut stream, Endian::Big);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_u8().expect("Failed to read u8"), 0xFF);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(read_pair::<i16>(&mut reader), (-7, 300));
+    assert_eq!(read_pair::<f32>(&mut reader), (0.5, -8.0));
+}
+
+#[test]
+fn read_write_generic_num() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    for value in [1u16, 2, 3].iter() {
+        writer.write_num(*value).expect("Failed to write u16");
+    }
+    for value in [-1.5f64, 2.25].iter() {
+        writer.write_num(*value).expect("Failed to write f64");
+    }
+    assert_eq!(writer.get_cur_pos().expect("Failed to get pos"), 3 * u16::SIZE + 2 * f64::SIZE);
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(read_table::<u16>(&mut reader, 3), vec![1, 2, 3]);
+    assert_eq!(read_table::<f64>(&mut reader, 2), vec![-1.5, 2.25]);
+}
+
+#[test]
+fn read_write_delta_block() {
+    let samples = vec![1000, 1001, 999, 1126, 872];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer
+        .write_delta_block(1000, &samples, 2)
+        .expect("Failed to write delta block");
+    assert!(writer.write_delta_block(0, &[200], 1).is_err());
+    assert_eq!(writer.get_cur_pos().expect("Failed to get pos"), 10);
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let read_samples = reader
+        .read_delta_block(1000, 2, samples.len())
+        .expect("Failed to read delta block");
+    assert_eq!(samples, read_samples);
+}
+
+#[test]
+fn read_delta_block_overflow() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_i8(1).expect("Failed to write i8");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert!(reader.read_delta_block(i64::MAX, 1, 1).is_err());
+
+    match reader.read_delta_block(0, 8, usize::MAX / 4) {
+        Err(BinaryError::AllocationTooLarge { requested: usize::MAX, .. }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+struct ForwardOnlyStream {
+    remaining: usize,
+}
+
+impl Stream for ForwardOnlyStream {
+    fn write(&mut self, _bytes: &[u8]) -> Result<usize, StreamError> {
+        Err(StreamError::WriteError)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        let read = buffer.len().min(self.remaining);
+        self.remaining -= read;
+
+        Ok(read)
+    }
+
+    fn seek(&mut self, _to: usize) -> Result<usize, StreamError> {
+        Err(StreamError::SeekError)
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        Err(StreamError::TellError)
+    }
+}
+
+struct TrickleStream {
+    data: Vec<u8>,
+    per_call: usize,
+    capacity: usize,
+}
+
+impl Stream for TrickleStream {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        let count = bytes
+            .len()
+            .min(self.per_call)
+            .min(self.capacity - self.data.len());
+        self.data.extend_from_slice(&bytes[..count]);
+        Ok(count)
+    }
+
+    fn read(&mut self, _buffer: &mut [u8]) -> Result<usize, StreamError> {
+        Err(StreamError::ReadError)
+    }
+
+    fn seek(&mut self, _to: usize) -> Result<usize, StreamError> {
+        Err(StreamError::SeekError)
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        Ok(self.data.len())
+    }
+}
+
+#[test]
+fn partial_writes_are_completed() {
+    let mut stream = TrickleStream {
+        data: Vec::new(),
+        per_call: 3,
+        capacity: 64,
+    };
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.write_u64(0x0807_0605_0403_0201).expect("Failed to write u64"), 8);
+    assert_eq!(writer.write_bytes(b"abcdefg").expect("Failed to write bytes"), 7);
+    writer.write_string("hi").expect("Failed to write string");
+
+    let mut expected = vec![1, 2, 3, 4, 5, 6, 7, 8];
+    expected.extend_from_slice(b"abcdefg");
+    expected.extend_from_slice(&[2, 0, 0, 0, 0, 0, 0, 0]);
+    expected.extend_from_slice(b"hi");
+    assert_eq!(stream.data, expected);
+
+    let mut stream = TrickleStream {
+        data: Vec::new(),
+        per_call: 3,
+        capacity: 5,
+    };
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(
+        writer.write_u64(1),
+        Err(BinaryError::ShortWrite {
+            expected: 8,
+            written: 5,
+        })
+    );
+}
+
+#[test]
+fn seek_to_end_reports_size() {
+    let mut stream = create_writer_stream("seek_to_end");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u64(1).expect("Failed to write u64");
+    writer.write_u16(2).expect("Failed to write u16");
+
+    let mut stream = create_reader_stream("seek_to_end");
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(reader.seek_to_end().expect("Failed to seek to end"), 10);
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 10);
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u32(3).expect("Failed to write u32");
+    writer.seek_to(0).expect("Failed to seek");
+    assert_eq!(writer.get_cur_pos().expect("Failed to get pos"), 0);
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(reader.seek_to_end().expect("Failed to seek to end"), 4);
+
+    let mut stream = ForwardOnlyStream { remaining: 13 };
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(reader.seek_to_end().expect("Failed to seek to end"), 13);
+
+    cleanup("seek_to_end");
+}
+
+struct TellOnlyStream {
+    data: Vec<u8>,
+    position: usize,
+    fail_at: Option<usize>,
+    reads: usize,
+}
+
+impl Stream for TellOnlyStream {
+    fn write(&mut self, _bytes: &[u8]) -> Result<usize, StreamError> {
+        Err(StreamError::WriteError)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        self.reads += 1;
+        if matches!(self.fail_at, Some(at) if self.position >= at) {
+            return Err(StreamError::ReadError);
+        }
+
+        let read = buffer.len().min(self.data.len() - self.position);
+        buffer[..read].copy_from_slice(&self.data[self.position..self.position + read]);
+        self.position += read;
+
+        Ok(read)
+    }
+
+    fn seek(&mut self, _to: usize) -> Result<usize, StreamError> {
+        Err(StreamError::SeekError)
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        Ok(self.position)
+    }
+}
+
+#[test]
+fn default_seek_end_reads_in_chunks_and_reports_errors() {
+    let data: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+    let mut stream = TellOnlyStream {
+        data: data.clone(),
+        position: 0,
+        fail_at: None,
+        reads: 0,
+    };
+
+    // Without seeking the stream can't be measured, and isn't drained trying.
+    assert_eq!(Err(StreamError::SeekError), stream.stream_len());
+    assert_eq!((0, 0), (stream.position, stream.reads));
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.skip_bytes(100).expect("Failed to skip");
+    assert_eq!(100, reader.read_u8().expect("Failed to read u8"));
+
+    stream.reads = 0;
+    assert_eq!(Ok(10_000), stream.seek_end());
+    assert_eq!(3 + 1, stream.reads);
+
+    let mut stream = TellOnlyStream {
+        data,
+        position: 0,
+        fail_at: Some(5000),
+        reads: 0,
+    };
+    assert_eq!(Err(StreamError::ReadError), stream.seek_end());
+}
+
+#[test]
+fn read_record_array_skips_unknown_trailing_bytes() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_usize(2).expect("Failed to write count");
+    writer.write_usize(4).expect("Failed to write record length");
+    writer.write_u32(10).expect("Failed to write u32");
+    writer.write_usize(7).expect("Failed to write record length");
+    writer.write_u32(20).expect("Failed to write u32");
+    writer
+        .write_bytes(&[0xEE; 3])
+        .expect("Failed to write unknown bytes");
+    writer.write_u8(42).expect("Failed to write trailer");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let records = reader
+        .read_record_array(8, 16, |r| r.read_u32())
+        .expect("Failed to read records");
+    assert_eq!(records, vec![10, 20]);
+    assert_eq!(reader.read_u8().expect("Failed to read trailer"), 42);
+
+    reader.seek_to(0).expect("Failed to seek");
+    assert!(reader.read_record_array(1, 16, |r| r.read_u32()).is_err());
+
+    reader.seek_to(0).expect("Failed to seek");
+    assert!(reader.read_record_array(8, 16, |r| r.read_u64()).is_err());
+    // The record count follows the configured length prefix.
+    let mut stream = Memorystream::new().expect("Error");
+    let config = BinaryConfig::builder().length_prefix(LengthPrefix::Varint).build();
+    let mut writer = BinaryWriter::with_config(&mut stream, config);
+    writer.write_varint_u64(1).expect("Failed to write count");
+    writer.write_usize(2).expect("Failed to write record length");
+    writer.write_u16(5).expect("Failed to write u16");
+
+    let mut reader = BinaryReader::with_config(&mut stream, config);
+    reader.seek_to(0).expect("Failed to seek");
+    let records = reader
+        .read_record_array(8, 16, |r| r.read_u16())
+        .expect("Failed to read records");
+    assert_eq!(records, vec![5]);
+}
+
+#[test]
+fn read_iter_until_eof() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    for value in 1..=5 {
+        writer.write_u32(value * 10).expect("Failed to write u32");
+    }
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let values: Result<Vec<u32>, BinaryError> = reader.read_iter(|r| r.read_u32()).collect();
+    assert_eq!(values, Ok(vec![10, 20, 30, 40, 50]));
+
+    let mut stream = Memorystream::from_vec(vec![1, 0, 0, 0, 2, 0]);
+    let mut reader = BinaryReader::new(&mut stream);
+    let mut values = reader.read_iter(|r| r.read_u32());
+    assert_eq!(values.next(), Some(Ok(1)));
+    assert_eq!(
+        values.next(),
+        Some(Err(BinaryError::UnexpectedEof {
+            expected: 4,
+            got: 2,
+        }))
+    );
+    assert_eq!(values.next(), None);
+}
+
+#[test]
+fn read_iter_reports_record_cut_at_field_boundary() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    for value in 1..=3 {
+        writer.write_u32(value).expect("Failed to write u32");
+    }
+
+    stream.seek(0).expect("Failed to seek");
+    let mut reader = BinaryReader::new(&mut stream);
+    let mut records = reader.read_iter(|r| Ok((r.read_u32()?, r.read_u32()?)));
+    assert_eq!(records.next(), Some(Ok((1, 2))));
+    assert_eq!(
+        records.next(),
+        Some(Err(BinaryError::UnexpectedEof {
+            expected: 4,
+            got: 0,
+        }))
+    );
+    assert_eq!(records.next(), None);
+}
+
+#[test]
+fn checked_block_detects_corruption() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    let written = writer
+        .write_checked_block(|w| {
+            w.write_u32(42)?;
+            w.write_string("body")?;
+            Ok(())
+        })
+        .expect("Failed to write checked block");
+    assert_eq!(written, 4 + 4 + 16 + 4);
+    let abandoned = writer.write_checked_block(|w| {
+        w.write_bool(true)?;
+        Err(BinaryError::InvalidData("abandoned".to_string()))
+    });
+    assert!(abandoned.is_err());
+    assert_eq!(writer.get_cur_pos().expect("Failed to get pos"), written);
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let (value, body) = reader
+        .read_checked_block(|r| Ok((r.read_u32()?, r.read_string()?)))
+        .expect("Failed to read checked block");
+    assert_eq!((value, body.as_str()), (42, "body"));
+
+    let mut corrupted = stream.clone().into_vec();
+    corrupted[8] ^= 0x01;
+    let mut stream = Memorystream::from_vec(corrupted);
+    let mut reader = BinaryReader::new(&mut stream);
+    match reader.read_checked_block(|r| r.read_u32()) {
+        Err(BinaryError::At { offset: 24, source }) => {
+            assert!(matches!(*source, BinaryError::ChecksumMismatch { .. }))
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let mut stream = Memorystream::from_vec(b"NOPE\0\0\0\0".to_vec());
+    let mut reader = BinaryReader::new(&mut stream);
+    assert!(reader.read_checked_block(|r| r.read_u32()).is_err());
+}
+
+#[test]
+fn patch_placeholder_length() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::with_endian(&mut stream, Endian::Big);
+    writer.write_u8(0xAA).expect("Failed to write u8");
+    let length_pos = writer.write_placeholder_u32().expect("Failed to write placeholder");
+    assert_eq!(length_pos, 1);
+
+    let body_start = writer.get_cur_pos().expect("Failed to get pos");
+    for name in ["a", "bcd", "efghij"].iter() {
+        writer.write_cstring(name).expect("Failed to write cstring");
+    }
+    let body_end = writer.get_cur_pos().expect("Failed to get pos");
+    writer
+        .patch_u32(length_pos, (body_end - body_start) as u32)
+        .expect("Failed to patch length");
+    assert_eq!(writer.get_cur_pos().expect("Failed to get pos"), body_end);
+    writer.write_u8(0xBB).expect("Failed to write u8");
+
+    assert_eq!(&stream.as_slice()[1..5], &[0, 0, 0, 13]);
+    let mut reader = BinaryReader::with_endian(&mut stream, Endian::Big);
+    reader.seek_to(1).expect("Failed to seek");
+    let len = reader.read_u32().expect("Failed to read length") as usize;
+    reader.skip(len).expect("Failed to skip body");
+    assert_eq!(reader.read_u8().expect("Failed to read u8"), 0xBB);
+}
+
+#[test]
+fn scoped_reader_stays_in_region() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u8(1).expect("Failed to write u8");
+    writer.write_u32(10).expect("Failed to write u32");
+    writer.write_u32(20).expect("Failed to write u32");
+    writer.write_u16(0xBEEF).expect("Failed to write u16");
+    writer.write_u16(0xCAFE).expect("Failed to write u16");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(1).expect("Failed to seek");
+    {
+        let mut scope = reader.scoped(8).expect("Failed to scope");
+        let mut inner = scope.reader();
+        assert_eq!(inner.read_u32().expect("Failed to read u32"), 10);
+        assert_eq!(scope.remaining(), 4);
+    }
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 9);
+    assert_eq!(reader.read_u16().expect("Failed to read u16"), 0xBEEF);
+
+    reader.seek_to(1).expect("Failed to seek");
+    {
+        let mut scope = reader.scoped(6).expect("Failed to scope");
+        let mut inner = scope.reader();
+        assert_eq!(inner.read_u32().expect("Failed to read u32"), 10);
+        assert_eq!(
+            inner.read_u32(),
+            Err(BinaryError::UnexpectedEof {
+                expected: 4,
+                got: 2,
+            })
+        );
+        assert!(inner.seek_to(8).is_err());
+    }
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 7);
+    assert_eq!(
+        reader.read_bytes(6).expect("Failed to read bytes"),
+        vec![0, 0, 0xEF, 0xBE, 0xFE, 0xCA]
+    );
+}
+
+#[test]
+fn read_variant_skips_unknown_tags() {
+    #[derive(Debug, PartialEq)]
+    enum Shape {
+        Circle(f32),
+        Rect(u16, u16),
+        Unknown(u32),
+    }
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(
+        writer
+            .write_variant(1, |w| w.write_f32(2.5).map(|_| ()))
+            .expect("Failed to write variant"),
+        12
+    );
+    writer
+        .write_variant(7, |w| {
+            w.write_string("from a newer writer")?;
+            w.write_u64(u64::MAX)?;
+            Ok(())
+        })
+        .expect("Failed to write variant");
+    writer
+        .write_variant(2, |w| {
+            w.write_u16(3)?;
+            w.write_u16(4)?;
+            // Trailing field an older reader doesn't know about.
+            w.write_u8(0xFF)?;
+            Ok(())
+        })
+        .expect("Failed to write variant");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let mut shapes = Vec::new();
+    for _ in 0..3 {
+        let shape = reader
+            .read_variant(|tag, body| match tag {
+                1 => Ok(Shape::Circle(body.read_f32()?)),
+                2 => Ok(Shape::Rect(body.read_u16()?, body.read_u16()?)),
+                other => Ok(Shape::Unknown(other)),
+            })
+            .expect("Failed to read variant");
+        shapes.push(shape);
+    }
+
+    assert_eq!(shapes, vec![Shape::Circle(2.5), Shape::Unknown(7), Shape::Rect(3, 4)]);
+    assert!(reader.read_u8().unwrap_err().is_eof());
+}
+
+#[test]
+fn write_padding_with_default_pad_byte() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.pad_byte(), 0);
+
+    writer.write_u8(1).expect("Failed to write u8");
+    writer.align(4).expect("Failed to align");
+    writer.set_pad_byte(0x20);
+    writer.write_u8(2).expect("Failed to write u8");
+    writer.align(4).expect("Failed to align");
+    assert_eq!(writer.align(4).expect("Failed to align"), 0);
+    writer.write_padding(2).expect("Failed to pad");
+    writer.write_padding_with(2, 0xFF).expect("Failed to pad");
+    writer.write_u8(3).expect("Failed to write u8");
+    writer.align_with(2, 0xAA).expect("Failed to align");
+    assert!(writer.align(0).is_err());
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(
+        reader.read_bytes(14).expect("Failed to read bytes"),
+        vec![1, 0, 0, 0, 2, 0x20, 0x20, 0x20, 0x20, 0x20, 0xFF, 0xFF, 3, 0xAA]
+    );
+}
+
+#[derive(Debug, PartialEq)]
+enum Shape {
+    Circle,
+    Square,
+}
+
+impl AsRef<str> for Shape {
+    fn as_ref(&self) -> &str {
+        match self {
+            Shape::Circle => "Circle",
+            Shape::Square => "Square",
+        }
+    }
+}
+
+impl std::str::FromStr for Shape {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Shape, ()> {
+        match s {
+            "Circle" => Ok(Shape::Circle),
+            "Square" => Ok(Shape::Square),
+            _ => Err(()),
+        }
+    }
+}
+
+#[test]
+fn read_write_enum_by_name() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer
+        .write_enum_by_name(&Shape::Square)
+        .expect("Failed to write enum");
+    writer
+        .write_enum_by_name(&Shape::Circle)
+        .expect("Failed to write enum");
+    writer
+        .write_string("Triangle")
+        .expect("Failed to write string");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_enum_by_name::<Shape>().expect("Failed to read enum"), Shape::Square);
+    assert_eq!(reader.read_enum_by_name::<Shape>().expect("Failed to read enum"), Shape::Circle);
+    match reader.read_enum_by_name::<Shape>() {
+        Err(BinaryError::InvalidData(..)) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn write_sized_exact_and_padded() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    writer
+        .write_sized_exact(6, |w| {
+            w.write_u32(1)?;
+            w.write_u16(2)?;
+            Ok(())
+        })
+        .expect("Failed to write sized record");
+
+    let result = writer.write_sized_exact(6, |w| {
+        w.write_u32(1)?;
+        w.write_u8(2)?;
+        Ok(())
+    });
+    assert!(result.is_err());
+
+    writer.seek_to(6).expect("Failed to seek");
+    writer.set_pad_byte(0xFF);
+    writer
+        .write_padded_to(4, |w| {
+            w.write_u8(7)?;
+            Ok(())
+        })
+        .expect("Failed to write padded record");
+    assert!(writer
+        .write_padded_to(1, |w| {
+            w.write_u16(7)?;
+            Ok(())
+        })
+        .is_err());
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(6).expect("Failed to seek");
+    assert_eq!(reader.read_bytes(4).expect("Failed to read bytes"), vec![7, 0xFF, 0xFF, 0xFF]);
+}
+
+#[test]
+fn read_write_bitmask_indices() {
+    let indices = vec![0, 3, 8, 9, 63, 99];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer
+        .write_bitmask_from_indices(&indices, 100)
+        .expect("Failed to write bitmask");
+    assert_eq!(writer.get_cur_pos().expect("Failed to get pos"), 13);
+    assert!(writer.write_bitmask_from_indices(&[100], 100).is_err());
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_u8().expect("Failed to read u8"), 0b0000_1001);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_bitmask_indices(100).expect("Failed to read bitmask"), indices);
+}
+
+#[test]
+fn read_write_bool_slice() {
+    let bits = vec![true, false, false, true, false, false, false, false, true, true, false];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.set_length_prefix(LengthPrefix::U8);
+    assert_eq!(writer.write_bool_slice(&bits).expect("Failed to write bools"), 3);
+    writer.write_bool_slice(&[]).expect("Failed to write bools");
+    writer.write_u8(3).expect("Failed to write count");
+    writer.write_u8(0xFF).expect("Failed to write packed bits");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.set_length_prefix(LengthPrefix::U8);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(
+        reader.read_bytes(3).expect("Failed to read bytes"),
+        vec![11, 0b0000_1001, 0b0000_0011]
+    );
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_bool_slice().expect("Failed to read bools"), bits);
+    assert!(reader.read_bool_slice().expect("Failed to read bools").is_empty());
+    assert_eq!(
+        reader.read_bool_slice().expect("Failed to read bools"),
+        vec![true, true, true]
+    );
+}
+
+#[test]
+fn align_to_and_pad_to_power_of_two() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_bytes(&[1, 2, 3]).expect("Failed to write bytes");
+    assert_eq!(writer.pad_to(4, 0xCC).expect("Failed to pad"), 1);
+    assert_eq!(writer.pad_to(4, 0xCC).expect("Failed to pad"), 0);
+    writer.write_u8(4).expect("Failed to write u8");
+    assert_eq!(writer.pad_to(8, 0xDD).expect("Failed to pad"), 3);
+    assert!(writer.pad_to(6, 0).is_err());
+    assert!(writer.pad_to(0, 0).is_err());
+    writer.write_u32(5).expect("Failed to write u32");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(
+        reader.read_bytes(8).expect("Failed to read bytes"),
+        vec![1, 2, 3, 0xCC, 4, 0xDD, 0xDD, 0xDD]
+    );
+    assert_eq!(reader.align_to(8).expect("Failed to align"), 8);
+    reader.seek_to(3).expect("Failed to seek");
+    assert_eq!(reader.align_to(2).expect("Failed to align"), 4);
+    assert_eq!(reader.read_u8().expect("Failed to read u8"), 4);
+    assert_eq!(reader.align_to(8).expect("Failed to align"), 8);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 5);
+    assert!(reader.align_to(3).is_err());
+}
+
+#[test]
+fn throttled_stream_limits_rate() {
+    let mut throttled = ThrottledStream::new(Memorystream::new().expect("Error"), 1000);
+    let started = Instant::now();
+
+    let mut writer = BinaryWriter::new(&mut throttled);
+    writer.write_bytes(&[0xAB; 200]).expect("Failed to write bytes");
+    assert!(started.elapsed() >= Duration::from_millis(200));
+
+    let mut reader = BinaryReader::new(&mut throttled);
+    assert_eq!(reader.seek_to(100).expect("Failed to seek"), 100);
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 100);
+    assert_eq!(reader.read_bytes(100).expect("Failed to read bytes"), vec![0xAB; 100]);
+    assert!(started.elapsed() >= Duration::from_millis(300));
+}
+
+#[test]
+fn binary_stream_patches_length_field() {
+    let mut stream = BinaryStream::with_endian(Memorystream::new().expect("Error"), Endian::Big);
+    stream.writer().write_u32(0).expect("Failed to write length");
+    let start = stream.get_cur_pos().expect("Failed to get pos");
+    stream.writer().write_string("payload").expect("Failed to write string");
+    stream.writer().write_u16(7).expect("Failed to write u16");
+    let end = stream.get_cur_pos().expect("Failed to get pos");
+
+    stream.seek_to(0).expect("Failed to seek");
+    assert_eq!(stream.reader().read_u32().expect("Failed to read length"), 0);
+    stream.seek_to(0).expect("Failed to seek");
+    stream
+        .writer()
+        .write_u32((end - start) as u32)
+        .expect("Failed to patch length");
+
+    stream.seek_to(0).expect("Failed to seek");
+    let mut reader = stream.reader();
+    assert_eq!(reader.read_u32().expect("Failed to read length"), 17);
+    assert_eq!(reader.read_string().expect("Failed to read string"), "payload");
+    assert_eq!(reader.read_u16().expect("Failed to read u16"), 7);
+    assert_eq!(&stream.into_inner().as_slice()[..4], &[0, 0, 0, 17]);
+}
+
+#[test]
+fn mock_stream_scripted_short_read() {
+    let mut stream = MockStream::with_script(
+        vec![1, 0, 0, 0, 2, 0, 0, 0],
+        vec![
+            MockAction::ShortRead(1),
+            MockAction::Succeed,
+            MockAction::ShortRead(2),
+            MockAction::ShortRead(0),
+        ],
+    );
+
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 1);
+    match reader.read_u32() {
+        Err(BinaryError::UnexpectedEof { expected: 4, got: 2 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+    assert_eq!(stream.remaining_actions(), 0);
+}
+
+#[test]
+fn mock_stream_scripted_writes() {
+    let mut stream = MockStream::with_script(
+        Vec::new(),
+        vec![
+            MockAction::AcceptPartial(1),
+            MockAction::AcceptPartial(2),
+            MockAction::Succeed,
+            MockAction::Fail(StreamError::WriteError),
+            MockAction::AcceptPartial(0),
+        ],
+    );
+
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.write_u32(0x0403_0201).expect("Failed to write u32"), 4);
+    assert!(matches!(
+        writer.write_u8(5),
+        Err(BinaryError::StreamError(StreamError::WriteError))
+    ));
+    assert!(matches!(
+        writer.write_u16(6),
+        Err(BinaryError::ShortWrite { expected: 2, written: 0 })
+    ));
+    writer.write_u8(7).expect("Failed to write u8");
+    assert_eq!(stream.as_slice(), &[1, 2, 3, 4, 7]);
+}
+
+#[test]
+fn close_persists_filestream() {
+    let mut stream = create_writer_stream("close");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_string("closed").expect("Failed to write string");
+    writer.write_u64(42).expect("Failed to write u64");
+    stream.close().expect("Failed to close");
+
+    let mut stream = create_reader_stream("close");
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(reader.read_string().expect("Failed to read string"), "closed");
+    assert_eq!(reader.read_u64().expect("Failed to read u64"), 42);
+    assert_eq!(stream.stream_len().expect("Failed to get length"), 22);
+
+    let memory = Memorystream::new().expect("Error");
+    memory.close().expect("Failed to close");
+
+    cleanup("close");
+}
+
+#[test]
+fn memorystream_write_past_end_zero_fills() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.seek_to(100).expect("Failed to seek"), 100);
+    writer.write_u32(0xAABB_CCDD).expect("Failed to write u32");
+    assert_eq!(writer.get_cur_pos().expect("Failed to get pos"), 104);
+
+    assert_eq!(stream.len(), 104);
+    assert!(stream.as_slice()[..100].iter().all(|&b| b == 0));
+    assert_eq!(&stream.as_slice()[100..], &[0xDD, 0xCC, 0xBB, 0xAA]);
+
+    // Seek back to patch, then ahead again past the new end.
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.seek_to(0).expect("Failed to seek");
+    writer.write_u8(1).expect("Failed to write u8");
+    writer.seek_to(110).expect("Failed to seek");
+    writer.write_u8(2).expect("Failed to write u8");
+    assert_eq!(stream.len(), 111);
+    assert_eq!(stream.as_slice()[0], 1);
+    assert_eq!(&stream.as_slice()[104..], &[0, 0, 0, 0, 0, 0, 2]);
+}
+
+#[test]
+fn memorystream_write_at_usize_max_fails() {
+    let mut stream = Memorystream::from_vec(vec![1, 2, 3]);
+    stream.seek(usize::MAX).expect("Failed to seek");
+    assert_eq!(stream.write(&[1]), Err(StreamError::WriteError));
+    assert_eq!(stream.as_slice(), &[1, 2, 3]);
+}
+
+#[test]
+fn bounded_stream_errors_past_limit() {
+    let mut bounded = BoundedStream::new(Memorystream::new().expect("Error"), 6);
+    let mut writer = BinaryWriter::new(&mut bounded);
+    writer.write_u32(1).expect("Failed to write u32");
+    assert!(writer.write_u32(2).is_err());
+    writer.write_u16(3).expect("Failed to write u16");
+    assert!(writer.write_u8(4).is_err());
+    assert_eq!(bounded.remaining(), 0);
+
+    let mut inner = bounded.into_inner();
+    inner.seek(0).expect("Failed to seek");
+    let mut bounded = BoundedStream::new(inner, 5);
+    let mut reader = BinaryReader::new(&mut bounded);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 1);
+    assert!(reader.read_u16().is_err());
+    assert_eq!(reader.read_u8().expect("Failed to read u8"), 3);
+    assert_eq!(bounded.remaining(), 0);
+}
+
+#[test]
+fn bounded_stream_stays_inside_its_region() {
+    let mut inner = Memorystream::from_vec(vec![0xEE; 4]);
+    inner.seek(4).expect("Failed to seek");
+
+    let mut bounded = BoundedStream::new(inner, 4);
+    let mut writer = BinaryWriter::new(&mut bounded);
+    writer.write_u16(1).expect("Failed to write u16");
+    assert_eq!(writer.get_cur_pos().expect("Failed to get pos"), 2);
+
+    // Position 0 is the start of the member, not of the archive, and
+    // nothing before or past it can be reached.
+    assert!(writer.seek(SeekOrigin::Current, -3).is_err());
+    writer.seek_to(0).expect("Failed to seek");
+    writer.write_u8(7).expect("Failed to write u8");
+    assert!(writer.seek_to(5).is_err());
+    assert_eq!(bounded.remaining(), 3);
+    assert_eq!(bounded.truncate(0), Err(StreamError::WriteError));
+
+    let inner = bounded.into_inner();
+    assert_eq!(inner.as_slice(), &[0xEE, 0xEE, 0xEE, 0xEE, 7, 0]);
+}
+
+#[test]
+fn read_write_result_with() {
+    let ok: Result<u32, String> = Ok(7);
+    let err: Result<u32, String> = Err("failed".to_string());
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    for value in [&ok, &err].iter() {
+        writer
+            .write_result_with(value, |w, v| w.write_u32(*v), |w, e| w.write_string(e))
+            .expect("Failed to write result");
+    }
+    writer.write_u8(2).expect("Failed to write u8");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    for expected in [&ok, &err].iter() {
+        let value = reader
+            .read_result_with(|r| r.read_u32(), |r| r.read_string())
+            .expect("Failed to read result");
+        assert_eq!(&value, *expected);
+    }
+    assert_eq!(
+        reader.read_result_with(|r| r.read_u32(), |r| r.read_string()),
+        Err(BinaryError::At {
+            offset: 20,
+            source: Box::new(BinaryError::InvalidDiscriminant(2)),
+        })
+    );
+}
+
+#[derive(Debug, PartialEq)]
+struct Tree {
+    children: Vec<Tree>,
+}
+
+fn read_tree(reader: &mut BinaryReader) -> Result<Tree, BinaryError> {
+    reader.enter_nesting()?;
+
+    let count = reader.read_u8()?;
+    let mut children = Vec::new();
+    for _ in 0..count {
+        children.push(read_tree(reader)?);
+    }
+
+    reader.exit_nesting();
+    Ok(Tree { children })
+}
+
+#[test]
+fn nesting_depth_limit() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_bytes(&[1, 1, 1, 0]).expect("Failed to write bytes");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let tree = read_tree(&mut reader).expect("Failed to read tree");
+    assert_eq!(reader.depth(), 0);
+    assert_eq!(tree.children[0].children[0].children[0].children.len(), 0);
+
+    reader.seek_to(0).expect("Failed to seek");
+    reader.set_max_depth(3);
+    match read_tree(&mut reader) {
+        Err(BinaryError::RecursionLimitExceeded) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn read_write_deduplicated_string_pool() {
+    let mut pool = StringPool::new();
+    let main = pool.intern("main").expect("Failed to intern");
+    let text = pool.intern(".text").expect("Failed to intern");
+    assert_eq!(pool.intern("main").expect("Failed to intern"), main);
+    assert!(pool.intern("bad\0name").is_err());
+    assert_eq!(pool.len(), 11);
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_usize(text).expect("Failed to write offset");
+    writer.write_usize(main).expect("Failed to write offset");
+    writer.write_usize(pool.len()).expect("Failed to write pool size");
+    writer.write_string_pool(&pool).expect("Failed to write pool");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let first = reader.read_usize().expect("Failed to read offset");
+    let second = reader.read_usize().expect("Failed to read offset");
+    let pool_len = reader.read_usize().expect("Failed to read pool size");
+    let table = reader.read_string_table(pool_len).expect("Failed to read table");
+
+    assert_eq!(table.get(first).expect("Failed to resolve"), ".text");
+    assert_eq!(table.get(second).expect("Failed to resolve"), "main");
+    assert_eq!(table.get(main + 1).expect("Failed to resolve"), "ain");
+    assert!(table.get(pool_len).is_err());
+}
+
+#[derive(Debug, PartialEq)]
+struct VersionedRecord {
+    id: u32,
+    flags: Option<u16>,
+}
+
+fn read_versioned_record(reader: &mut BinaryReader) -> VersionedRecord {
+    let version = reader.read_u32().expect("Failed to read version");
+    reader
+        .versioned(version, |v, r| {
+            let id = r.read_u32()?;
+            let flags = r.read_since(v, 3, |r| r.read_u16())?;
+            Ok(VersionedRecord { id, flags })
+        })
+        .expect("Failed to read record")
+}
+
+#[test]
+fn read_versioned_fields() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u32(2).expect("Failed to write version");
+    writer.write_u32(10).expect("Failed to write id");
+    writer.write_u32(3).expect("Failed to write version");
+    writer.write_u32(11).expect("Failed to write id");
+    writer.write_u16(0x8001).expect("Failed to write flags");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(
+        read_versioned_record(&mut reader),
+        VersionedRecord { id: 10, flags: None }
+    );
+    assert_eq!(
+        read_versioned_record(&mut reader),
+        VersionedRecord { id: 11, flags: Some(0x8001) }
+    );
+}
+
+#[test]
+fn two_pass_write_prefixes_size() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u8(0xAB).expect("Failed to write u8");
+
+    let size = writer
+        .two_pass(|w| {
+            w.write_u32(1)?;
+            w.align(8)?;
+            w.write_string("body")?;
+            Ok(())
+        })
+        .expect("Failed to write two-pass block");
+    assert_eq!(size, 4 + 3 + 8 + 4);
+
+    let mut calls = 0;
+    let result = writer.two_pass(|w| {
+        calls += 1;
+        w.write_bytes(&vec![0; calls])?;
+        Ok(())
+    });
+    assert!(result.is_err());
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(1).expect("Failed to seek");
+    assert_eq!(reader.read_usize().expect("Failed to read size"), size);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 1);
+    reader.seek_to(16).expect("Failed to seek");
+    assert_eq!(reader.read_string().expect("Failed to read string"), "body");
+}
+
+#[test]
+fn read_write_packed_10bit() {
+    // MIPI CSI-2 RAW10: four high bytes followed by the packed low bits.
+    let raw = vec![0xFF, 0x00, 0x55, 0xAA, 0x93, 0x80, 0x40, 0x20, 0x10, 0xE4];
+    let samples = vec![0x3FF, 0x000, 0x155, 0x2AA, 0x200, 0x101, 0x082, 0x043];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_packed_10bit(&samples).expect("Failed to write samples");
+    assert!(writer.write_packed_10bit(&[0x400, 0, 0, 0]).is_err());
+    assert!(writer.write_packed_10bit(&[0, 0, 0]).is_err());
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_bytes(10).expect("Failed to read bytes"), raw);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_packed_10bit(8).expect("Failed to read samples"), samples);
+}
+
+#[test]
+fn read_write_packed_12bit() {
+    // MIPI CSI-2 RAW12: two high bytes followed by both low nibbles.
+    let raw = vec![0xAB, 0x12, 0x3C, 0xFF, 0x00, 0x0F];
+    let samples = vec![0xABC, 0x123, 0xFFF, 0x000];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_packed_12bit(&samples).expect("Failed to write samples");
+    assert!(writer.write_packed_12bit(&[0x1000, 0]).is_err());
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_bytes(6).expect("Failed to read bytes"), raw);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_packed_12bit(4).expect("Failed to read samples"), samples);
+    assert!(reader.read_packed_12bit(3).is_err());
+}
+
+#[test]
+fn memorystream_replace_buffer() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u16(1).expect("Failed to write u16");
+
+    let old = stream.replace_buffer(vec![2, 0, 3, 0]);
+    assert_eq!(old, vec![1, 0]);
+
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 0);
+    assert_eq!(reader.read_u16().expect("Failed to read u16"), 2);
+    assert_eq!(reader.read_u16().expect("Failed to read u16"), 3);
+    assert!(reader.read_u8().is_err());
+
+    let old = stream.replace_buffer(old);
+    assert_eq!(old, vec![2, 0, 3, 0]);
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(reader.read_u16().expect("Failed to read u16"), 1);
+}
+
+#[test]
+fn read_exact_count() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_usize(4).expect("Failed to write count");
+    for joint in 0..4 {
+        writer.write_u16(joint).expect("Failed to write u16");
+    }
+    writer.write_usize(3).expect("Failed to write count");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let joints = reader
+        .read_exact_count(4, |r| r.read_u16())
+        .expect("Failed to read joints");
+    assert_eq!(joints, vec![0, 1, 2, 3]);
+
+    match reader.read_exact_count(4, |r| r.read_u16()) {
+        Err(BinaryError::CountMismatch { expected: 4, found: 3 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+    // A count written by write_vec reads back under any length prefix.
+    let mut stream = Memorystream::new().expect("Error");
+    let config = BinaryConfig::builder().length_prefix(LengthPrefix::U32).build();
+    let mut writer = BinaryWriter::with_config(&mut stream, config);
+    writer.write_vec(&[7u16, 8]).expect("Failed to write vec");
+
+    let mut reader = BinaryReader::with_config(&mut stream, config);
+    reader.seek_to(0).expect("Failed to seek");
+    let values = reader
+        .read_exact_count(2, |r| r.read_u16())
+        .expect("Failed to read values");
+    assert_eq!(values, vec![7, 8]);
+}
+
+#[test]
+fn write_length_prefixed_buffered() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    let written = writer
+        .write_length_prefixed_buffered(2, |w| {
+            w.write_u32(5)?;
+            w.write_string("abc")?;
+            Ok(())
+        })
+        .expect("Failed to write block");
+    assert_eq!(written, 2 + 4 + 8 + 3);
+
+    assert!(writer
+        .write_length_prefixed_buffered(1, |w| {
+            w.write_bytes(&vec![0; 256])?;
+            Ok(())
+        })
+        .is_err());
+    assert!(writer.write_length_prefixed_buffered(3, |_| Ok(())).is_err());
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_u16().expect("Failed to read prefix"), 15);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 5);
+    assert_eq!(reader.read_string().expect("Failed to read string"), "abc");
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), written);
+}
+
+#[test]
+fn nested_writers_inherit_length_prefix() {
+    let config = BinaryConfig::builder().length_prefix(LengthPrefix::U8).build();
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::with_config(&mut stream, config);
+    let size = writer.two_pass(|w| w.write_string("hi").map(|_| ())).expect("Failed to write");
+    assert_eq!(3, size);
+    let written = writer
+        .write_length_prefixed_buffered(2, |w| w.write_string("abc").map(|_| ()))
+        .expect("Failed to write block");
+    assert_eq!(2 + 1 + 3, written);
+
+    stream.seek(0).expect("Failed to seek");
+    let mut reader = BinaryReader::with_config(&mut stream, config);
+    assert_eq!(3, reader.read_usize().expect("Failed to read size"));
+    assert_eq!("hi", reader.read_string().expect("Failed to read string"));
+    assert_eq!(4, reader.read_u16().expect("Failed to read prefix"));
+    assert_eq!("abc", reader.read_string().expect("Failed to read string"));
+}
+
+#[test]
+fn read_write_pascal_strings() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer
+        .write_pascal_string("Caf\u{e9} \u{2022}", StringEncoding::MacRoman)
+        .expect("Failed to write pascal string");
+    writer
+        .write_long_pascal_string("Caf\u{e9}", StringEncoding::Utf8)
+        .expect("Failed to write long pascal string");
+    assert!(writer
+        .write_pascal_string(&"x".repeat(256), StringEncoding::Utf8)
+        .is_err());
+    assert!(writer
+        .write_pascal_string("\u{4e2d}", StringEncoding::MacRoman)
+        .is_err());
+    writer
+        .write_long_pascal_string(&"x".repeat(256), StringEncoding::Utf8)
+        .expect("Failed to write long pascal string");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(
+        reader.read_bytes(7).expect("Failed to read bytes"),
+        vec![6, b'C', b'a', b'f', 0x8E, b' ', 0xA5]
+    );
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(
+        reader
+            .read_pascal_string(StringEncoding::MacRoman)
+            .expect("Failed to read pascal string"),
+        "Caf\u{e9} \u{2022}"
+    );
+    assert_eq!(
+        reader
+            .read_long_pascal_string(StringEncoding::Utf8)
+            .expect("Failed to read long pascal string"),
+        "Caf\u{e9}"
+    );
+    assert_eq!(
+        reader
+            .read_long_pascal_string(StringEncoding::Utf8)
+            .expect("Failed to read long pascal string")
+            .len(),
+        256
+    );
+}
+
+#[test]
+fn read_write_big_endian() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::with_endian(&mut stream, Endian::Big);
+    writer.write_u32(0x0102_0304).expect("Failed to write u32");
+    writer.write_i16(-2).expect("Failed to write i16");
+    writer.write_f64(1.5).expect("Failed to write f64");
+    writer.write_u64(u64::MAX - 1).expect("Failed to write u64");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_bytes(4).expect("Failed to read bytes"), vec![1, 2, 3, 4]);
+
+    reader.set_endian(Endian::Big);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 0x0102_0304);
+    assert_eq!(reader.read_i16().expect("Failed to read i16"), -2);
+    assert_eq!(reader.read_f64().expect("Failed to read f64"), 1.5);
+    assert_eq!(reader.read_u64().expect("Failed to read u64"), u64::MAX - 1);
+}
+
+#[test]
+fn read_hand_crafted_big_endian() {
+    // PNG IHDR chunk length and a 640x480 width/height pair.
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer
+        .write_bytes(&[0x00, 0x00, 0x00, 0x0D, 0x00, 0x00, 0x02, 0x80, 0x01, 0xE0, 0x3F, 0xC0, 0x00, 0x00])
+        .expect("Failed to write bytes");
+
+    let mut reader = BinaryReader::with_endian(&mut stream, Endian::Big);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 13);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 640);
+    assert_eq!(reader.read_u16().expect("Failed to read u16"), 480);
+    assert_eq!(reader.read_f32().expect("Failed to read f32"), 1.5);
+
+    reader.set_endian(Endian::Native);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), u32::from_ne_bytes([0, 0, 0, 0x0D]));
+}
+
+#[test]
+fn read_write_bool() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_bool(true).expect("Failed to write bool");
+    writer.write_bool(false).expect("Failed to write bool");
+    writer.write_u8(2).expect("Failed to write u8");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_bytes(2).expect("Failed to read bytes"), vec![1, 0]);
+    reader.seek_to(0).expect("Failed to seek");
+    assert!(reader.read_bool().expect("Failed to read bool"));
+    assert!(!reader.read_bool().expect("Failed to read bool"));
+    match reader.read_bool() {
+        Err(BinaryError::At { offset: 2, source }) => match *source {
+            BinaryError::InvalidBool(2) => {}
+            other => panic!("Unexpected source {:?}", other),
+        },
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn read_write_char() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_char('A').expect("Failed to write char");
+    writer.write_char('\u{1F980}').expect("Failed to write char");
+    writer.write_u32(0xD800).expect("Failed to write u32");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_char().expect("Failed to read char"), 'A');
+    assert_eq!(reader.read_char().expect("Failed to read char"), '\u{1F980}');
+    match reader.read_char() {
+        Err(BinaryError::At { offset: 8, source }) => match *source {
+            BinaryError::InvalidChar(0xD800) => {}
+            other => panic!("Unexpected source {:?}", other),
+        },
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Inventory {
+    owner: String,
+    items: Vec<u32>,
+}
+
+#[test]
+fn read_write_serde_value() {
+    let inventory = Inventory {
+        owner: "player".to_string(),
+        items: vec![3, 1, 4, 1, 5],
+    };
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_value(&inventory).expect("Failed to write value");
+    writer.write_u8(9).expect("Failed to write u8");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let read_inventory: Inventory = reader.read_value().expect("Failed to read value");
+    assert_eq!(inventory, read_inventory);
+    assert_eq!(reader.read_u8().expect("Failed to read u8"), 9);
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Event {
+    Ping,
+    Move(i16, i16),
+    Say { text: String, loud: bool },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Frame {
+    id: u32,
+    events: Vec<Event>,
+    tag: Option<char>,
+    weight: f32,
+    parent: Option<Box<Frame>>,
+}
+
+#[test]
+fn fixed_codec_round_trip() {
+    let frame = Frame {
+        id: 7,
+        events: vec![
+            Event::Ping,
+            Event::Move(-3, 4),
+            Event::Say { text: "hi".to_string(), loud: true },
+        ],
+        tag: Some('x'),
+        weight: 1.5,
+        parent: Some(Box::new(Frame {
+            id: 1,
+            events: Vec::new(),
+            tag: None,
+            weight: -0.0,
+            parent: None,
+        })),
+    };
+    let mut scores = HashMap::new();
+    scores.insert("a".to_string(), (1u8, 2u128));
+
+    for endian in [Endian::Little, Endian::Big] {
+        let config = BinaryConfig::builder().endian(endian).codec(Codec::Fixed).build();
+        let mut stream = Memorystream::new().expect("Error");
+        let mut writer = BinaryWriter::with_config(&mut stream, config);
+        writer.write_value(&frame).expect("Failed to write value");
+        writer.write_vec(&frame.events).expect("Failed to write vec");
+        writer.write_map(&scores).expect("Failed to write map");
+        writer.write_option(&Some(Event::Move(1, 2))).expect("Failed to write option");
+
+        let mut reader = BinaryReader::with_config(&mut stream, config);
+        reader.seek_to(0).expect("Failed to seek");
+        assert_eq!(reader.read_value::<Frame>().expect("Failed to read value"), frame);
+        assert_eq!(reader.read_vec::<Event>().expect("Failed to read vec"), frame.events);
+        assert_eq!(reader.read_map::<String, (u8, u128)>().expect("Failed to read map"), scores);
+        assert_eq!(
+            reader.read_option::<Event>().expect("Failed to read option"),
+            Some(Event::Move(1, 2))
+        );
+    }
+
+    let mut stream = Memorystream::from_vec(vec![9, 0, 0, 0]);
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.set_codec(Codec::Fixed);
+    assert!(reader.read_vec::<Event>().unwrap_err().is_eof());
+}
+
+#[test]
+fn fixed_codec_enforces_depth_and_alloc_limits() {
+    let nested = vec![vec![vec![1u8]]];
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.set_codec(Codec::Fixed);
+    writer.write_value(&nested).expect("Failed to write value");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.set_codec(Codec::Fixed);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_value::<Vec<Vec<Vec<u8>>>>().expect("Failed to read value"), nested);
+    assert_eq!(reader.depth(), 0);
+
+    reader.seek_to(0).expect("Failed to seek");
+    reader.set_max_depth(2);
+    match reader.read_value::<Vec<Vec<Vec<u8>>>>() {
+        Err(BinaryError::At { source, .. }) if *source == BinaryError::RecursionLimitExceeded => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+    assert_eq!(reader.depth(), 0);
+
+    // Sixteen elements are under the limit, but sixteen u64s are not.
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.set_codec(Codec::Fixed);
+    writer.write_vec(&[vec![0u64; 16]]).expect("Failed to write vec");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.set_codec(Codec::Fixed);
+    reader.seek_to(0).expect("Failed to seek");
+    reader.set_max_alloc(100);
+    match reader.read_vec::<Vec<u64>>() {
+        Err(BinaryError::At { source, .. })
+            if *source == BinaryError::AllocationTooLarge { requested: 128, limit: 100 } => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn fixed_codec_golden_bytes() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::with_endian(&mut stream, Endian::Big);
+    writer.set_codec(Codec::Fixed);
+    writer
+        .write_vec(&[Event::Ping, Event::Say { text: "ok".to_string(), loud: false }])
+        .expect("Failed to write vec");
+    writer.write_option(&Some((0x0102u16, 'A', -1i8))).expect("Failed to write option");
+
+    assert_eq!(
+        stream.as_slice(),
+        &[
+            // write_vec count
+            0, 0, 0, 0, 0, 0, 0, 2,
+            // Event::Ping
+            0, 0, 0, 0,
+            // Event::Say { text: "ok", loud: false }
+            0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0, 2, b'o', b'k', 0,
+            // Some((0x0102, 'A', -1))
+            1, 1, 2, 0, 0, 0, 0x41, 0xFF,
+        ][..]
+    );
+}
+
+#[test]
+fn read_write_vec() {
+    let empty: Vec<u32> = Vec::new();
+    let numbers: Vec<u32> = vec![1, 2, 3, 0xFFFF_FFFF];
+    let strings = vec!["alpha".to_string(), String::new(), "gamma".to_string()];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_vec(&empty).expect("Failed to write vec");
+    writer.write_vec(&numbers).expect("Failed to write vec");
+    writer.write_vec(&strings).expect("Failed to write vec");
+    writer.write_usize(usize::MAX).expect("Failed to write count");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_vec::<u32>().expect("Failed to read vec"), empty);
+    assert_eq!(reader.read_vec::<u32>().expect("Failed to read vec"), numbers);
+    assert_eq!(reader.read_vec::<String>().expect("Failed to read vec"), strings);
+    assert!(reader.read_vec::<u64>().is_err());
+}
+
+#[test]
+fn write_iter_matches_write_vec() {
+    let numbers: Vec<u32> = (0..10).filter(|n| n % 3 != 0).map(|n| n * 100).collect();
+
+    let mut expected = Memorystream::new().expect("Error");
+    BinaryWriter::new(&mut expected).write_vec(&numbers).expect("Failed to write vec");
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    let iter = (0..10u32).filter(|n| n % 3 != 0).map(|n| n * 100);
+    let written = writer.write_iter(numbers.len(), iter).expect("Failed to write iter");
+    assert_eq!(written, expected.len());
+    assert_eq!(stream.as_slice(), expected.as_slice());
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(
+        writer.write_iter(3, vec![1u8, 2]),
+        Err(BinaryError::CountMismatch { expected: 3, found: 2 })
+    );
+    assert_eq!(
+        writer.write_iter(1, 0u8..),
+        Err(BinaryError::CountMismatch { expected: 1, found: 2 })
+    );
+}
+
+#[test]
+fn read_vec_n_uses_external_count() {
+    let points = vec![(1i32, -1i32), (2, -2), (3, -3)];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u16(points.len() as u16).expect("Failed to write header");
+    for &(x, y) in &points {
+        writer.write_i32(x).expect("Failed to write i32");
+        writer.write_i32(y).expect("Failed to write i32");
+    }
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let count = reader.read_u16().expect("Failed to read header") as usize;
+    let batched: Vec<(i32, i32)> = reader.read_vec_n(count).expect("Failed to read points");
+    assert_eq!(batched, points);
+    assert_eq!(batched.capacity(), count);
+
+    reader.seek_to(2).expect("Failed to seek");
+    let mut manual = Vec::new();
+    for _ in 0..count {
+        manual.push((
+            reader.read_i32().expect("Failed to read i32"),
+            reader.read_i32().expect("Failed to read i32"),
+        ));
+    }
+    assert_eq!(batched, manual);
+
+    reader.set_max_alloc(64);
+    match reader.read_vec_n::<(i32, i32)>(9) {
+        Err(BinaryError::AllocationTooLarge { requested: 72, limit: 64 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn read_vec_until_eof() {
+    let points: Vec<(u16, i32)> = vec![(1, -1), (2, 40_000), (0xFFFF, 0)];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    for &(x, y) in &points {
+        writer.write_u16(x).expect("Failed to write u16");
+        writer.write_i32(y).expect("Failed to write i32");
+    }
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_vec_until_eof::<(u16, i32)>(6).expect("Failed to read"), points);
+    assert!(reader.read_vec_until_eof::<(u16, i32)>(6).expect("Failed to read").is_empty());
+
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u16(7).expect("Failed to write u16");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    match reader.read_vec_until_eof::<(u16, i32)>(6) {
+        Err(BinaryError::UnexpectedEof { expected: 6, got: 2 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn read_write_map() {
+    let mut scores: HashMap<String, u32> = HashMap::new();
+    scores.insert("alpha".to_string(), 1);
+    scores.insert("beta".to_string(), 20);
+    scores.insert(String::new(), 0xFFFF_FFFF);
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_map(&scores).expect("Failed to write map");
+    writer.write_usize(usize::MAX).expect("Failed to write count");
+
+    // Entry order is not preserved, so compare whole maps rather than bytes.
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let map: HashMap<String, u32> = reader.read_map().expect("Failed to read map");
+    assert_eq!(map, scores);
+    assert!(reader.read_map::<u32, u32>().is_err());
+}
+
+#[test]
+fn read_write_duration_and_system_time() {
+    let duration = Duration::new(3, 250_000_000);
+    let now = SystemTime::now();
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.write_duration(duration).expect("Failed to write duration"), 12);
+    writer.write_system_time(now).expect("Failed to write system time");
+    assert!(writer
+        .write_system_time(UNIX_EPOCH - Duration::from_secs(1))
+        .is_err());
+    writer.write_u64(0).expect("Failed to write u64");
+    writer.write_u32(1_000_000_000).expect("Failed to write u32");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_duration().expect("Failed to read duration"), duration);
+    assert_eq!(reader.read_system_time().expect("Failed to read system time"), now);
+    assert!(reader.read_duration().is_err());
+}
+
+#[test]
+fn read_write_socket_addr() {
+    let v4: SocketAddr = "192.168.1.20:8080".parse().expect("Failed to parse address");
+    let v6: SocketAddr = "[2001:db8::1]:443".parse().expect("Failed to parse address");
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.write_socket_addr(v4).expect("Failed to write address"), 7);
+    assert_eq!(writer.write_socket_addr(v6).expect("Failed to write address"), 19);
+    writer.write_u8(5).expect("Failed to write u8");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_socket_addr().expect("Failed to read address"), v4);
+    assert_eq!(reader.read_socket_addr().expect("Failed to read address"), v6);
+    assert_eq!(
+        reader.read_ip_addr(),
+        Err(BinaryError::At {
+            offset: 26,
+            source: Box::new(BinaryError::InvalidAddressFamily(5)),
+        })
+    );
+}
+
+#[test]
+fn write_slice_and_byte_vec() {
+    let payload = vec![0xDE, 0xAD, 0xBE, 0xEF, 0x00];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.write_slice(&payload[1..3]).expect("Failed to write slice"), 2);
+    assert_eq!(writer.write_byte_vec(&payload).expect("Failed to write bytes"), 13);
+    writer.set_length_prefix(LengthPrefix::U8);
+    writer.write_byte_vec(&[]).expect("Failed to write bytes");
+    assert_eq!(&stream.as_slice()[..4], &[0xAD, 0xBE, 5, 0]);
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(2).expect("Failed to seek");
+    assert_eq!(reader.read_byte_vec().expect("Failed to read bytes"), payload);
+    reader.set_length_prefix(LengthPrefix::U8);
+    assert!(reader.read_byte_vec().expect("Failed to read bytes").is_empty());
+    assert!(reader.read_byte_vec().is_err());
+}
+
+#[test]
+fn read_write_blobs() {
+    let blobs = vec![vec![1, 2, 3], Vec::new(), vec![0xAB; 300]];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.write_blobs(&blobs).expect("Failed to write blobs"), 8 + 11 + 8 + 308);
+    writer.write_u64(1).expect("Failed to write count");
+    writer.write_u64(1 << 40).expect("Failed to write length");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_blobs().expect("Failed to read blobs"), blobs);
+    assert!(matches!(
+        reader.read_blobs(),
+        Err(BinaryError::AllocationTooLarge { requested: 0x100_0000_0000, .. })
+    ));
+
+    reader.seek_to(0).expect("Failed to seek");
+    reader.set_max_alloc(100);
+    assert!(matches!(
+        reader.read_blobs(),
+        Err(BinaryError::AllocationTooLarge { requested: 300, limit: 100 })
+    ));
+}
+
+#[test]
+fn read_write_string_vec() {
+    let table = ["alpha", "", "γ", ""];
+    let empty: [&str; 0] = [];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::with_endian(&mut stream, Endian::Big);
+    assert_eq!(writer.write_string_vec(&table).expect("Failed to write table"), 8 + 13 + 8 + 10 + 8);
+    assert_eq!(writer.write_string_vec(&empty).expect("Failed to write table"), 8);
+    writer.write_u64(2).expect("Failed to write count");
+    writer.write_u64(500).expect("Failed to write length");
+
+    let mut reader = BinaryReader::with_endian(&mut stream, Endian::Big);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_string_vec().expect("Failed to read table"), table);
+    assert_eq!(reader.read_string_vec().expect("Failed to read table"), Vec::<String>::new());
+
+    reader.set_max_alloc(100);
+    assert!(matches!(
+        reader.read_string_vec(),
+        Err(BinaryError::AllocationTooLarge { requested: 500, limit: 100 })
+    ));
+    reader.seek_to(0).expect("Failed to seek");
+    reader.set_max_alloc(8);
+    assert!(matches!(
+        reader.read_string_vec(),
+        Err(BinaryError::AllocationTooLarge { limit: 8, .. })
+    ));
+}
+
+#[test]
+fn byte_order_type_parameter() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::<BigEndian>::with_byte_order(&mut stream);
+    writer.write_u32(0x0102_0304).expect("Failed to write u32");
+    writer.write_f64(1.5).expect("Failed to write f64");
+    writer.write_string("be").expect("Failed to write string");
+
+    let mut writer = BinaryWriter::<LittleEndian>::with_byte_order(&mut stream);
+    writer.write_u16(0x0506).expect("Failed to write u16");
+    writer
+        .write_checked_block(|w| w.write_i32(-2).map(|_| ()))
+        .expect("Failed to write block");
+    assert_eq!(&stream.as_slice()[..4], &[1, 2, 3, 4]);
+    assert_eq!(&stream.as_slice()[22..24], &[6, 5]);
+
+    let mut reader = BinaryReader::<BigEndian>::with_byte_order(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 0x0102_0304);
+    assert_eq!(reader.read_f64().expect("Failed to read f64"), 1.5);
+    assert_eq!(reader.read_string().expect("Failed to read string"), "be");
+
+    let mut reader = BinaryReader::<LittleEndian>::with_byte_order(&mut stream);
+    reader.seek_to(22).expect("Failed to seek");
+    assert_eq!(reader.read_u16().expect("Failed to read u16"), 0x0506);
+    assert_eq!(
+        reader.read_checked_block(|r| r.read_i32()).expect("Failed to read block"),
+        -2
+    );
+
+    // The runtime setting reads the same bytes the same way.
+    let mut reader = BinaryReader::with_endian(&mut stream, Endian::Big);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 0x0102_0304);
+}
+
+#[test]
+fn reader_writer_with_config() {
+    assert_eq!(BinaryConfig::builder().build(), BinaryConfig::default());
+
+    let config = BinaryConfig::builder()
+        .endian(Endian::Big)
+        .length_prefix(LengthPrefix::U32)
+        .max_alloc(16)
+        .pad_byte(0xEE)
+        .build();
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::with_config(&mut stream, config);
+    writer.write_string("hi").expect("Failed to write string");
+    writer.write_u16(0x0102).expect("Failed to write u16");
+    writer.write_padding(1).expect("Failed to write padding");
+    assert_eq!(stream.as_slice(), &[0, 0, 0, 2, b'h', b'i', 1, 2, 0xEE]);
+
+    let mut stream = Memorystream::from_vec(vec![0, 0, 0, 2, b'h', b'i', 1, 2, 0, 0, 0, 17]);
+    let mut reader = BinaryReader::with_config(&mut stream, config);
+    assert_eq!(reader.read_string().expect("Failed to read string"), "hi");
+    assert_eq!(reader.read_u16().expect("Failed to read u16"), 0x0102);
+    assert!(matches!(
+        reader.read_byte_vec(),
+        Err(BinaryError::AllocationTooLarge { requested: 17, limit: 16 })
+    ));
+}
+
+#[test]
+fn read_write_f32_array() {
+    let matrix: [f32; 16] = [
+        1.0, 0.0, 0.0, 0.0, //
+        0.0, 0.5, -0.25, 0.0, //
+        0.0, 0.25, 0.5, 0.0, //
+        10.0, -3.5, 7.125, 1.0,
+    ];
+
+    for endian in [Endian::Little, Endian::Big] {
+        let mut stream = Memorystream::new().expect("Error");
+        let mut writer = BinaryWriter::with_endian(&mut stream, endian);
+        assert_eq!(writer.write_f32_array(&matrix).expect("Failed to write array"), 64);
+        writer.write_f32_array(&[1.5f32, 2.5, 3.5]).expect("Failed to write array");
+
+        let mut reader = BinaryReader::with_endian(&mut stream, endian);
+        reader.seek_to(0).expect("Failed to seek");
+        let read = reader.read_f32_array::<16>().expect("Failed to read array");
+        assert_eq!(read, matrix);
+
+        reader.seek_to(0).expect("Failed to seek");
+        for value in read {
+            assert_eq!(reader.read_f32().expect("Failed to read f32"), value);
+        }
+        assert_eq!(reader.read_f32_array::<3>().expect("Failed to read array"), [1.5, 2.5, 3.5]);
+        assert!(reader.read_f32_array::<1>().is_err());
+    }
+}
+
+#[test]
+fn slice_readers_match_primitive_readers() {
+    let data: Vec<u8> = (0..96u32).map(|i| (i * 37 + 11) as u8).collect();
+
+    for endian in [Endian::Little, Endian::Big] {
+        let mut stream = Memorystream::from_vec(data.clone());
+        let mut reader = BinaryReader::with_endian(&mut stream, endian);
+
+        // Compared as bytes so float NaNs compare by bit pattern.
+        macro_rules! check {
+            ($slice:ident, $single:ident, $count:expr) => {
+                reader.seek_to(0).expect("Failed to seek");
+                let bulk = reader.$slice($count).expect("Failed to read slice");
+                reader.seek_to(0).expect("Failed to seek");
+                for value in bulk {
+                    let single = reader.$single().expect("Failed to read");
+                    assert_eq!(value.to_le_bytes(), single.to_le_bytes());
+                }
+            };
+        }
+
+        check!(read_u16_slice, read_u16, 48);
+        check!(read_i16_slice, read_i16, 48);
+        check!(read_u32_slice, read_u32, 24);
+        check!(read_i32_slice, read_i32, 24);
+        check!(read_u64_slice, read_u64, 12);
+        check!(read_i64_slice, read_i64, 12);
+        check!(read_f32_slice, read_f32, 24);
+        check!(read_f64_slice, read_f64, 12);
+
+        let mut expected = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        if endian == Endian::Big {
+            expected = expected.swap_bytes();
+        }
+        reader.seek_to(0).expect("Failed to seek");
+        assert_eq!(reader.read_u32_slice(1).expect("Failed to read slice"), vec![expected]);
+
+        // A failed read leaves the reader usable for the next one.
+        reader.seek_to(90).expect("Failed to seek");
+        assert!(reader.read_u64_slice(1).unwrap_err().is_eof());
+        reader.seek_to(0).expect("Failed to seek");
+        assert_eq!(reader.read_u32_slice(1).expect("Failed to read slice"), vec![expected]);
+    }
+}
+
+#[test]
+fn floats_preserve_nan_bits() {
+    let quiet_payload = f32::from_bits(0x7FC0_1234);
+    let signaling = f32::from_bits(0xFF80_0001);
+    let signaling_f64 = f64::from_bits(0x7FF0_0000_0000_0ABC);
+
+    for endian in [Endian::Little, Endian::Big] {
+        let mut stream = Memorystream::new().expect("Error");
+        let mut writer = BinaryWriter::with_endian(&mut stream, endian);
+        writer.write_f32(quiet_payload).expect("Failed to write f32");
+        writer.write_f32(signaling).expect("Failed to write f32");
+        writer.write_f64(signaling_f64).expect("Failed to write f64");
+        writer.write_f64(-0.0).expect("Failed to write f64");
+
+        let mut reader = BinaryReader::with_endian(&mut stream, endian);
+        reader.seek_to(0).expect("Failed to seek");
+        assert_eq!(reader.read_f32().expect("Failed to read f32").to_bits(), 0x7FC0_1234);
+        assert_eq!(reader.read_f32().expect("Failed to read f32").to_bits(), 0xFF80_0001);
+        assert_eq!(
+            reader.read_f64().expect("Failed to read f64").to_bits(),
+            0x7FF0_0000_0000_0ABC
+        );
+        assert_eq!(reader.read_f64().expect("Failed to read f64").to_bits(), (-0.0f64).to_bits());
+    }
+}
+
+#[test]
+fn seek_relative_and_from_end() {
+    let mut stream = create_writer_stream("seek_relative");
+    let mut writer = BinaryWriter::new(&mut stream);
+    for value in 0..4 {
+        writer.write_u32(value).expect("Failed to write u32");
+    }
+    assert_eq!(writer.seek(SeekOrigin::Current, -8).expect("Failed to seek"), 8);
+    writer.write_u32(20).expect("Failed to write u32");
+    assert!(writer.seek(SeekOrigin::Current, -13).is_err());
+
+    let mut stream = create_reader_stream("seek_relative");
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(reader.seek(SeekOrigin::End, -4).expect("Failed to seek"), 12);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 3);
+    assert_eq!(reader.seek(SeekOrigin::Current, -8).expect("Failed to seek"), 8);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 20);
+    assert_eq!(reader.seek(SeekOrigin::Start, 4).expect("Failed to seek"), 4);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 1);
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u64(5).expect("Failed to write u64");
+    writer.write_u16(6).expect("Failed to write u16");
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(reader.seek(SeekOrigin::End, -10).expect("Failed to seek"), 0);
+    assert_eq!(reader.read_u64().expect("Failed to read u64"), 5);
+    assert!(reader.seek(SeekOrigin::End, -11).is_err());
+
+    cleanup("seek_relative");
+}
+
+#[test]
+fn read_truncated_value_is_unexpected_eof() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u16(0xBEEF).expect("Failed to write u16");
+    writer.write_u8(0x01).expect("Failed to write u8");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_u16().expect("Failed to read u16"), 0xBEEF);
+    match reader.read_u32() {
+        Err(BinaryError::UnexpectedEof { expected: 4, got: 1 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let mut stream = create_writer_stream("truncated_value");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_bytes(&[1, 2, 3]).expect("Failed to write bytes");
+
+    let mut stream = create_reader_stream("truncated_value");
+    let mut reader = BinaryReader::new(&mut stream);
+    match reader.read_f64() {
+        Err(BinaryError::UnexpectedEof { expected: 8, got: 3 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    cleanup("truncated_value");
+}
+
+#[test]
+fn read_bytes_past_end_is_not_zero_padded() {
+    let mut stream = create_writer_stream("read_bytes_past_end");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_bytes(&[9, 8, 7]).expect("Failed to write bytes");
+
+    let mut stream = create_reader_stream("read_bytes_past_end");
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(
+        reader.peek_bytes(5),
+        Err(BinaryError::UnexpectedEof {
+            expected: 5,
+            got: 3,
+        })
+    );
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 0);
+    assert_eq!(
+        reader.read_bytes(5),
+        Err(BinaryError::UnexpectedEof {
+            expected: 5,
+            got: 3,
+        })
+    );
+
+    cleanup("read_bytes_past_end");
+}
+
+#[test]
+fn stored_offsets_beyond_usize_are_rejected() {
+    let offset = u32::MAX as u64 + 1;
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u64(offset).expect("Failed to write offset");
+    writer.write_u64(offset).expect("Failed to write length");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    if cfg!(target_pointer_width = "32") {
+        assert_eq!(reader.read_usize(), Err(BinaryError::OffsetOutOfRange(offset)));
+        assert_eq!(reader.read_string(), Err(BinaryError::OffsetOutOfRange(offset)));
+    } else {
+        assert_eq!(reader.read_usize().expect("Failed to read usize") as u64, offset);
+        assert!(matches!(
+            reader.read_string(),
+            Err(BinaryError::AllocationTooLarge { .. })
+        ));
+    }
+}
+
+#[test]
+fn allocation_limit() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_usize(usize::MAX).expect("Failed to write length");
+    writer
+        .write_string("within limit")
+        .expect("Failed to write string");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    match reader.read_string() {
+        Err(BinaryError::AllocationTooLarge { requested, limit }) => {
+            assert_eq!(requested, usize::MAX);
+            assert_eq!(limit, 64 * 1024 * 1024);
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+    assert_eq!(reader.read_string().expect("Failed to read string"), "within limit");
+
+    reader.set_max_alloc(4);
+    reader.seek_to(0).expect("Failed to seek");
+    match reader.read_bytes(5) {
+        Err(BinaryError::AllocationTooLarge { requested: 5, limit: 4 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+    assert_eq!(reader.read_bytes(4).expect("Failed to read bytes").len(), 4);
+
+    reader.seek_to(0).expect("Failed to seek");
+    assert!(reader.read_vec::<u8>().is_err());
+}
+
+#[test]
+fn position_guard_restores_on_error() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u32(5).expect("Failed to write u32");
+    {
+        let mut guard = writer.position_guard().expect("Failed to take guard");
+        guard.seek_to(64).expect("Failed to seek");
+        guard.write_u8(1).expect("Failed to write u8");
+    }
+    assert_eq!(writer.get_cur_pos().expect("Failed to get pos"), 4);
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(2).expect("Failed to seek");
+
+    let look_ahead = |reader: &mut BinaryReader| -> Result<u32, BinaryError> {
+        let mut guard = reader.position_guard()?;
+        assert_eq!(guard.position(), 2);
+        guard.seek_to(0)?;
+        guard.read_u32()?;
+        guard.seek_to(64)?;
+        guard.read_u32()
+    };
+    assert!(look_ahead(&mut reader).is_err());
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 2);
+}
+
+#[test]
+fn try_read_rewinds_on_failure() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u8(2).expect("Failed to write u8");
+    writer.write_u32(0xAABB_CCDD).expect("Failed to write u32");
+    writer.write_u16(0x1122).expect("Failed to write u16");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(1).expect("Failed to seek");
+    let result = reader.try_read(|r| {
+        let a = r.read_u32()?;
+        let b = r.read_u32()?;
+        Ok((a, b))
+    });
+    assert!(matches!(result, Err(BinaryError::UnexpectedEof { expected: 4, got: 2 })));
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 1);
+
+    let value = reader
+        .try_read(|r| Ok((r.read_u32()?, r.read_u16()?)))
+        .expect("Failed to read");
+    assert_eq!(value, (0xAABB_CCDD, 0x1122));
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 7);
+}
+
+#[test]
+fn peek_does_not_advance() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u8(7).expect("Failed to write u8");
+    writer.write_u32(0xDEAD_BEEF).expect("Failed to write u32");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.peek_u8().expect("Failed to peek u8"), 7);
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 0);
+    assert_eq!(reader.peek_bytes(2).expect("Failed to peek bytes"), vec![7, 0xEF]);
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 0);
+    assert_eq!(reader.read_u8().expect("Failed to read u8"), 7);
+    assert_eq!(reader.peek_u32().expect("Failed to peek u32"), 0xDEAD_BEEF);
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 1);
+
+    reader.seek_to(3).expect("Failed to seek");
+    assert!(reader.peek_u32().is_err());
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 3);
+    assert!(reader.peek_bytes(3).is_err());
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 3);
+}
+
+#[test]
+fn skip_past_fields() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u64(1).expect("Failed to write u64");
+    writer.write_u16(2).expect("Failed to write u16");
+    writer.write_u32(3).expect("Failed to write u32");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.skip(10).expect("Failed to skip"), 10);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 3);
+
+    match reader.skip(usize::MAX) {
+        Err(BinaryError::StreamError(StreamError::SeekError)) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn read_bytes_into_reused_buffer() {
+    let data: Vec<u8> = (0..64).collect();
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_bytes(&data).expect("Failed to write bytes");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let mut chunks = Vec::new();
+    let mut scratch = [0u8; 16];
+    for _ in 0..4 {
+        let read = reader.read_bytes_into(&mut scratch).expect("Failed to read bytes");
+        assert_eq!(read, 16);
+        chunks.extend_from_slice(&scratch);
+    }
+    assert_eq!(chunks, data);
+
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_bytes(64).expect("Failed to read bytes"), chunks);
+
+    reader.seek_to(60).expect("Failed to seek");
+    match reader.read_bytes_into(&mut scratch) {
+        Err(BinaryError::UnexpectedEof { expected: 16, got: 4 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn write_bytes_sub_slice() {
+    let mut stream = Memorystream::new().expect("Failed to open memory stream");
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    let buffer: Vec<u8> = (0..16).collect();
+    writer
+        .write_bytes(&buffer[4..8])
+        .expect("Failed to write bytes");
+    writer
+        .write_bytes(&buffer[12..])
+        .expect("Failed to write bytes");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let value = reader.read_bytes(8).expect("Failed to read bytes");
+    assert_eq!(vec![4, 5, 6, 7, 12, 13, 14, 15], value);
+}
+
+#[test]
+fn flush_persists_file_writes() {
+    let mut stream = create_writer_stream("flush");
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    writer.write_u32(0xDEADBEEF).expect("Failed to write u32");
+    writer
+        .write_string("flushed")
+        .expect("Failed to write string");
+    writer.flush().expect("Failed to flush");
+
+    let mut stream = create_reader_stream("flush");
+    let mut reader = BinaryReader::new(&mut stream);
+
+    assert_eq!(0xDEADBEEF, reader.read_u32().expect("Failed to read u32"));
+    assert_eq!("flushed", reader.read_string().expect("Failed to read string"));
+
+    cleanup("flush");
+}
+
+#[test]
+fn tcp_stream_round_trips_frames() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Failed to bind listener");
+    let address = listener.local_addr().expect("Failed to get local address");
+
+    let server = std::thread::spawn(move || {
+        let (socket, _) = listener.accept().expect("Failed to accept connection");
+        let mut stream = TcpStreamWrapper::new(socket);
+        let mut frames = Vec::new();
+
+        for _ in 0..3 {
+            let mut reader = BinaryReader::new(&mut stream);
+            let len = reader.read_u32().expect("Failed to read frame length");
+            let frame = reader.read_bytes(len as usize).expect("Failed to read frame");
+
+            let mut writer = BinaryWriter::new(&mut stream);
+            writer.write_u32(len).expect("Failed to write frame length");
+            writer.write_bytes(&frame).expect("Failed to write frame");
+            writer.flush().expect("Failed to flush");
+            frames.push(frame);
+        }
+
+        frames
+    });
+
+    let socket = std::net::TcpStream::connect(address).expect("Failed to connect");
+    let mut stream = TcpStreamWrapper::new(socket);
+    let messages: [&[u8]; 3] = [b"hello", b"", b"length-prefixed"];
+
+    for message in messages.iter() {
+        let mut writer = BinaryWriter::new(&mut stream);
+        writer
+            .write_u32(message.len() as u32)
+            .expect("Failed to write frame length");
+        writer.write_bytes(message).expect("Failed to write frame");
+        writer.flush().expect("Failed to flush");
+
+        let mut reader = BinaryReader::new(&mut stream);
+        let len = reader.read_u32().expect("Failed to read frame length");
+        let echo = reader.read_bytes(len as usize).expect("Failed to read frame");
+        assert_eq!(*message, &echo[..]);
+    }
+
+    let mut reader = BinaryReader::new(&mut stream);
+    match reader.seek_to(0) {
+        Err(BinaryError::StreamError(StreamError::SeekError)) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+    match reader.get_cur_pos() {
+        Err(BinaryError::StreamError(StreamError::TellError)) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let frames = server.join().expect("Server thread panicked");
+    assert_eq!(3, frames.len());
+}
+
+#[test]
+fn chain_stream_reads_across_segments() {
+    let first = Memorystream::from_vec(vec![1, 2, 0x78, 0x56]);
+    let empty = Memorystream::new().expect("Error");
+    let last = Memorystream::from_vec(vec![0x34, 0x12, 9]);
+    let mut stream = ChainStream::new(vec![Box::new(first), Box::new(empty), Box::new(last)])
+        .expect("Failed to create chain");
+    assert_eq!(stream.stream_len().expect("Failed to get len"), 7);
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(2).expect("Failed to seek");
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 0x1234_5678);
+    assert_eq!(reader.get_cur_pos().expect("Failed to tell"), 6);
+    assert_eq!(reader.read_u8().expect("Failed to read u8"), 9);
+    assert!(reader.read_u8().is_err());
+
+    reader.seek_to(1).expect("Failed to seek");
+    assert_eq!(reader.read_bytes(5).expect("Failed to read bytes"), vec![2, 0x78, 0x56, 0x34, 0x12]);
+
+    assert_eq!(stream.write(&[0]), Err(StreamError::WriteError));
+}
+
+#[test]
+fn owned_reader_and_writer_one_liners() {
+    let mut owned = BinaryWriter::to_vec();
+    let mut writer = owned.writer();
+    writer.write_u32(0xDEAD_BEEF).expect("Failed to write u32");
+    writer.write_string("owned").expect("Failed to write string");
+    owned.writer().write_u8(7).expect("Failed to write u8");
+    let bytes = owned.finish();
+
+    let mut owned = BinaryReader::from_bytes(&bytes);
+    assert_eq!(owned.reader().read_u32().expect("Failed to read u32"), 0xDEAD_BEEF);
+    assert_eq!(owned.reader().read_string().expect("Failed to read string"), "owned");
+    assert_eq!(owned.remaining(), &[7]);
+    assert_eq!(owned.reader().read_u8().expect("Failed to read u8"), 7);
+
+    let bytes = {
+        let mut owned = BinaryWriter::to_vec().with_endian(Endian::Big);
+        owned.writer().write_u16(0x0102).expect("Failed to write u16");
+        owned.finish()
+    };
+    assert_eq!(bytes, vec![1, 2]);
+    assert_eq!(
+        BinaryReader::from_bytes(&bytes)
+            .with_endian(Endian::Big)
+            .reader()
+            .read_u16()
+            .expect("Failed to read u16"),
+        0x0102
+    );
+}
+
+#[test]
+fn retry_stream_recovers_from_transient_errors() {
+    let script = [
+        MockAction::Fail(StreamError::ReadError),
+        MockAction::Fail(StreamError::ReadError),
+        MockAction::Succeed,
+    ];
+    let mock = MockStream::with_script(vec![0x78, 0x56, 0x34, 0x12], script);
+    let mut stream = RetryStream::new(mock, 2).with_backoff(Duration::ZERO);
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 0x1234_5678);
+    assert_eq!(stream.retries(), 2);
+
+    let script = [
+        MockAction::Fail(StreamError::WriteError),
+        MockAction::Fail(StreamError::WriteError),
+        MockAction::Fail(StreamError::WriteError),
+    ];
+    let mock = MockStream::with_script(Vec::new(), script);
+    let mut stream = RetryStream::new(mock, 2).with_backoff(Duration::ZERO);
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(
+        writer.write_u8(1),
+        Err(BinaryError::StreamError(StreamError::WriteError))
+    );
+    assert_eq!(stream.retries(), 2);
+
+    // Errors outside the retryable set surface immediately.
+    let mock = MockStream::with_script(vec![1], [MockAction::Fail(StreamError::SeekError)]);
+    let mut stream = RetryStream::new(mock, 5)
+        .with_backoff(Duration::ZERO)
+        .with_retryable(&[StreamError::ReadError]);
+    assert_eq!(stream.read(&mut [0]), Err(StreamError::SeekError));
+    assert_eq!(stream.retries(), 0);
+    assert_eq!(stream.get_ref().remaining_actions(), 0);
+}
+
+#[test]
+fn slice_stream_reads_borrowed_bytes() {
+    let bytes = [
+        0x2A, 0x34, 0x12, 0x78, 0x56, 0x34, 0x12, 0x00, 0x00, 0x80, 0x3F, 0xFF,
+    ];
+
+    let mut stream = SliceStream::new(&bytes);
+    let mut reader = BinaryReader::new(&mut stream);
+
+    assert_eq!(0x2A, reader.read_u8().expect("Failed to read u8"));
+    assert_eq!(1, reader.get_cur_pos().expect("Failed to tell"));
+    assert_eq!(0x1234, reader.read_u16().expect("Failed to read u16"));
+    assert_eq!(3, reader.get_cur_pos().expect("Failed to tell"));
+    assert_eq!(0x12345678, reader.read_u32().expect("Failed to read u32"));
+    assert_eq!(7, reader.get_cur_pos().expect("Failed to tell"));
+    assert_eq!(1.0, reader.read_f32().expect("Failed to read f32"));
+    assert_eq!(11, reader.get_cur_pos().expect("Failed to tell"));
+
+    reader.seek_to(3).expect("Failed to seek");
+    assert_eq!(0x12345678, reader.read_u32().expect("Failed to read u32"));
+
+    reader.seek_to(11).expect("Failed to seek");
+    match reader.read_u16() {
+        Err(BinaryError::UnexpectedEof { expected: 2, got: 1 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn slice_stream_rejects_writes() {
+    let bytes = [0u8; 4];
+    let mut stream = SliceStream::new(&bytes);
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    match writer.write_u8(1) {
+        Err(BinaryError::StreamError(StreamError::WriteError)) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn memory_stream_into_vec() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    writer.write_u8(0x01).expect("Failed to write u8");
+    writer.write_u16(0x0302).expect("Failed to write u16");
+    writer
+        .write_string("hi")
+        .expect("Failed to write string");
+    writer.write_i32(-1).expect("Failed to write i32");
+
+    writer.seek_to(1).expect("Failed to seek");
+    assert_eq!(17, stream.len());
+    assert_eq!(&[0x01, 0x02, 0x03], &stream.as_slice()[..3]);
+
+    assert_eq!(
+        vec![
+            0x01, 0x02, 0x03, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, b'h', b'i', 0xFF,
+            0xFF, 0xFF, 0xFF,
+        ],
+        stream.into_vec()
+    );
+}
+
+#[test]
+fn memory_stream_from_vec() {
+    let mut stream = Memorystream::from_vec(vec![0x2A, 0x78, 0x56, 0x34, 0x12, 0x01]);
+    let mut reader = BinaryReader::new(&mut stream);
+
+    assert_eq!(0x2A, reader.read_u8().expect("Failed to read u8"));
+    assert_eq!(0x12345678, reader.read_u32().expect("Failed to read u32"));
+    assert!(reader.read_bool().expect("Failed to read bool"));
+
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u8(0xFF).expect("Failed to write u8");
+    assert_eq!(vec![0x2A, 0x78, 0x56, 0x34, 0x12, 0x01, 0xFF], stream.into_vec());
+}
+
+#[test]
+fn memory_stream_with_capacity() {
+    let mut preallocated = Memorystream::with_capacity(1024);
+    let mut growing = Memorystream::new().expect("Error");
+    assert!(preallocated.is_empty());
+
+    for stream in [&mut preallocated, &mut growing] {
+        let mut writer = BinaryWriter::new(stream);
+        for i in 0..100u32 {
+            writer.write_u32(i).expect("Failed to write u32");
+        }
+    }
+
+    assert_eq!(400, preallocated.len());
+    assert_eq!(growing.into_vec(), preallocated.into_vec());
+}
+
+#[test]
+fn file_mode_truncate_and_append() {
+    let name = "file_modes";
+
+    let mut stream = create_writer_stream(name);
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u32(1).expect("Failed to write u32");
+    writer.write_u32(2).expect("Failed to write u32");
+
+    let mut stream = Filestream::open_with(name, FileMode::Append).expect("Failed to open stream");
+    assert_eq!(8, stream.tell().expect("Failed to tell"));
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u32(3).expect("Failed to write u32");
+    assert_eq!(12, writer.get_cur_pos().expect("Failed to get pos"));
+    writer.seek_to(0).expect("Failed to seek");
+    writer.write_u32(5).expect("Failed to write u32");
+    assert_eq!(16, writer.get_cur_pos().expect("Failed to get pos"));
+
+    let mut stream = Filestream::open_with(name, FileMode::Read).expect("Failed to open stream");
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(16, reader.seek_to_end().expect("Failed to seek"));
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(1, reader.read_u32().expect("Failed to read u32"));
+    assert_eq!(2, reader.read_u32().expect("Failed to read u32"));
+    assert_eq!(3, reader.read_u32().expect("Failed to read u32"));
+    assert_eq!(5, reader.read_u32().expect("Failed to read u32"));
+
+    let mut stream = Filestream::open_with(name, FileMode::Truncate).expect("Failed to open stream");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u32(4).expect("Failed to write u32");
+
+    let mut stream = Filestream::open_with(name, FileMode::Read).expect("Failed to open stream");
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(4, reader.seek_to_end().expect("Failed to seek"));
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(4, reader.read_u32().expect("Failed to read u32"));
+
+    cleanup(name);
+}
+
+#[test]
+fn file_mode_read_only_rejects_writes() {
+    let name = "file_mode_read_only";
+    create_writer_stream(name);
+
+    let mut stream = Filestream::open_with(name, FileMode::Read).expect("Failed to open stream");
+    let mut writer = BinaryWriter::new(&mut stream);
+    match writer.write_u8(1) {
+        Err(BinaryError::StreamError(StreamError::WriteError)) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    cleanup(name);
+}
+
+struct CountingStream<S: Stream> {
+    inner: S,
+    reads: usize,
+    writes: usize,
+}
+
+impl<S: Stream> Stream for CountingStream<S> {
+    fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        self.writes += 1;
+        self.inner.write(bytes)
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        self.reads += 1;
+        self.inner.read(buffer)
+    }
+
+    fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        self.inner.seek(to)
+    }
+
+    fn stream_len(&mut self) -> Result<usize, StreamError> {
+        self.inner.stream_len()
+    }
+
+    fn tell(&mut self) -> Result<usize, StreamError> {
+        self.inner.tell()
+    }
+}
+
+#[test]
+fn zero_length_reads_and_writes() {
+    let mut stream = CountingStream {
+        inner: Memorystream::new().expect("Error"),
+        reads: 0,
+        writes: 0,
+    };
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_string("").expect("Failed to write string");
+    writer.write_bytes(&[]).expect("Failed to write bytes");
+    assert_eq!(stream.writes, 1);
+    assert_eq!(stream.inner.len(), 8);
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_string().expect("Failed to read string"), "");
+    assert_eq!(reader.read_bytes(0).expect("Failed to read bytes"), Vec::<u8>::new());
+    assert_eq!(reader.read_string_with_len(0).expect("Failed to read string"), "");
+    assert_eq!(stream.reads, 1);
+
+    // An empty write doesn't zero-fill up to a cursor past the end.
+    let mut memory = Memorystream::new().expect("Error");
+    memory.seek(16).expect("Failed to seek");
+    assert_eq!(memory.write(&[]).expect("Failed to write"), 0);
+    assert_eq!(memory.len(), 0);
+
+    let bytes = [1u8, 2];
+    let mut streams: Vec<Box<dyn Stream>> = vec![
+        Box::new(Memorystream::from_vec(bytes.to_vec())),
+        Box::new(SliceStream::new(&bytes)),
+        Box::new(SinkStream::new()),
+        Box::new(BufferedStream::new(Memorystream::from_vec(bytes.to_vec()))),
+        Box::new(
+            ChainStream::new(vec![Box::new(Memorystream::from_vec(bytes.to_vec()))])
+                .expect("Failed to create chain"),
+        ),
+    ];
+    for stream in streams.iter_mut() {
+        assert_eq!(stream.read(&mut []).expect("Failed to read"), 0);
+        assert_eq!(stream.write(&[]).expect("Failed to write"), 0);
+    }
+}
+
+#[test]
+fn skip_validates_without_decoding() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_string("x".repeat(4096)).expect("Failed to write string");
+    writer.write_vec(&[1u32, 2, 3]).expect("Failed to write vec");
+    writer.write_byte_vec(&[9; 100]).expect("Failed to write bytes");
+    let bytes = stream.into_vec();
+
+    fn validate(reader: &mut BinaryReader) -> Result<(), BinaryError> {
+        reader.skip_string()?;
+        reader.skip_vec(4)?;
+        reader.skip_byte_vec()
+    }
+
+    // Skipping reads only the length prefixes, where a full parse also reads
+    // every body.
+    let mut counting = CountingStream {
+        inner: Memorystream::from_vec(bytes.clone()),
+        reads: 0,
+        writes: 0,
+    };
+    validate(&mut BinaryReader::new(&mut counting)).expect("Failed to validate");
+    assert_eq!(counting.inner.position(), bytes.len());
+    assert_eq!(counting.reads, 3);
+
+    counting.inner.rewind();
+    counting.reads = 0;
+    let mut reader = BinaryReader::new(&mut counting);
+    reader.read_string().expect("Failed to read string");
+    reader.read_vec::<u32>().expect("Failed to read vec");
+    reader.read_byte_vec().expect("Failed to read bytes");
+    assert_eq!(counting.reads, 8);
+
+    // The default `seek_end` reports the end as an absolute position.
+    counting.inner.set_position(2);
+    assert_eq!(counting.seek_end().expect("Failed to seek to end"), bytes.len());
+
+    // A truncated body is caught without seeking past the end.
+    let mut truncated = Memorystream::from_vec(bytes[..bytes.len() - 1].to_vec());
+    let mut reader = BinaryReader::new(&mut truncated);
+    assert_eq!(
+        validate(&mut reader),
+        Err(BinaryError::UnexpectedEof { expected: 100, got: 99 })
+    );
+
+    // Streams without a length are read through and discarded.
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Failed to bind listener");
+    let mut sender = std::net::TcpStream::connect(listener.local_addr().expect("Failed to get address"))
+        .expect("Failed to connect");
+    let (socket, _) = listener.accept().expect("Failed to accept connection");
+    std::io::Write::write_all(&mut sender, &[0; 10]).expect("Failed to send");
+    drop(sender);
+    let mut socket = TcpStreamWrapper::new(socket);
+    let mut reader = BinaryReader::new(&mut socket);
+    reader.skip_bytes(6).expect("Failed to skip");
+    assert_eq!(reader.skip_bytes(6), Err(BinaryError::UnexpectedEof { expected: 6, got: 4 }));
+}
+
+#[test]
+fn buffered_stream_seeks_across_buffer_boundary() {
+    let data: Vec<u8> = (0..64).collect();
+    let mut stream = BufferedStream::with_capacity(Memorystream::from_vec(data), 16);
+    let mut reader = BinaryReader::new(&mut stream);
+
+    reader.seek_to(20).expect("Failed to seek");
+    assert_eq!(20, reader.read_u8().expect("Failed to read u8"));
+    assert_eq!(21, reader.get_cur_pos().expect("Failed to tell"));
+
+    reader.seek_to(14).expect("Failed to seek");
+    assert_eq!(
+        vec![14, 15, 16, 17],
+        reader.read_bytes(4).expect("Failed to read bytes")
+    );
+    assert_eq!(18, reader.get_cur_pos().expect("Failed to tell"));
+
+    reader.seek_to(30).expect("Failed to seek");
+    assert_eq!(
+        u32::from_le_bytes([30, 31, 32, 33]),
+        reader.read_u32().expect("Failed to read u32")
+    );
+
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.seek_to(2).expect("Failed to seek");
+    writer.write_u8(0xFF).expect("Failed to write u8");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(3, reader.read_u8().expect("Failed to read u8"));
+    reader.seek_to(2).expect("Failed to seek");
+    assert_eq!(0xFF, reader.read_u8().expect("Failed to read u8"));
+}
+
+#[test]
+fn buffered_stream_reduces_underlying_reads() {
+    let mut source = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut source);
+    for i in 0..1024u32 {
+        writer.write_u32(i).expect("Failed to write u32");
+    }
+    let data = source.into_vec();
+
+    let mut unbuffered = CountingStream {
+        inner: Memorystream::from_vec(data.clone()),
+        reads: 0,
+        writes: 0,
+    };
+    let mut reader = BinaryReader::new(&mut unbuffered);
+    for i in 0..1024u32 {
+        assert_eq!(i, reader.read_u32().expect("Failed to read u32"));
+    }
+
+    let mut buffered = BufferedStream::new(CountingStream {
+        inner: Memorystream::from_vec(data),
+        reads: 0,
+        writes: 0,
+    });
+    let mut reader = BinaryReader::new(&mut buffered);
+    for i in 0..1024u32 {
+        assert_eq!(i, reader.read_u32().expect("Failed to read u32"));
+    }
+
+    assert_eq!(1024, unbuffered.reads);
+    assert!(buffered.get_ref().reads <= 2);
+}
+
+#[test]
+fn buffered_write_stream_batches_writes() {
+    let mut stream = BufferedWriteStream::with_capacity(
+        CountingStream {
+            inner: Memorystream::new().expect("Error"),
+            reads: 0,
+            writes: 0,
+        },
+        256,
+    );
+    let mut writer = BinaryWriter::new(&mut stream);
+    for i in 0..1024u32 {
+        writer.write_u32(i).expect("Failed to write u32");
+    }
+    assert_eq!(writer.get_cur_pos().expect("Failed to tell"), 4096);
+    assert_eq!(stream.get_ref().writes, 15);
+    assert_eq!(stream.buffered(), 256);
+
+    // Seeking flushes the buffer before moving.
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.seek_to(4).expect("Failed to seek");
+    writer.write_u32(0xFFFF_FFFF).expect("Failed to write u32");
+    assert_eq!(stream.get_ref().writes, 16);
+
+    let counting = stream.into_inner().expect("Failed to flush");
+    assert_eq!(counting.writes, 17);
+
+    let mut inner = counting.inner;
+    let mut reader = BinaryReader::new(&mut inner);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 0);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 0xFFFF_FFFF);
+    reader.seek_to(4092).expect("Failed to seek");
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 1023);
+}
+
+#[test]
+fn buffered_stream_over_file() {
+    let mut stream = create_writer_stream("buffered");
+    let mut writer = BinaryWriter::new(&mut stream);
+    for i in 0..100u64 {
+        writer.write_u64(i).expect("Failed to write u64");
+    }
+
+    let mut stream = BufferedStream::new(create_reader_stream("buffered"));
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(50 * 8).expect("Failed to seek");
+    assert_eq!(50, reader.read_u64().expect("Failed to read u64"));
+    reader.seek_to(10 * 8).expect("Failed to seek");
+    assert_eq!(10, reader.read_u64().expect("Failed to read u64"));
+    assert_eq!(800, reader.seek_to_end().expect("Failed to seek"));
+
+    cleanup("buffered");
+}
+
+#[test]
+fn varint_round_trip() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    let unsigned = [
+        (0u64, 1),
+        (127, 1),
+        (128, 2),
+        (16383, 2),
+        (16384, 3),
+        (u64::MAX, 10),
+    ];
+    for &(value, len) in unsigned.iter() {
+        assert_eq!(
+            len,
+            writer
+                .write_varint_u64(value)
+                .expect("Failed to write varint")
+        );
+    }
+
+    let signed = [
+        (0i64, 1),
+        (-1, 1),
+        (63, 1),
+        (-64, 1),
+        (64, 2),
+        (i64::MAX, 10),
+        (i64::MIN, 10),
+    ];
+    for &(value, len) in signed.iter() {
+        assert_eq!(
+            len,
+            writer
+                .write_varint_i64(value)
+                .expect("Failed to write varint")
+        );
+    }
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    for &(value, _) in unsigned.iter() {
+        assert_eq!(
+            value,
+            reader.read_varint_u64().expect("Failed to read varint")
+        );
+    }
+    for &(value, _) in signed.iter() {
+        assert_eq!(
+            value,
+            reader.read_varint_i64().expect("Failed to read varint")
+        );
+    }
+}
+
+#[test]
+fn varint_rejects_overlong_encoding() {
+    let mut stream = Memorystream::from_vec(vec![0xFF; 11]);
+    let mut reader = BinaryReader::new(&mut stream);
+    match reader.read_varint_u64() {
+        Err(BinaryError::VarintTooLong) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let mut overflow = vec![0xFF; 9];
+    overflow.push(0x02);
+    let mut stream = Memorystream::from_vec(overflow);
+    let mut reader = BinaryReader::new(&mut stream);
+    match reader.read_varint_u64() {
+        Err(BinaryError::VarintTooLong) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn read_write_cstring() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    assert_eq!(
+        6,
+        writer
+            .write_cstring("hello")
+            .expect("Failed to write cstring")
+    );
+    assert_eq!(1, writer.write_cstring("").expect("Failed to write cstring"));
+    match writer.write_cstring("bad\0string") {
+        Err(BinaryError::InvalidData(_)) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+    writer.write_u8(7).expect("Failed to write u8");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!("hello", reader.read_cstring().expect("Failed to read cstring"));
+    assert_eq!("", reader.read_cstring().expect("Failed to read cstring"));
+    assert_eq!(7, reader.read_u8().expect("Failed to read u8"));
+}
+
+#[test]
+fn read_until_delimiter() {
+    let mut stream = Memorystream::from_vec(b"GET /\nHost\nend".to_vec());
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(reader.read_until(b'\n').expect("Failed to read"), b"GET /\n".to_vec());
+    assert_eq!(reader.read_until(b'\n').expect("Failed to read"), b"Host\n".to_vec());
+    assert_eq!(reader.read_until(b'\n').expect("Failed to read"), b"end".to_vec());
+    assert_eq!(
+        reader.read_until(b'\n'),
+        Err(BinaryError::UnexpectedEof {
+            expected: 1,
+            got: 0,
+        })
+    );
+
+    let mut stream = Memorystream::from_vec(vec![b'a'; 64]);
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.set_max_alloc(16);
+    assert_eq!(
+        reader.read_until(b'\n'),
+        Err(BinaryError::AllocationTooLarge {
+            requested: 17,
+            limit: 16,
+        })
+    );
+}
+
+#[test]
+fn hexdump_formats_like_xxd() {
+    let mut data = b"Hello, World!\n".to_vec();
+    data.extend_from_slice(&[0x00, 0x01, 0x7F, 0xFF, b'A']);
+    let mut stream = Memorystream::from_vec(data);
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(1).expect("Failed to seek");
+
+    let dump = reader.hexdump(18).expect("Failed to dump");
+    assert_eq!(
+        dump,
+        "00000001: 656c 6c6f 2c20 576f 726c 6421 0a00 017f  ello, World!....\n\
+         00000011: ff41                                     .A\n"
+    );
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 1);
+    assert_eq!(reader.hexdump(0).expect("Failed to dump"), "");
+    assert!(reader.hexdump(19).is_err());
+}
+
+#[test]
+fn write_cow_strings() {
+    let source = String::from("zero-copy");
+    let borrowed: Cow<str> = Cow::Borrowed(&source[..4]);
+    let owned: Cow<str> = Cow::Owned("zero".to_string());
+
+    let mut expected = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut expected);
+    writer.write_string("zero").expect("Failed to write string");
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.write_string(borrowed).expect("Failed to write string"), 4);
+    assert_eq!(writer.write_string(owned).expect("Failed to write string"), 4);
+    assert_eq!(&stream.as_slice()[..12], expected.as_slice());
+    assert_eq!(&stream.as_slice()[12..], expected.as_slice());
+}
+
+#[test]
+fn read_write_externally_framed_strings() {
+    let names = ["root", "", "caf\u{e9}"];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    for name in names.iter() {
+        writer.write_u16(name.len() as u16).expect("Failed to write length");
+    }
+    for name in names.iter() {
+        assert_eq!(writer.write_string_raw(name).expect("Failed to write string"), name.len());
+    }
+    writer.write_bytes(&[0xFF, 0xFE]).expect("Failed to write bytes");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let lens: Vec<usize> = (0..names.len())
+        .map(|_| reader.read_u16().expect("Failed to read length") as usize)
+        .collect();
+    for (name, &len) in names.iter().zip(lens.iter()) {
+        assert_eq!(reader.read_string_with_len(len).expect("Failed to read string"), *name);
+    }
+
+    let position = reader.get_cur_pos().expect("Failed to get pos");
+    assert!(reader.read_string_with_len(2).is_err());
+    reader.seek_to(position).expect("Failed to seek");
+    assert_eq!(
+        reader.read_string_with_len(3),
+        Err(BinaryError::UnexpectedEof {
+            expected: 3,
+            got: 2,
+        })
+    );
+    reader.seek_to(position).expect("Failed to seek");
+    reader.set_max_alloc(1);
+    assert_eq!(
+        reader.read_string_with_len(2),
+        Err(BinaryError::AllocationTooLarge {
+            requested: 2,
+            limit: 1,
+        })
+    );
+}
+
+#[test]
+fn expect_and_write_magic() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.write_magic(b"\x89PNG").expect("Failed to write magic"), 4);
+    writer.write_u16(7).expect("Failed to write u16");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    reader.expect_magic(b"\x89PNG").expect("Failed to match magic");
+    assert_eq!(reader.read_u16().expect("Failed to read u16"), 7);
+
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(
+        reader.expect_magic(b"GIF8"),
+        Err(BinaryError::At {
+            offset: 0,
+            source: Box::new(BinaryError::BadMagic {
+                expected: b"GIF8".to_vec(),
+                found: b"\x89PNG".to_vec(),
+            }),
+        })
+    );
+}
+
+#[test]
+fn read_write_nonzero() {
+    let id = NonZeroU32::new(7).expect("Zero id");
+    let big_id = NonZeroU64::new(u64::MAX).expect("Zero id");
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.write_nonzero_u32(id).expect("Failed to write u32"), 4);
+    assert_eq!(writer.write_nonzero_u64(big_id).expect("Failed to write u64"), 8);
+    writer.write_u32(0).expect("Failed to write u32");
+    writer.write_u64(0).expect("Failed to write u64");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_nonzero_u32().expect("Failed to read u32"), id);
+    assert_eq!(reader.read_nonzero_u64().expect("Failed to read u64"), big_id);
+    assert_eq!(
+        reader.read_nonzero_u32(),
+        Err(BinaryError::At {
+            offset: 12,
+            source: Box::new(BinaryError::UnexpectedZero),
+        })
+    );
+    assert_eq!(
+        reader.read_nonzero_u64(),
+        Err(BinaryError::At {
+            offset: 16,
+            source: Box::new(BinaryError::UnexpectedZero),
+        })
+    );
+}
+
+#[test]
+fn read_write_fixed_point() {
+    assert_eq!(BinaryWriter::to_fixed_point(19.99, 2).expect("Failed to scale"), 1999);
+    assert_eq!(BinaryWriter::to_fixed_point(-0.125, 2).expect("Failed to scale"), -13);
+    assert!(BinaryWriter::to_fixed_point(1.0e18, 2).is_err());
+    assert!(BinaryWriter::to_fixed_point(f64::NAN, 0).is_err());
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.write_fixed_point_f64(19.99, 2).expect("Failed to write"), 9);
+    writer.write_fixed_point(-1234567, 4).expect("Failed to write");
+    writer.write_fixed_point_f64(52.520008, 6).expect("Failed to write");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_fixed_point().expect("Failed to read"), (1999, 2));
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_fixed_point_f64().expect("Failed to read"), 19.99);
+    assert_eq!(reader.read_fixed_point_f64().expect("Failed to read"), -123.4567);
+    assert_eq!(reader.read_fixed_point().expect("Failed to read"), (52520008, 6));
+}
+
+#[test]
+fn read_string_bom() {
+    let mut data = vec![0xEF, 0xBB, 0xBF];
+    data.extend_from_slice("h\u{e9}llo".as_bytes());
+    data.extend_from_slice(&[0xFF, 0xFE, b'h', 0, 0xAC, 0x20]);
+    data.extend_from_slice(&[0xFE, 0xFF, 0, b'h', 0x20, 0xAC]);
+    data.extend_from_slice(b"plain");
+    data.extend_from_slice(&[0xFF, 0xFE, b'h']);
+
+    let mut stream = Memorystream::from_vec(data);
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(reader.read_string_bom(9).expect("Failed to read UTF-8"), "h\u{e9}llo");
+    assert_eq!(reader.read_string_bom(6).expect("Failed to read UTF-16LE"), "h\u{20ac}");
+    assert_eq!(reader.read_string_bom(6).expect("Failed to read UTF-16BE"), "h\u{20ac}");
+    assert_eq!(reader.read_string_bom(5).expect("Failed to read plain"), "plain");
+    assert!(reader.read_string_bom(3).is_err());
+}
+
+#[test]
+fn read_write_path() {
+    let path = Path::new("assets/textures/stone.png");
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_path(path).expect("Failed to write path");
+    writer.write_path(PathBuf::new()).expect("Failed to write path");
+    writer.write_u8(9).expect("Failed to write u8");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_path().expect("Failed to read path"), path);
+    assert_eq!(reader.read_path().expect("Failed to read path"), PathBuf::new());
+    assert!(reader.read_path().is_err());
+}
+
+#[cfg(unix)]
+#[test]
+fn read_write_non_utf8_path() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = Path::new(OsStr::from_bytes(b"data/\xFFname"));
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.write_path(path).expect("Failed to write path"), 1 + 8 + 10);
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_path().expect("Failed to read path"), path);
+}
+
+#[test]
+fn read_str_borrowed() {
+    let mut data = Vec::new();
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_string("borrowed").expect("Failed to write string");
+    writer.write_string("").expect("Failed to write string");
+    writer.write_u64(3).expect("Failed to write length");
+    writer.write_bytes(&[b'o', 0xFF, b'k']).expect("Failed to write bytes");
+    data.extend_from_slice(stream.as_slice());
+
+    let mut slice = SliceStream::new(&data);
+    let mut reader = BinaryReader::new(&mut slice);
+    let value = reader.read_str_borrowed().expect("Failed to read str");
+    assert_eq!(value, "borrowed");
+    assert_eq!(value.as_ptr(), data[8..].as_ptr());
+    assert_eq!(reader.read_str_borrowed().expect("Failed to read str"), "");
+    match reader.read_str_borrowed() {
+        Err(BinaryError::At { offset: 33, source }) => {
+            assert!(matches!(*source, BinaryError::InvalidUtf8(e) if e.valid_up_to() == 1))
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let address = reader.read_str_borrowed().expect("Failed to read str").as_ptr();
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 16);
+    assert_eq!(address, stream.as_slice()[8..].as_ptr());
+
+    stream.truncate(33);
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(24).expect("Failed to seek");
+    match reader.read_str_borrowed() {
+        Err(BinaryError::UnexpectedEof { expected: 3, got: 1 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+    assert!(BinaryReader::new(&mut SinkStream::new()).read_str_borrowed().is_err());
+}
+
+#[test]
+fn read_enum_discriminant() {
+    #[derive(Debug, PartialEq)]
+    enum Shape {
+        Circle,
+        Square,
+    }
+
+    let decode = |tag| match tag {
+        0 => Some(Shape::Circle),
+        1 => Some(Shape::Square),
+        _ => None,
+    };
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u32(1).expect("Failed to write u32");
+    writer.write_u32(7).expect("Failed to write u32");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_enum(decode).expect("Failed to read enum"), Shape::Square);
+    assert_eq!(
+        reader.read_enum(decode),
+        Err(BinaryError::At {
+            offset: 4,
+            source: Box::new(BinaryError::InvalidDiscriminant(7)),
+        })
+    );
+}
+
+#[test]
+fn read_write_tagged_values() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.write_tagged_u32(7).expect("Failed to write"), 5);
+    writer.write_tagged_string("name").expect("Failed to write");
+    writer.write_tagged_i64(-9).expect("Failed to write");
+    writer.write_tagged_f64(2.5).expect("Failed to write");
+    writer.write_tagged_bytes(&[1, 2]).expect("Failed to write");
+    writer.write_tagged_bool(true).expect("Failed to write");
+    writer.write_tagged(&TaggedValue::U8(3)).expect("Failed to write");
+    writer.write_u8(0xEE).expect("Failed to write");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let expected = vec![
+        TaggedValue::U32(7),
+        TaggedValue::Str("name".to_string()),
+        TaggedValue::I64(-9),
+        TaggedValue::F64(2.5),
+        TaggedValue::Bytes(vec![1, 2]),
+        TaggedValue::Bool(true),
+        TaggedValue::U8(3),
+    ];
+    for value in expected {
+        assert_eq!(reader.read_tagged().expect("Failed to read tagged"), value);
+    }
+
+    let offset = reader.get_cur_pos().expect("Failed to get pos");
+    assert_eq!(
+        reader.read_tagged(),
+        Err(BinaryError::At {
+            offset,
+            source: Box::new(BinaryError::UnknownTag(0xEE)),
+        })
+    );
+}
+
+#[test]
+fn adaptive_length_prefix_widths() {
+    let lengths = [
+        (0usize, 1),
+        (63, 1),
+        (64, 2),
+        (16383, 2),
+        (16384, 4),
+        (0x3FFF_FFFF, 4),
+        (0x4000_0000, 9),
+        (usize::MAX, 9),
+    ];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    for &(len, width) in &lengths {
+        assert_eq!(writer.write_adaptive_len(len).expect("Failed to write len"), width);
+    }
+    writer.write_string_adaptive("short").expect("Failed to write string");
+    let long = "y".repeat(300);
+    assert_eq!(writer.write_string_adaptive(&long).expect("Failed to write string"), 302);
+    writer.write_u8(0xC1).expect("Failed to write u8");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    for &(len, _) in &lengths {
+        assert_eq!(reader.read_adaptive_len().expect("Failed to read len"), len);
+    }
+    assert_eq!(reader.read_string_adaptive().expect("Failed to read string"), "short");
+    assert_eq!(reader.read_string_adaptive().expect("Failed to read string"), long);
+    assert!(reader.read_adaptive_len().is_err());
+}
+
+#[test]
+fn diff_streams() {
+    let data: Vec<u8> = (0..10_000u32).map(|i| i as u8).collect();
+
+    let mut a = Memorystream::from_vec(data.clone());
+    let mut b = Memorystream::from_vec(data.clone());
+    assert_eq!(binary_rw::diff(&mut a, &mut b).expect("Failed to diff"), None);
+
+    let mut changed = data.clone();
+    changed[5000] ^= 0xFF;
+    let mut c = Memorystream::from_vec(changed);
+    assert_eq!(binary_rw::diff(&mut a, &mut c).expect("Failed to diff"), Some(5000));
+
+    let mut short = Memorystream::from_vec(data[..4096].to_vec());
+    assert_eq!(binary_rw::diff(&mut a, &mut short).expect("Failed to diff"), Some(4096));
+    assert_eq!(binary_rw::diff(&mut short, &mut a).expect("Failed to diff"), Some(4096));
+
+    let mut empty = Memorystream::new().expect("Error");
+    assert_eq!(binary_rw::diff(&mut empty, &mut a).expect("Failed to diff"), Some(0));
+}
+
+#[test]
+fn progress_advances_without_moving_cursor() {
+    let mut stream = Memorystream::from_vec((0..64).collect());
+    let mut reader = BinaryReader::new(&mut stream);
+
+    let mut last = -1.0;
+    for i in 0..8 {
+        let (position, total) = reader.progress().expect("Failed to get progress");
+        assert_eq!((position, total), (i * 8, 64));
+        assert_eq!(reader.progress().expect("Failed to get progress"), (position, total));
+
+        let fraction = position as f64 / total as f64;
+        assert!(fraction > last);
+        last = fraction;
+
+        reader.read_u64().expect("Failed to read u64");
+    }
+    assert_eq!(reader.progress().expect("Failed to get progress"), (64, 64));
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("Failed to bind listener");
+    let socket = std::net::TcpStream::connect(listener.local_addr().expect("Failed to get address"))
+        .expect("Failed to connect");
+    let mut stream = TcpStreamWrapper::new(socket);
+    let mut reader = BinaryReader::new(&mut stream);
+    assert!(reader.progress().unwrap_err().is_stream_error());
+}
+
+#[test]
+fn read_write_string_varint() {
+    let short = "x".repeat(127);
+    let long = "y".repeat(20_000);
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.write_string_varint("").expect("Failed to write string"), 1);
+    assert_eq!(writer.write_string_varint(&short).expect("Failed to write string"), 1 + 127);
+    assert_eq!(writer.write_string_varint(&long).expect("Failed to write string"), 3 + 20_000);
+    writer.write_varint_u64(1024).expect("Failed to write varint");
+    assert_eq!(&stream.as_slice()[129..132], &[0xA0, 0x9C, 0x01]);
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_string_varint().expect("Failed to read string"), "");
+    assert_eq!(reader.read_string_varint().expect("Failed to read string"), short);
+    assert_eq!(reader.read_string_varint().expect("Failed to read string"), long);
+
+    reader.set_max_alloc(1000);
+    let position = reader.get_cur_pos().expect("Failed to get pos");
+    match reader.read_string_varint() {
+        Err(BinaryError::AllocationTooLarge { requested: 1024, limit: 1000 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), position + 2);
+}
+
+#[test]
+fn read_write_bigint() {
+    let large: Vec<u8> = (1..=40).collect();
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(writer.write_bigint(&[0, 0], Sign::NoSign).expect("Failed to write bigint"), 9);
+    assert_eq!(writer.write_bigint(&[0, 42], Sign::Plus).expect("Failed to write bigint"), 10);
+    writer.write_bigint(&large, Sign::Minus).expect("Failed to write bigint");
+    assert!(writer.write_bigint(&[], Sign::Plus).is_err());
+    assert!(writer.write_bigint(&[1], Sign::NoSign).is_err());
+    writer.write_u8(3).expect("Failed to write u8");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_bigint().expect("Failed to read bigint"), (Sign::NoSign, vec![]));
+    assert_eq!(reader.read_bigint().expect("Failed to read bigint"), (Sign::Plus, vec![42]));
+    assert_eq!(reader.read_bigint().expect("Failed to read bigint"), (Sign::Minus, large));
+    match reader.read_bigint() {
+        Err(BinaryError::At { source, .. }) => {
+            assert_eq!(*source, BinaryError::InvalidDiscriminant(3))
+        }
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[cfg(feature = "bigint")]
+#[test]
+fn read_write_num_bigint() {
+    use num_bigint::BigInt;
+
+    let values = [
+        BigInt::from(0),
+        BigInt::from(255),
+        BigInt::parse_bytes(b"-123456789012345678901234567890123456789", 10).expect("Bad literal"),
+    ];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    for value in &values {
+        writer.write_num_bigint(value).expect("Failed to write bigint");
+    }
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    for value in &values {
+        assert_eq!(&reader.read_num_bigint().expect("Failed to read bigint"), value);
+    }
+}
+
+#[test]
+fn read_exact_and_write_exact() {
+    let script = [MockAction::AcceptPartial(2), MockAction::AcceptPartial(3)];
+    let mut stream = MockStream::with_script(Vec::new(), script);
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_exact(&[1, 2, 3, 4, 5, 6]).expect("Failed to write exact");
+    assert_eq!(stream.as_slice(), &[1, 2, 3, 4, 5, 6]);
+
+    let script = [MockAction::AcceptPartial(2), MockAction::AcceptPartial(0)];
+    let mut stream = MockStream::with_script(Vec::new(), script);
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(
+        writer.write_exact(&[1, 2, 3]),
+        Err(BinaryError::ShortWrite { expected: 3, written: 2 })
+    );
+
+    let script = [MockAction::ShortRead(1), MockAction::ShortRead(2)];
+    let mut stream = MockStream::with_script(vec![1, 2, 3, 4, 5], script);
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(reader.read_exact(4).expect("Failed to read exact"), vec![1, 2, 3, 4]);
+    assert_eq!(
+        reader.read_exact(3),
+        Err(BinaryError::UnexpectedEof { expected: 3, got: 1 })
+    );
+}
+
+#[test]
+fn skip_unknown_record_tail() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    // Records from a newer version: a u32 length, the known u16 field,
+    // then fields this reader doesn't understand.
+    for (id, extra) in [(7u16, 5usize), (9u16, 3usize)] {
+        writer.write_u32(2 + extra as u32).expect("Failed to write length");
+        writer.write_u16(id).expect("Failed to write id");
+        writer.write_bytes(&vec![0xee; extra]).expect("Failed to write tail");
+    }
+    writer.write_u8(0x42).expect("Failed to write trailer");
+
+    stream.seek(0).expect("Failed to seek");
+    let mut reader = BinaryReader::new(&mut stream);
+
+    let len = reader.read_u32().expect("Failed to read length") as usize;
+    let end = reader.get_cur_pos().expect("Failed to tell") + len;
+    assert_eq!(7, reader.read_u16().expect("Failed to read id"));
+    reader.skip_to(end).expect("Failed to skip tail");
+    assert!(reader.skip_to(end - 1).is_err());
+
+    let len = reader.read_u32().expect("Failed to read length") as usize;
+    {
+        let mut scope = reader.scoped(len).expect("Failed to scope");
+        let mut record = scope.reader();
+        assert_eq!(9, record.read_u16().expect("Failed to read id"));
+        assert_eq!(3, record.skip_remaining_in_scope().expect("Failed to skip tail"));
+        assert_eq!(0, record.remaining().expect("Failed to get remaining"));
+    }
+
+    assert_eq!(0x42, reader.read_u8().expect("Failed to read trailer"));
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn read_write_json() {
+    let value = serde_json::json!({
+        "name": "binary",
+        "tags": [1, 2, { "nested": true }],
+        "meta": { "version": "1.2.1" }
+    });
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_json(&value).expect("Failed to write json");
+    writer.write_byte_vec(b"{not json").expect("Failed to write bytes");
+
+    stream.seek(0).expect("Failed to seek");
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(value, reader.read_json().expect("Failed to read json"));
+
+    let err = reader.read_json().unwrap_err();
+    assert!(matches!(err.root(), BinaryError::JsonError(_)));
+    assert!(err.is_decode_error());
+}
+
+#[test]
+fn copy_between_streams() {
+    let data: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+    let mut source = Memorystream::from_vec(data.clone());
+    let mut target = Memorystream::new().expect("Error");
+
+    let mut reader = BinaryReader::new(&mut source);
+    let mut writer = BinaryWriter::new(&mut target);
+    reader.seek_to(100).expect("Failed to seek");
+    writer.write_u8(0xff).expect("Failed to write u8");
+
+    assert_eq!(9000, binary_rw::copy(&mut reader, &mut writer, 9000).expect("Failed to copy"));
+    assert_eq!(9100, reader.get_cur_pos().expect("Failed to tell"));
+
+    match binary_rw::copy(&mut reader, &mut writer, 1000) {
+        Err(BinaryError::UnexpectedEof { expected: 1000, got: 900 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let copied = target.into_vec();
+    assert_eq!(0xff, copied[0]);
+    assert_eq!(&data[100..], &copied[1..]);
+}
+
+#[test]
+fn string_length_prefix_follows_endian() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::with_endian(&mut stream, Endian::Big);
+    writer.write_string("héllo").expect("Failed to write string");
+    assert_eq!(&[0, 0, 0, 0, 0, 0, 0, 6], &stream.as_slice()[..8]);
+
+    stream.seek(0).expect("Failed to seek");
+    let mut reader = BinaryReader::with_endian(&mut stream, Endian::Big);
+    assert_eq!("héllo", reader.read_string().expect("Failed to read string"));
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::with_endian(&mut stream, Endian::Little);
+    writer.write_string("héllo").expect("Failed to write string");
+    assert_eq!(&[6, 0, 0, 0, 0, 0, 0, 0], &stream.as_slice()[..8]);
+
+    stream.seek(0).expect("Failed to seek");
+    let mut reader = BinaryReader::with_endian(&mut stream, Endian::Little);
+    assert_eq!("héllo", reader.read_string().expect("Failed to read string"));
+}
+
+#[test]
+fn string_length_prefix_endian_mismatch() {
+    let mut stream = Memorystream::new().expect("Error");
+    let config = BinaryConfig::builder()
+        .endian(Endian::Big)
+        .length_prefix(LengthPrefix::U32)
+        .build();
+    let mut writer = BinaryWriter::with_config(&mut stream, config);
+    writer.write_string("hi").expect("Failed to write string");
+
+    stream.seek(0).expect("Failed to seek");
+    let config = BinaryConfig::builder()
+        .endian(Endian::Little)
+        .length_prefix(LengthPrefix::U32)
+        .build();
+    let mut reader = BinaryReader::with_config(&mut stream, config);
+
+    // The big-endian length 2 reads back as 0x02000000, far more than the
+    // two bytes of payload left.
+    assert!(reader.read_string().is_err());
+}
+
+#[test]
+fn byte_budget_limits_total_read() {
+    let mut stream = Memorystream::from_vec(vec![1; 64]);
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.read_u32().expect("Failed to read u32");
+    reader.limit(10);
+
+    let mut values = 0;
+    let err = loop {
+        match reader.read_u32() {
+            Ok(_) => values += 1,
+            Err(e) => break e,
+        }
+    };
+    assert_eq!(2, values);
+    assert_eq!(BinaryError::ByteBudgetExceeded { consumed: 8, limit: 10 }, err);
+    assert!(!err.is_decode_error());
+
+    // The failed read left the stream alone, and what's left still fits.
+    assert_eq!(12, reader.get_cur_pos().expect("Failed to tell"));
+    reader.read_u16().expect("Failed to read u16");
+    assert!(reader.read_u8().is_err());
+    assert!(reader.skip_bytes(1).is_err());
+    assert!(reader.scoped(1).is_err());
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_vec(&[7u32; 4]).expect("Failed to write vec");
+    stream.seek(0).expect("Failed to seek");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.limit(20);
+    match reader.read_vec::<u32>() {
+        Err(BinaryError::ByteBudgetExceeded { limit: 20, .. }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn peeks_do_not_use_byte_budget() {
+    let mut stream = Memorystream::from_vec(vec![1; 8]);
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.limit(4);
+
+    for _ in 0..3 {
+        assert_eq!(1, reader.peek_u8().expect("Failed to peek u8"));
+    }
+    assert!(reader.try_read(|r| r.read_string()).is_err());
+    assert_eq!(0x0101_0101, reader.read_u32().expect("Failed to read u32"));
+}
+
+#[test]
+fn read_write_header() {
+    let header = FileHeader {
+        magic: *b"BRWF",
+        version: 3,
+        flags: 0x8001,
+    };
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(8, writer.write_header(&header).expect("Failed to write header"));
+
+    stream.seek(0).expect("Failed to seek");
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(header, reader.read_header(b"BRWF", 1, 3).expect("Failed to read header"));
+
+    stream.seek(0).expect("Failed to seek");
+    let mut reader = BinaryReader::new(&mut stream);
+    match reader.read_header(b"XXXX", 1, 3).unwrap_err().root() {
+        BinaryError::BadMagic { found, .. } => assert_eq!(b"BRWF", found.as_slice()),
+        other => panic!("Unexpected error {:?}", other),
+    }
+
+    stream.seek(0).expect("Failed to seek");
+    let mut reader = BinaryReader::new(&mut stream);
+    let err = reader.read_header(b"BRWF", 4, 6).unwrap_err();
+    assert_eq!(&BinaryError::UnsupportedVersion { version: 3, min: 4, max: 6 }, err.root());
+    assert_eq!(Some(4), err.offset());
+}
+
+#[test]
+fn decode_rejects_trailing_bytes() {
+    for codec in [Codec::Bincode, Codec::Fixed] {
+        let config = BinaryConfig::builder().codec(codec).build();
+        let mut stream = Memorystream::new().expect("Error");
+        let mut writer = BinaryWriter::with_config(&mut stream, config);
+        writer.write_value(&7u64).expect("Failed to write value");
+        writer.write_u32(0xabcd).expect("Failed to write u32");
+        writer.write_bytes(&[1, 2, 3, 4, 5, 6, 7, 8]).expect("Failed to write bytes");
+
+        stream.seek(0).expect("Failed to seek");
+        let mut reader = BinaryReader::with_config(&mut stream, config);
+        let err = reader.read_value::<u32>().unwrap_err();
+        assert_eq!(&BinaryError::TrailingBytes { consumed: 4, expected: 8 }, err.root());
+        assert!(err.is_decode_error());
+
+        // The whole value was still read, so the next read lines up.
+        assert_eq!(0xabcd, reader.read_u32().expect("Failed to read u32"));
+
+        match reader.read_vec_until_eof::<u16>(4).unwrap_err().root() {
+            BinaryError::TrailingBytes { consumed: 2, expected: 4 } => {}
+            other => panic!("Unexpected error {:?}", other),
+        }
+    }
+}
+
+#[test]
+fn truncated_bincode_values_report_eof() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_vec(&[1u32, 2, 3]).expect("Failed to write vec");
+    let mut data = stream.into_vec();
+    data.truncate(data.len() - 2);
+
+    let mut stream = Memorystream::from_vec(data);
+    let mut reader = BinaryReader::new(&mut stream);
+    let err = reader.read_vec::<u32>().unwrap_err();
+    assert!(err.is_eof());
+    assert_eq!(&BinaryError::UnexpectedEof { expected: 4, got: 2 }, err.root());
+    assert_eq!(Some(16), err.offset());
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_option(&Some(7u32)).expect("Failed to write option");
+    let mut map = HashMap::new();
+    map.insert(1u32, 2u32);
+    writer.write_map(&map).expect("Failed to write map");
+
+    let data = stream.into_vec();
+    let mut stream = Memorystream::from_vec(data[..3].to_vec());
+    let err = BinaryReader::new(&mut stream).read_option::<u32>().unwrap_err();
+    assert_eq!(&BinaryError::UnexpectedEof { expected: 4, got: 2 }, err.root());
+
+    let mut stream = Memorystream::from_vec(data[5..data.len() - 1].to_vec());
+    let err = BinaryReader::new(&mut stream).read_map::<u32, u32>().unwrap_err();
+    assert!(err.is_eof());
+    assert!(!err.is_decode_error());
+}
+
+#[test]
+fn read_cstring_unterminated() {
+    let mut stream = Memorystream::from_vec(b"abc".to_vec());
+    let mut reader = BinaryReader::new(&mut stream);
+    match reader.read_cstring() {
+        Err(BinaryError::UnexpectedEof { .. }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    let mut stream = Memorystream::from_vec(vec![b'a'; 64]);
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.set_max_alloc(16);
+    match reader.read_cstring() {
+        Err(BinaryError::AllocationTooLarge {
+            requested: 17,
+            limit: 16,
+        }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn read_write_fixed_string() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    match writer.write_fixed_string("exact", 4, 0) {
+        Err(BinaryError::InvalidData(_)) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+    assert_eq!(
+        5,
+        writer
+            .write_fixed_string("exact", 5, 0)
+            .expect("Failed to write fixed string")
+    );
+    assert_eq!(
+        8,
+        writer
+            .write_fixed_string("pad", 8, b' ')
+            .expect("Failed to write fixed string")
+    );
+    assert_eq!(
+        4,
+        writer
+            .write_fixed_string("", 4, 0)
+            .expect("Failed to write fixed string")
+    );
+
+    assert_eq!(b"pad     ", &stream.as_slice()[5..13]);
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(
+        "exact",
+        reader
+            .read_fixed_string(5, 0)
+            .expect("Failed to read fixed string")
+    );
+    assert_eq!(
+        "pad",
+        reader
+            .read_fixed_string(8, b' ')
+            .expect("Failed to read fixed string")
+    );
+    assert_eq!(
+        "",
+        reader
+            .read_fixed_string(4, 0)
+            .expect("Failed to read fixed string")
+    );
+}
+
+#[test]
+fn read_write_string_utf16() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    assert_eq!(
+        8,
+        writer
+            .write_string_utf16("abc")
+            .expect("Failed to write UTF-16 string")
+    );
+    assert_eq!(
+        8,
+        writer
+            .write_string_utf16("\u{e9}\u{1F600}")
+            .expect("Failed to write UTF-16 string")
+    );
+    assert_eq!(
+        &[0x03, 0x00, b'a', 0x00, b'b', 0x00, b'c', 0x00],
+        &stream.as_slice()[..8]
+    );
+    assert_eq!(
+        &[0x03, 0x00, 0xE9, 0x00, 0x3D, 0xD8, 0x00, 0xDE],
+        &stream.as_slice()[8..]
+    );
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(
+        "abc",
+        reader
+            .read_string_utf16()
+            .expect("Failed to read UTF-16 string")
+    );
+    assert_eq!(
+        "\u{e9}\u{1F600}",
+        reader
+            .read_string_utf16()
+            .expect("Failed to read UTF-16 string")
+    );
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::with_endian(&mut stream, Endian::Big);
+    writer
+        .write_string_utf16("\u{1F600}")
+        .expect("Failed to write UTF-16 string");
+    assert_eq!(&[0x00, 0x02, 0xD8, 0x3D, 0xDE, 0x00], stream.as_slice());
+
+    let mut reader = BinaryReader::with_endian(&mut stream, Endian::Big);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(
+        "\u{1F600}",
+        reader
+            .read_string_utf16()
+            .expect("Failed to read UTF-16 string")
+    );
+}
+
+#[test]
+fn read_string_utf16_unpaired_surrogate() {
+    let mut stream = Memorystream::from_vec(vec![0x02, 0x00, 0x3D, 0xD8, b'a', 0x00]);
+    let mut reader = BinaryReader::new(&mut stream);
+    match reader.read_string_utf16() {
+        Err(BinaryError::At { offset: 2, source }) => match *source {
+            BinaryError::Utf16Error(_) => {}
+            other => panic!("Unexpected source {:?}", other),
+        },
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn read_write_128() {
+    for &endian in [Endian::Little, Endian::Big].iter() {
+        let mut stream = Memorystream::new().expect("Error");
+        let mut writer = BinaryWriter::with_endian(&mut stream, endian);
+
+        for &value in [0u128, 1, u128::MAX, 0x0102030405060708090A0B0C0D0E0F10].iter() {
+            assert_eq!(16, writer.write_u128(value).expect("Failed to write u128"));
+        }
+        for &value in [0i128, -1, i128::MIN, i128::MAX].iter() {
+            assert_eq!(16, writer.write_i128(value).expect("Failed to write i128"));
+        }
+        assert_eq!(16, writer.write_num(7u128).expect("Failed to write u128"));
+
+        let first = if endian == Endian::Big { 0x01 } else { 0x10 };
+        assert_eq!(first, stream.as_slice()[48]);
+
+        let mut reader = BinaryReader::with_endian(&mut stream, endian);
+        reader.seek_to(0).expect("Failed to seek");
+        for &value in [0u128, 1, u128::MAX, 0x0102030405060708090A0B0C0D0E0F10].iter() {
+            assert_eq!(value, reader.read_u128().expect("Failed to read u128"));
+        }
+        for &value in [0i128, -1, i128::MIN, i128::MAX].iter() {
+            assert_eq!(value, reader.read_i128().expect("Failed to read i128"));
+        }
+        assert_eq!(7u128, reader.read_num().expect("Failed to read u128"));
+    }
+}
+
+#[test]
+fn checksum_stream_crc32() {
+    let data: Vec<u8> = (0..=255).collect();
+
+    let mut stream = ChecksumStream::new(Memorystream::new().expect("Error"));
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_bytes(b"123456789").expect("Failed to write bytes");
+    assert_eq!(0xCBF43926, stream.checksum());
+
+    stream.reset_checksum();
+    assert_eq!(0, stream.checksum());
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_bytes(&data).expect("Failed to write bytes");
+    let checksum = stream.checksum();
+    assert_eq!(0x29058C73, checksum);
+
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u32(checksum).expect("Failed to write u32");
+
+    let mut stream = ChecksumStream::new(stream.into_inner());
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(9).expect("Failed to seek");
+    reader.read_bytes(256).expect("Failed to read bytes");
+    let computed = stream.checksum();
+
+    let mut reader = BinaryReader::new(&mut stream);
+    let stored = reader.read_u32().expect("Failed to read u32");
+    assert_eq!(stored, computed);
+}
+
+#[test]
+fn stream_io_bridges_std_io() {
+    use std::io::{Read, Write};
+
+    let mut io = StreamIo::new(Memorystream::new().expect("Error"));
+    let name = "widget";
+    write!(io, "id={} name={}", 42, name).expect("Failed to write");
+    io.flush().expect("Failed to flush");
+
+    let mut stream = io.into_inner();
+    stream.seek(0).expect("Failed to seek");
+
+    let mut io = StreamIo::new(stream);
+    let mut text = String::new();
+    io.read_to_string(&mut text).expect("Failed to read");
+    assert_eq!("id=42 name=widget", text);
+
+    let mut io = StreamIo::new(SliceStream::new(b"abcdef"));
+    let mut sink = Vec::new();
+    std::io::copy(&mut Read::by_ref(&mut io).take(4), &mut sink).expect("Failed to copy");
+    assert_eq!(b"abcd", &sink[..]);
+
+    match io.write(b"x") {
+        Err(e) => assert_eq!(std::io::ErrorKind::Other, e.kind()),
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn io_stream_over_cursor() {
+    let mut stream = IoStream::new(std::io::Cursor::new(Vec::new()));
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    writer.write_u32(0xCAFEBABE).expect("Failed to write u32");
+    writer
+        .write_string("cursor")
+        .expect("Failed to write string");
+    writer.write_f64(2.5).expect("Failed to write f64");
+    writer.write_bool(true).expect("Failed to write bool");
+    assert_eq!(27, writer.get_cur_pos().expect("Failed to tell"));
+
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(27, reader.seek_to_end().expect("Failed to seek"));
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(0xCAFEBABE, reader.read_u32().expect("Failed to read u32"));
+    assert_eq!("cursor", reader.read_string().expect("Failed to read string"));
+    assert_eq!(2.5, reader.read_f64().expect("Failed to read f64"));
+    assert!(reader.read_bool().expect("Failed to read bool"));
+
+    assert_eq!(27, stream.into_inner().into_inner().len());
+}
+
+#[test]
+fn length_prefix_widths() {
+    let prefixes = [
+        (LengthPrefix::U8, 1),
+        (LengthPrefix::U16, 2),
+        (LengthPrefix::U32, 4),
+        (LengthPrefix::U64, 8),
+        (LengthPrefix::Varint, 1),
+    ];
+
+    for &(prefix, width) in prefixes.iter() {
+        let mut stream = Memorystream::new().expect("Error");
+        let mut writer = BinaryWriter::new(&mut stream);
+        writer.set_length_prefix(prefix);
+        writer
+            .write_string("prefixed")
+            .expect("Failed to write string");
+        writer
+            .write_vec(&[1u16, 2, 3])
+            .expect("Failed to write vec");
+        assert_eq!(width + 8 + width + 6, stream.len());
+
+        let mut reader = BinaryReader::new(&mut stream);
+        reader.set_length_prefix(prefix);
+        reader.seek_to(0).expect("Failed to seek");
+        assert_eq!(
+            "prefixed",
+            reader.read_string().expect("Failed to read string")
+        );
+        assert_eq!(
+            vec![1u16, 2, 3],
+            reader.read_vec::<u16>().expect("Failed to read vec")
+        );
+    }
+}
+
+#[test]
+fn length_prefix_too_narrow() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.set_length_prefix(LengthPrefix::U8);
+
+    match writer.write_string("x".repeat(256)) {
+        Err(BinaryError::LengthOverflow { len: 256, max: 255 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+    assert!(stream.is_empty());
+}
+
+#[test]
+fn write_string_overflows_configured_prefix() {
+    let mut stream = Memorystream::new().expect("Error");
+    let config = BinaryConfig::builder().length_prefix(LengthPrefix::U8).build();
+    let mut writer = BinaryWriter::with_config(&mut stream, config);
+
+    let err = writer.write_string("x".repeat(300)).unwrap_err();
+    assert_eq!(BinaryError::LengthOverflow { len: 300, max: 255 }, err);
+    assert!(!err.is_decode_error());
+    assert!(stream.is_empty());
+}
+
+#[test]
+fn read_to_end_reads_tail() {
+    let mut stream = Memorystream::from_vec((0..10).collect());
+    let mut reader = BinaryReader::new(&mut stream);
+
+    assert_eq!(0x0100, reader.read_u16().expect("Failed to read u16"));
+    assert_eq!(
+        vec![2, 3, 4, 5, 6, 7, 8, 9],
+        reader.read_to_end().expect("Failed to read to end")
+    );
+    assert!(reader
+        .read_to_end()
+        .expect("Failed to read to end")
+        .is_empty());
+
+    reader.seek_to(0).expect("Failed to seek");
+    reader.set_max_alloc(4);
+    match reader.read_to_end() {
+        Err(BinaryError::AllocationTooLarge {
+            requested: 10,
+            limit: 4,
+        }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn remaining_tracks_consumed_bytes() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u32(1).expect("Failed to write u32");
+    writer.write_u16(2).expect("Failed to write u16");
+    writer.write_u64(3).expect("Failed to write u64");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(14, reader.len().expect("Failed to get length"));
+    assert_eq!(14, reader.remaining().expect("Failed to get remaining"));
+    assert!(!reader.is_empty().expect("Failed to get length"));
+
+    reader.read_u32().expect("Failed to read u32");
+    assert_eq!(10, reader.remaining().expect("Failed to get remaining"));
+    reader.read_u16().expect("Failed to read u16");
+    assert_eq!(8, reader.remaining().expect("Failed to get remaining"));
+
+    let mut values = Vec::new();
+    while reader.remaining().expect("Failed to get remaining") > 0 {
+        values.push(reader.read_u64().expect("Failed to read u64"));
+    }
+    assert_eq!(vec![3], values);
+    assert_eq!(14, reader.len().expect("Failed to get length"));
+}
+
+#[test]
+fn read_write_option() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    assert_eq!(5, writer.write_option(&Some(42u32)).expect("Failed to write option"));
+    assert_eq!(1, writer.write_option::<u32>(&None).expect("Failed to write option"));
+    writer
+        .write_option(&Some("text".to_string()))
+        .expect("Failed to write option");
+    writer.write_u8(2).expect("Failed to write u8");
+    assert_eq!(&[1, 42, 0, 0, 0, 0], &stream.as_slice()[..6]);
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(Some(42u32), reader.read_option().expect("Failed to read option"));
+    assert_eq!(None, reader.read_option::<u32>().expect("Failed to read option"));
+    assert_eq!(
+        Some("text".to_string()),
+        reader.read_option().expect("Failed to read option")
+    );
+    match reader.read_option::<u32>() {
+        Err(BinaryError::At { source, .. }) => match *source {
+            BinaryError::InvalidBool(2) => {}
+            other => panic!("Unexpected source {:?}", other),
+        },
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn read_write_result() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    let ok: Result<u32, String> = Ok(7);
+    let err: Result<u32, String> = Err("failed".to_string());
+    let pair: Result<(i16, bool), u8> = Err(3);
+    assert_eq!(5, writer.write_result(&ok).expect("Failed to write result"));
+    writer.write_result(&err).expect("Failed to write result");
+    writer.write_result(&pair).expect("Failed to write result");
+    writer.write_u8(2).expect("Failed to write u8");
+    assert_eq!(&[0, 7, 0, 0, 0, 1], &stream.as_slice()[..6]);
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(ok, reader.read_result().expect("Failed to read result"));
+    assert_eq!(err, reader.read_result().expect("Failed to read result"));
+    assert_eq!(pair, reader.read_result().expect("Failed to read result"));
+    match reader.read_result::<u32, u32>() {
+        Err(BinaryError::At { source, .. }) => match *source {
+            BinaryError::InvalidDiscriminant(2) => {}
+            other => panic!("Unexpected source {:?}", other),
+        },
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn decode_errors_report_offset() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u32(7).expect("Failed to write u32");
+    writer
+        .write_string("ok")
+        .expect("Failed to write string");
+    writer.write_usize(3).expect("Failed to write usize");
+    writer
+        .write_bytes(&[b'a', 0xFF, b'b'])
+        .expect("Failed to write bytes");
+    writer.write_usize(3).expect("Failed to write usize");
+    writer.write_bytes(&[1, 1, 5]).expect("Failed to write bytes");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    reader.read_u32().expect("Failed to read u32");
+    assert_eq!("ok", reader.read_string().expect("Failed to read string"));
+
+    let error = reader.read_string().expect_err("Expected a UTF-8 error");
+    assert_eq!("At offset 23: Encountered a UTF-8 decoding error", error.to_string());
+    match error {
+        BinaryError::At { offset: 23, source } => match *source {
+            BinaryError::Utf8Error(_) => {}
+            other => panic!("Unexpected source {:?}", other),
+        },
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    reader.seek_to(25).expect("Failed to seek"); println!("{:?}", reader.read_bytes(8)); reader.seek_to(25).unwrap();
+    let error = reader.read_vec::<bool>().expect_err("Expected a decode error");
+    match error {
+        BinaryError::At { offset: 35, source } => match *source {
+            BinaryError::BinCodeErr(_) => {}
+            other => panic!("Unexpected source {:?}", other),
+        },
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn length_prefixed_reads_past_end_are_unexpected_eof() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_usize(10).expect("Failed to write usize");
+    writer.write_bytes(b"shor").expect("Failed to write bytes");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    match reader.read_string() {
+        Err(BinaryError::UnexpectedEof {
+            expected: 10,
+            got: 4,
+        }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    reader.seek_to(0).expect("Failed to seek");
+    match reader.read_value::<String>() {
+        Err(BinaryError::UnexpectedEof {
+            expected: 10,
+            got: 4,
+        }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    reader.seek_to(8).expect("Failed to seek");
+    match reader.read_bytes(6) {
+        Err(BinaryError::UnexpectedEof { expected: 6, got: 4 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    reader.seek_to(11).expect("Failed to seek");
+    match reader.read_pascal_string(StringEncoding::Utf8) {
+        Err(BinaryError::UnexpectedEof {
+            expected: 114,
+            got: 0,
+        }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    reader.seek_to(12).expect("Failed to seek");
+    match reader.read_u128() {
+        Err(BinaryError::UnexpectedEof { expected: 16, got: 0 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn binary_readable_builtin_types() {
+    let value: Vec<Option<String>> = vec![Some("a".to_string()), None, Some("bc".to_string())];
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.set_length_prefix(LengthPrefix::U8);
+    assert_eq!(9, value.write_to(&mut writer).expect("Failed to write"));
+    writer.write_u16(7).expect("Failed to write u16");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.set_length_prefix(LengthPrefix::U8);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(
+        value,
+        Vec::<Option<String>>::read_from(&mut reader).expect("Failed to read")
+    );
+    assert_eq!(7, <u16 as BinaryReadable>::read_from(&mut reader).expect("Failed to read u16"));
+}
+
+#[test]
+fn binary_readable_tuples() {
+    let pair = (42u32, "answer".to_string());
+    let quad = (-3i16, true, 1.5f64, Some('x'));
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.set_length_prefix(LengthPrefix::U8);
+    assert_eq!(11, pair.write_to(&mut writer).expect("Failed to write pair"));
+    assert_eq!(16, quad.write_to(&mut writer).expect("Failed to write quad"));
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.set_length_prefix(LengthPrefix::U8);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(
+        pair,
+        <(u32, String)>::read_from(&mut reader).expect("Failed to read pair")
+    );
+    assert_eq!(
+        quad,
+        <(i16, bool, f64, Option<char>)>::read_from(&mut reader).expect("Failed to read quad")
+    );
+    assert_eq!(reader.get_cur_pos().expect("Failed to get pos"), 27);
+}
+
+#[cfg(feature = "derive")]
+#[derive(Debug, PartialEq, BinaryReadable, BinaryWritable)]
+struct Vertex(f32, f32);
+
+#[cfg(feature = "derive")]
+#[derive(Debug, PartialEq, BinaryReadable, BinaryWritable)]
+struct Marker;
+
+#[cfg(feature = "derive")]
+#[derive(Debug, PartialEq, BinaryReadable, BinaryWritable)]
+struct Mesh {
+    id: u32,
+    name: String,
+    visible: bool,
+    vertices: Vec<Vertex>,
+    parent: Option<u64>,
+    #[binary(skip)]
+    cache: Vec<u8>,
+    origin: Vertex,
+    marker: Marker,
+}
+
+#[cfg(feature = "derive")]
+#[test]
+fn derive_round_trip() {
+    let mesh = Mesh {
+        id: 9,
+        name: "quad".to_string(),
+        visible: true,
+        vertices: vec![Vertex(0.0, 0.0), Vertex(1.0, 0.5)],
+        parent: Some(3),
+        cache: vec![1, 2, 3],
+        origin: Vertex(-1.0, 2.0),
+        marker: Marker,
+    };
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    let written = mesh.write_to(&mut writer).expect("Failed to write mesh");
+    assert_eq!(4 + 12 + 1 + 24 + 9 + 8, written);
+    assert_eq!(written, stream.len());
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let read = Mesh::read_from(&mut reader).expect("Failed to read mesh");
+
+    assert_eq!(
+        Mesh {
+            cache: Vec::new(),
+            ..mesh
+        },
+        read
+    );
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn async_tcp_round_trip() {
+    use binary_rw::asyncstream::{AsyncBinaryReader, AsyncBinaryWriter};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .expect("Failed to bind listener");
+    let address = listener.local_addr().expect("Failed to get local address");
+
+    let server = tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.expect("Failed to accept connection");
+
+        let mut reader = AsyncBinaryReader::new(&mut socket);
+        let id = reader.read_u32().await.expect("Failed to read u32");
+        let name = reader.read_string().await.expect("Failed to read string");
+        let scale = reader.read_f64().await.expect("Failed to read f64");
+        let hash = reader.read_i128().await.expect("Failed to read i128");
+
+        let mut writer = AsyncBinaryWriter::new(&mut socket);
+        writer.write_u32(id + 1).await.expect("Failed to write u32");
+        writer
+            .write_string(&name.to_uppercase())
+            .await
+            .expect("Failed to write string");
+        writer.write_f64(scale * 2.0).await.expect("Failed to write f64");
+        writer.write_i128(-hash).await.expect("Failed to write i128");
+        writer.write_bool(true).await.expect("Failed to write bool");
+        writer.flush().await.expect("Failed to flush");
+    });
+
+    let mut socket = tokio::net::TcpStream::connect(address)
+        .await
+        .expect("Failed to connect");
+
+    let mut writer = AsyncBinaryWriter::new(&mut socket);
+    writer.write_u32(41).await.expect("Failed to write u32");
+    writer.write_string("frame").await.expect("Failed to write string");
+    writer.write_f64(1.25).await.expect("Failed to write f64");
+    writer.write_i128(i128::MAX).await.expect("Failed to write i128");
+    writer.flush().await.expect("Failed to flush");
+
+    let mut reader = AsyncBinaryReader::new(&mut socket);
+    assert_eq!(42, reader.read_u32().await.expect("Failed to read u32"));
+    assert_eq!("FRAME", reader.read_string().await.expect("Failed to read string"));
+    assert_eq!(2.5, reader.read_f64().await.expect("Failed to read f64"));
+    assert_eq!(-i128::MAX, reader.read_i128().await.expect("Failed to read i128"));
+    assert!(reader.read_bool().await.expect("Failed to read bool"));
+    match reader.seek_to(0).await {
+        Err(BinaryError::StreamError(StreamError::SeekError)) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+
+    server.await.expect("Server task panicked");
+    match reader.read_u8().await {
+        Err(BinaryError::UnexpectedEof { expected: 1, got: 0 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[cfg(feature = "tokio")]
+struct AsyncMemory(Memorystream);
+
+#[cfg(feature = "tokio")]
+impl binary_rw::asyncstream::AsyncStream for AsyncMemory {
+    async fn write(&mut self, bytes: &[u8]) -> Result<usize, StreamError> {
+        Stream::write(&mut self.0, bytes)
+    }
+
+    async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, StreamError> {
+        Stream::read(&mut self.0, buffer)
+    }
+
+    async fn seek(&mut self, to: usize) -> Result<usize, StreamError> {
+        Stream::seek(&mut self.0, to)
+    }
+
+    async fn tell(&mut self) -> Result<usize, StreamError> {
+        Stream::tell(&mut self.0)
+    }
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn async_layout_matches_sync() {
+    use binary_rw::asyncstream::{AsyncBinaryReader, AsyncBinaryWriter};
+
+    for prefix in [LengthPrefix::U8, LengthPrefix::U64, LengthPrefix::Varint] {
+        let mut stream = AsyncMemory(Memorystream::new().expect("Error"));
+        let mut writer = AsyncBinaryWriter::new(&mut stream);
+        writer.set_length_prefix(prefix);
+        writer.write_usize(300).await.expect("Failed to write usize");
+        writer.write_isize(-2).await.expect("Failed to write isize");
+        writer.write_string("async").await.expect("Failed to write string");
+
+        let mut expected = Memorystream::new().expect("Error");
+        let config = BinaryConfig::builder().length_prefix(prefix).build();
+        let mut writer = BinaryWriter::with_config(&mut expected, config);
+        writer.write_usize(300).expect("Failed to write usize");
+        writer.write_isize(-2).expect("Failed to write isize");
+        writer.write_string("async").expect("Failed to write string");
+        assert_eq!(expected.as_slice(), stream.0.as_slice());
+
+        stream.0.seek(0).expect("Failed to seek");
+        let mut reader = AsyncBinaryReader::new(&mut stream);
+        reader.set_length_prefix(prefix);
+        assert_eq!(300, reader.read_usize().await.expect("Failed to read usize"));
+        assert_eq!(-2, reader.read_isize().await.expect("Failed to read isize"));
+        assert_eq!("async", reader.read_string().await.expect("Failed to read string"));
+    }
+
+    let mut stream = AsyncMemory(Memorystream::new().expect("Error"));
+    let mut writer = AsyncBinaryWriter::new(&mut stream);
+    writer.set_length_prefix(LengthPrefix::U8);
+    match writer.write_string(&"x".repeat(256)).await {
+        Err(BinaryError::LengthOverflow { len: 256, max: 255 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn sink_stream_measures_size() {
+    let mut sink = SinkStream::new();
+    let mut writer = BinaryWriter::new(&mut sink);
+
+    writer.write_u8(1).expect("Failed to write u8");
+    writer.write_u16(2).expect("Failed to write u16");
+    writer.write_f64(3.0).expect("Failed to write f64");
+    writer.write_string("sized").expect("Failed to write string");
+    writer.write_bool(true).expect("Failed to write bool");
+    assert_eq!(1 + 2 + 8 + (8 + 5) + 1, sink.bytes_written());
+
+    let mut reader = BinaryReader::new(&mut sink);
+    match reader.read_u8() {
+        Err(BinaryError::StreamError(StreamError::ReadError)) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn tee_stream_mirrors_writes() {
+    let checksum = ChecksumStream::new(Memorystream::new().expect("Error"));
+    let mut stream = TeeStream::new(Memorystream::new().expect("Error"), checksum);
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    writer.write_u32(0x01020304).expect("Failed to write u32");
+    writer.write_string("tee").expect("Failed to write string");
+    assert_eq!(15, writer.get_cur_pos().expect("Failed to tell"));
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(4).expect("Failed to seek");
+    assert_eq!("tee", reader.read_string().expect("Failed to read string"));
+
+    let (primary, checksum) = stream.into_inner();
+    let expected = {
+        let mut check = ChecksumStream::new(Memorystream::new().expect("Error"));
+        check.write(primary.as_slice()).expect("Failed to write");
+        check.checksum()
+    };
+    assert_eq!(expected, checksum.checksum());
+    assert_eq!(primary.into_vec(), checksum.into_inner().into_vec());
+}
+
+#[test]
+fn read_write_fixed_arrays() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    assert_eq!(4, writer.write_array(&[0xDE, 0xAD, 0xBE, 0xEF]).expect("Failed to write array"));
+    for &value in [1.0f32, 0.0, 0.0, 0.5].iter() {
+        writer.write_f32(value).expect("Failed to write f32");
+    }
+    writer.write_u8(9).expect("Failed to write u8");
+
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(0).expect("Failed to seek");
+    let magic: [u8; 4] = reader.read_array().expect("Failed to read array");
+    assert_eq!([0xDE, 0xAD, 0xBE, 0xEF], magic);
+
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(
+        magic.to_vec(),
+        reader.read_bytes(4).expect("Failed to read bytes")
+    );
+
+    let quaternion: [f32; 4] = reader.read_array_of().expect("Failed to read array");
+    assert_eq!([1.0, 0.0, 0.0, 0.5], quaternion);
+
+    match reader.read_array::<2>() {
+        Err(BinaryError::UnexpectedEof { expected: 2, got: 1 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn bulk_slices_match_per_element_reads() {
+    let values: Vec<u32> = (0..100_000u32).map(|i| i.wrapping_mul(2_654_435_761)).collect();
+    let floats = [0.5f32, -1.25, 3.0e10, f32::MIN_POSITIVE];
+
+    for &endian in [Endian::Little, Endian::Big].iter() {
+        let mut stream = Memorystream::new().expect("Error");
+        let mut writer = BinaryWriter::with_endian(&mut stream, endian);
+        assert_eq!(
+            400_000,
+            writer
+                .write_u32_slice(&values)
+                .expect("Failed to write u32 slice")
+        );
+        for &value in floats.iter() {
+            writer.write_f32(value).expect("Failed to write f32");
+        }
+        writer
+            .write_i16_slice(&[-2, 7])
+            .expect("Failed to write i16 slice");
+
+        let mut reader = BinaryReader::with_endian(&mut stream, endian);
+        reader.seek_to(0).expect("Failed to seek");
+        let bulk = reader
+            .read_u32_slice(values.len())
+            .expect("Failed to read u32 slice");
+        assert_eq!(values, bulk);
+
+        reader.seek_to(0).expect("Failed to seek");
+        for &value in values.iter().take(1000) {
+            assert_eq!(value, reader.read_u32().expect("Failed to read u32"));
+        }
+
+        reader.seek_to(400_000).expect("Failed to seek");
+        assert_eq!(
+            floats.to_vec(),
+            reader.read_f32_slice(4).expect("Failed to read f32 slice")
+        );
+        assert_eq!(-2, reader.read_i16().expect("Failed to read i16"));
+        assert_eq!(7, reader.read_i16().expect("Failed to read i16"));
+    }
+
+    let mut stream = Memorystream::from_vec(vec![0; 6]);
+    let mut reader = BinaryReader::new(&mut stream);
+    match reader.read_u64_slice(1) {
+        Err(BinaryError::UnexpectedEof { expected: 8, got: 6 }) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+}
+
+#[cfg(feature = "half")]
+#[test]
+fn read_write_f16() {
+    let exact = [0.0f32, 1.0, -2.5, 0.099975586, 65504.0];
+
+    for &endian in [Endian::Little, Endian::Big].iter() {
+        let mut stream = Memorystream::new().expect("Error");
+        let mut writer = BinaryWriter::with_endian(&mut stream, endian);
+        for &value in exact.iter() {
+            assert_eq!(2, writer.write_f16(value).expect("Failed to write f16"));
+        }
+        writer.write_f16(0.1).expect("Failed to write f16");
+        writer.write_f16(1.0e6).expect("Failed to write f16");
+
+        let one = if endian == Endian::Big { [0x3C, 0x00] } else { [0x00, 0x3C] };
+        assert_eq!(&one, &stream.as_slice()[2..4]);
+
+        let mut reader = BinaryReader::with_endian(&mut stream, endian);
+        reader.seek_to(0).expect("Failed to seek");
+        for &value in exact.iter() {
+            assert_eq!(value, reader.read_f16().expect("Failed to read f16"));
+        }
+
+        let lossy = reader.read_f16().expect("Failed to read f16");
+        assert_ne!(0.1, lossy);
+        assert!((lossy - 0.1).abs() < 0.0001);
+        assert_eq!(f32::INFINITY, reader.read_f16().expect("Failed to read f16"));
+    }
+}
+
+#[test]
+fn memory_stream_clone_and_rewind() {
+    let mut stream = Memorystream::from_vec((1..=8).collect());
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(0x0201, reader.read_u16().expect("Failed to read u16"));
+
+    let mut copy = stream.clone();
+    assert_eq!(2, copy.position());
+    let mut reader = BinaryReader::new(&mut copy);
+    assert_eq!(3, reader.read_u8().expect("Failed to read u8"));
+
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(3, reader.read_u8().expect("Failed to read u8"));
+    assert_eq!(3, stream.position());
+
+    stream.rewind();
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(
+        (1..=8).collect::<Vec<u8>>(),
+        reader.read_to_end().expect("Failed to read to end")
+    );
+
+    stream.set_position(6);
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(0x0807, reader.read_u16().expect("Failed to read u16"));
+
+    stream.set_position(10);
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u8(0xFF).expect("Failed to write u8");
+    assert_eq!(&[7, 8, 0, 0, 0xFF], &stream.as_slice()[6..]);
+}
+
+#[test]
+fn memory_stream_position_matches_tell() {
+    let mut stream = Memorystream::from_vec((0..16).collect());
+
+    stream.seek(5).expect("Failed to seek");
+    assert_eq!(stream.position(), 5);
+
+    stream.set_position(9);
+    assert_eq!(stream.tell().expect("Failed to tell"), 9);
+
+    let mut byte = [0u8];
+    stream.read(&mut byte).expect("Failed to read");
+    assert_eq!(byte, [9]);
+    assert_eq!(stream.position(), stream.tell().expect("Failed to tell"));
+
+    stream.seek_end().expect("Failed to seek to end");
+    assert_eq!(stream.position(), 16);
+
+    stream.set_position(20);
+    assert_eq!(stream.tell().expect("Failed to tell"), 20);
+    assert_eq!(stream.read(&mut byte).expect("Failed to read"), 0);
+    stream.write(&[1]).expect("Failed to write");
+    assert_eq!((stream.position(), stream.len()), (21, 21));
+}
+
+#[test]
+fn transaction_rollback_discards_partial_writes() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+
+    writer.write_u32(1).expect("Failed to write u32");
+    writer.begin_transaction().expect("Failed to begin transaction");
+    writer.write_u32(2).expect("Failed to write u32");
+    writer.write_string("partial").expect("Failed to write string");
+    writer.rollback().expect("Failed to roll back");
+    assert_eq!(4, writer.get_cur_pos().expect("Failed to tell"));
+
+    writer.begin_transaction().expect("Failed to begin transaction");
+    writer.write_u16(3).expect("Failed to write u16");
+    writer.commit().expect("Failed to commit");
+    match writer.commit() {
+        Err(BinaryError::InvalidData(_)) => {}
+        other => panic!("Unexpected result {:?}", other),
+    }
+    assert_eq!(&[1, 0, 0, 0, 3, 0], stream.as_slice());
+
+    let mut stream = create_writer_stream("transaction");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u64(7).expect("Failed to write u64");
+    writer.begin_transaction().expect("Failed to begin transaction");
+    writer.write_u64(8).expect("Failed to write u64");
+    writer.rollback().expect("Failed to roll back");
+
+    let mut stream = create_reader_stream("transaction");
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(8, reader.len().expect("Failed to get length"));
+    assert_eq!(7, reader.read_u64().expect("Failed to read u64"));
+
+    cleanup("transaction");
+}
+
+#[test]
+fn transaction_rollback_from_middle_keeps_existing_data() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u32(1).expect("Failed to write u32");
+    writer.write_u32(2).expect("Failed to write u32");
+
+    writer.seek_to(0).expect("Failed to seek");
+    writer.begin_transaction().expect("Failed to begin transaction");
+    writer.write_u8(9).expect("Failed to write u8");
+    writer.rollback().expect("Failed to roll back");
+    assert_eq!(0, writer.get_cur_pos().expect("Failed to tell"));
+    assert_eq!(8, stream.len());
+
+    // Growth past the old end is still cut off.
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.seek_to(4).expect("Failed to seek");
+    writer.begin_transaction().expect("Failed to begin transaction");
+    writer.write_u64(3).expect("Failed to write u64");
+    writer.rollback().expect("Failed to roll back");
+    assert_eq!(4, writer.get_cur_pos().expect("Failed to tell"));
+    assert_eq!(8, stream.len());
+
+    // A stream that can't truncate fails without moving or ending the
+    // transaction.
+    let mut stream = CountingStream {
+        inner: Memorystream::new().expect("Error"),
+        reads: 0,
+        writes: 0,
+    };
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u32(1).expect("Failed to write u32");
+    writer.begin_transaction().expect("Failed to begin transaction");
+    writer.write_u32(2).expect("Failed to write u32");
+    assert!(writer.rollback().is_err());
+    assert_eq!(8, writer.get_cur_pos().expect("Failed to tell"));
+    writer.commit().expect("Failed to commit");
+}
+
+#[test]
+fn errors_compare_with_assert_eq() {
+    let mut stream = Memorystream::from_vec(vec![2, 0xAA]);
+    let mut reader = BinaryReader::new(&mut stream);
+
+    assert_eq!(
+        Err(BinaryError::At {
+            offset: 0,
+            source: Box::new(BinaryError::InvalidBool(2)),
+        }),
+        reader.read_bool()
+    );
+    assert_eq!(
+        Err(BinaryError::UnexpectedEof {
+            expected: 2,
+            got: 1,
+        }),
+        reader.read_u16()
+    );
+    assert_ne!(BinaryError::InvalidBool(2), BinaryError::InvalidChar(2));
+
+    let mut stream = SliceStream::new(&[]);
+    let mut writer = BinaryWriter::new(&mut stream);
+    assert_eq!(
+        Err(BinaryError::StreamError(StreamError::WriteError)),
+        writer.write_u8(1)
+    );
+    let error = StreamError::SeekError;
+    let copy = error;
+    assert_eq!(error, copy);
+}
+
+#[test]
+fn error_predicates() {
+    let eof = BinaryError::UnexpectedEof { expected: 4, got: 1 };
+    assert!(eof.is_eof());
+    assert!(!eof.is_decode_error());
+    assert!(!eof.is_stream_error());
+
+    let stream = BinaryError::StreamError(StreamError::ReadError);
+    assert!(stream.is_stream_error());
+    assert_eq!(stream.as_stream_error(), Some(&StreamError::ReadError));
+    assert!(!stream.is_eof());
+    assert!(!stream.is_decode_error());
+
+    let utf8 = BinaryError::from(String::from_utf8(vec![0xFF]).unwrap_err());
+    assert!(utf8.is_decode_error());
+    assert!(utf8.as_utf8_error().is_some());
+    assert!(utf8.as_stream_error().is_none());
+
+    let bincode = BinaryError::BinCodeErr(Box::new(bincode::ErrorKind::SizeLimit));
+    assert!(bincode.is_decode_error());
+    assert!(bincode.as_bincode_error().is_some());
+
+    for error in [
+        BinaryError::InvalidBool(2),
+        BinaryError::InvalidChar(0xD800),
+        BinaryError::VarintTooLong,
+        BinaryError::UnknownTag(9),
+        BinaryError::InvalidData("bad".to_string()),
+    ] {
+        assert!(error.is_decode_error());
+        assert!(!error.is_eof());
+    }
+    for error in [
+        BinaryError::RecursionLimitExceeded,
+        BinaryError::AllocationTooLarge { requested: 2, limit: 1 },
+        BinaryError::ShortWrite { expected: 2, written: 1 },
+    ] {
+        assert!(!error.is_decode_error());
+        assert!(!error.is_eof());
+        assert!(!error.is_stream_error());
+    }
+
+    let mut stream = Memorystream::from_vec(vec![0, 2]);
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.seek_to(1).expect("Failed to seek");
+    let error = reader.read_bool().unwrap_err();
+    assert_eq!(error.offset(), Some(1));
+    assert_eq!(error.root(), &BinaryError::InvalidBool(2));
+    assert!(error.is_decode_error());
+    assert!(reader.read_u16().unwrap_err().is_eof());
+}
+
+#[test]
+fn binary_error_into_io_error() {
+    use std::io::ErrorKind;
+
+    fn read_header(bytes: &[u8]) -> std::io::Result<u32> {
+        let mut stream = Memorystream::from_vec(bytes.to_vec());
+        let mut reader = BinaryReader::new(&mut stream);
+        Ok(reader.read_u32()?)
+    }
+
+    assert_eq!(read_header(&[1, 0, 0, 0]).expect("Failed to read header"), 1);
+    assert_eq!(read_header(&[1, 0]).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+
+    let utf8 = BinaryError::from(String::from_utf8(vec![0xC0]).unwrap_err());
+    assert_eq!(std::io::Error::from(utf8).kind(), ErrorKind::InvalidData);
+
+    let nested = BinaryError::At {
+        offset: 4,
+        source: Box::new(BinaryError::InvalidBool(7)),
+    };
+    let error = std::io::Error::from(nested);
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(error.to_string().contains('4'));
+
+    let stream = BinaryError::StreamError(StreamError::ReadError);
+    assert_eq!(std::io::Error::from(stream).kind(), ErrorKind::Other);
+    let short = BinaryError::ShortWrite { expected: 4, written: 1 };
+    assert_eq!(std::io::Error::from(short).kind(), ErrorKind::WriteZero);
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn deflate_stream_round_trip() {
+    use binary_rw::deflatestream::DeflateStream;
+
+    let raw: Vec<u8> = (0..4096u32).map(|i| (i % 16) as u8).collect();
+
+    let stream = create_writer_stream("deflate");
+    let mut compressed = DeflateStream::compress(stream);
+    let mut writer = BinaryWriter::new(&mut compressed);
+    writer.write_bytes(&raw).expect("Failed to write bytes");
+    writer.write_u32(0xDEAD_BEEF).expect("Failed to write u32");
+    assert!(writer.get_cur_pos().is_err());
+    assert!(writer.seek_to(0).is_err());
+    let mut stream = compressed.finish().expect("Failed to finish");
+    assert!(stream.stream_len().expect("Failed to get length") < raw.len());
+
+    let stream = create_reader_stream("deflate");
+    let mut decompressed = DeflateStream::decompress(stream);
+    let mut reader = BinaryReader::new(&mut decompressed);
+    assert_eq!(reader.read_bytes(raw.len()).expect("Failed to read bytes"), raw);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 0xDEAD_BEEF);
+    assert!(reader.read_u8().is_err());
+    assert!(decompressed.write(&[1]).is_err());
+
+    cleanup("deflate");
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn seekable_deflate_stream_random_access() {
+    use binary_rw::seekabledeflatestream::SeekableDeflateStream;
+
+    let raw: Vec<u8> = (0..10_000u32).map(|i| (i * 7 % 251) as u8).collect();
+
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u32(0xfeed).expect("Failed to write header");
+    let mut compressed = SeekableDeflateStream::compress(stream, 1024).expect("Failed to wrap");
+    let mut writer = BinaryWriter::new(&mut compressed);
+    writer.write_bytes(&raw).expect("Failed to write bytes");
+    assert_eq!(raw.len(), writer.get_cur_pos().expect("Failed to tell"));
+    assert!(writer.seek_to(0).is_err());
+    let stream = compressed.finish().expect("Failed to finish");
+
+    let mut decompressed = SeekableDeflateStream::decompress(stream).expect("Failed to open");
+    assert_eq!(10, decompressed.block_count());
+    let mut reader = BinaryReader::new(&mut decompressed);
+    assert_eq!(raw.len(), reader.len().expect("Failed to get length"));
+
+    // Straddles the boundary between the fifth and sixth blocks.
+    reader.seek_to(5000).expect("Failed to seek");
+    assert_eq!(&raw[5000..5300], &reader.read_bytes(300).expect("Failed to read bytes")[..]);
+    reader.seek_to(9990).expect("Failed to seek");
+    assert_eq!(&raw[9990..], &reader.read_bytes(10).expect("Failed to read bytes")[..]);
+    assert!(reader.read_u8().is_err());
+    assert_eq!(3, decompressed.blocks_decompressed());
+    assert!(decompressed.write(&[1]).is_err());
+
+    let mut stream = decompressed.finish().expect("Failed to finish");
+    stream.seek(0).expect("Failed to seek");
+    assert_eq!(0xfeed, BinaryReader::new(&mut stream).read_u32().expect("Failed to read header"));
+    assert!(SeekableDeflateStream::decompress(Memorystream::from_vec(raw)).is_err());
+}
+
+#[cfg(feature = "compression")]
+#[test]
+fn seekable_deflate_stream_rejects_bad_index() {
+    use binary_rw::seekabledeflatestream::SeekableDeflateStream;
+
+    let mut compressed = SeekableDeflateStream::compress(Memorystream::new().expect("Error"), 16)
+        .expect("Failed to wrap");
+    BinaryWriter::new(&mut compressed).write_bytes(&[7; 40]).expect("Failed to write");
+    let data = compressed.finish().expect("Failed to finish").into_vec();
+
+    // Three index entries of 16 bytes sit before the 24-byte trailer.
+    let index_start = data.len() - 24 - 3 * 16;
+    let corrupt = |entry: usize, field: usize, value: u64| {
+        let mut data = data.clone();
+        let at = index_start + entry * 16 + field;
+        data[at..at + 8].copy_from_slice(&value.to_le_bytes());
+        SeekableDeflateStream::decompress(Memorystream::from_vec(data))
+    };
+
+    assert!(corrupt(2, 8, u64::MAX / 2).is_err());
+    assert!(corrupt(1, 8, 1 << 40).is_err());
+    assert!(corrupt(1, 0, 0).is_err());
+    assert!(corrupt(0, 8, 1).is_err());
+}
+
+#[cfg(feature = "hash")]
+#[test]
+fn hashing_stream_digest() {
+    use binary_rw::hashingstream::HashingStream;
+    use sha2::{Digest, Sha256};
+
+    let mut stream = HashingStream::new(Memorystream::new().expect("Error"));
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_string("manifest entry").expect("Failed to write string");
+    writer.write_u32(0xdead_beef).expect("Failed to write u32");
+    writer.write_bytes(&[0u8; 1000]).expect("Failed to write bytes");
+
+    let mut expected = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::new(&mut expected);
+    writer.write_string("manifest entry").expect("Failed to write string");
+    writer.write_u32(0xdead_beef).expect("Failed to write u32");
+    writer.write_bytes(&[0u8; 1000]).expect("Failed to write bytes");
+    let expected: [u8; 32] = Sha256::digest(expected.as_slice()).into();
+
+    assert_eq!(expected, stream.finalize());
+
+    // Reads are hashed the same way.
+    let mut stream = HashingStream::new(Memorystream::from_vec(b"abc".to_vec()));
+    let mut reader = BinaryReader::new(&mut stream);
+    reader.read_bytes(3).expect("Failed to read bytes");
+    assert_eq!([0xba, 0x78, 0x16, 0xbf], stream.finalize()[..4]);
+}
+
+#[cfg(feature = "crypto")]
+#[test]
+fn encrypted_stream_round_trip() {
+    use binary_rw::encryptedstream::EncryptedStream;
+
+    let key = [7u8; 32];
+    let nonce = [3u8; 16];
+    let text = "attack at dawn, bring snacks";
+
+    let stream = create_writer_stream("encrypted");
+    let mut encrypted = EncryptedStream::new(stream, &key, &nonce).expect("Failed to wrap");
+    let mut writer = BinaryWriter::new(&mut encrypted);
+    writer.write_string(text).expect("Failed to write string");
+    for i in 0..16u32 {
+        writer.write_u32(i * 1000).expect("Failed to write u32");
+    }
+    encrypted.flush().expect("Failed to flush");
+
+    let raw = std::fs::read("encrypted").expect("Failed to read file");
+    assert_eq!(raw.len(), 8 + text.len() + 64);
+    assert!(!raw.windows(text.len()).any(|w| w == text.as_bytes()));
+
+    let stream = create_reader_stream("encrypted");
+    let mut decrypted = EncryptedStream::new(stream, &key, &nonce).expect("Failed to wrap");
+    let mut reader = BinaryReader::new(&mut decrypted);
+    reader.seek_to(8 + text.len() + 4 * 13).expect("Failed to seek");
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 13000);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_string().expect("Failed to read string"), text);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 0);
+
+    let stream = create_reader_stream("encrypted");
+    let mut wrong_key = EncryptedStream::new(stream, &[8u8; 32], &nonce).expect("Failed to wrap");
+    let mut reader = BinaryReader::new(&mut wrong_key);
+    assert!(reader.read_string().is_err());
+
+    cleanup("encrypted");
+}
+
+#[cfg(feature = "log")]
+#[test]
+fn tracing_stream_logs_calls() {
+    use binary_rw::tracingstream::TracingStream;
+    use std::sync::Mutex;
+
+    static RECORDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct TestLogger;
+
+    impl log::Log for TestLogger {
+        fn enabled(&self, metadata: &log::Metadata) -> bool {
+            metadata.level() <= log::Level::Debug
+        }
+
+        fn log(&self, record: &log::Record) {
+            if record.target().ends_with("tracingstream") {
+                RECORDS.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    log::set_logger(&TestLogger).expect("Failed to set logger");
+    log::set_max_level(log::LevelFilter::Debug);
+
+    let mut stream = TracingStream::new(Memorystream::new().expect("Error"));
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u32(7).expect("Failed to write u32");
+    writer.seek_to(0).expect("Failed to seek");
+    let mut reader = BinaryReader::new(&mut stream);
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 7);
+
+    assert_eq!(
+        *RECORDS.lock().unwrap(),
+        vec![
+            "write(4) -> 4, position 4",
+            "seek(0) -> 0, position 0",
+            "read(4) -> 4, position 4",
+        ]
+    );
+}
+
+#[test]
+fn memory_stream_truncate_and_clear() {
+    let mut stream = Memorystream::with_capacity(64);
+    let mut writer = BinaryWriter::new(&mut stream);
+    writer.write_u64(1).expect("Failed to write u64");
+    writer.write_u32(2).expect("Failed to write u32");
+
+    stream.truncate(10);
+    assert_eq!(10, stream.len());
+    assert_eq!(10, stream.position());
+    stream.set_position(4);
+    stream.truncate(20);
+    assert_eq!(10, stream.len());
+    assert_eq!(4, stream.position());
+
+    let capacity = stream.capacity();
+    stream.clear();
+    assert_eq!(0, stream.len());
+    assert_eq!(0, stream.position());
+    assert_eq!(capacity, stream.capacity());
+
+    for round in 0..3u16 {
+        stream.clear();
+        let mut writer = BinaryWriter::new(&mut stream);
+        assert_eq!(0, writer.get_cur_pos().expect("Failed to get pos"));
+        writer.write_u16(round).expect("Failed to write u16");
+        assert_eq!(&round.to_le_bytes(), stream.as_slice());
+    }
+    assert_eq!(capacity, stream.capacity());
+}
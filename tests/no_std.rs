@@ -0,0 +1,39 @@
+// Only touches the parts of the crate that exist without `std`, so running
+// it with `--no-default-features` checks the `no_std` build end to end.
+extern crate binary_rw;
+
+use binary_rw::{
+    memorystream::Memorystream, BinaryError, BinaryReader, BinaryWriter, Endian, LengthPrefix,
+};
+
+#[test]
+fn memory_stream_round_trip() {
+    let mut stream = Memorystream::new().expect("Error");
+    let mut writer = BinaryWriter::with_endian(&mut stream, Endian::Big);
+    writer.set_length_prefix(LengthPrefix::Varint);
+    writer.write_u32(0xDEAD_BEEF).expect("Failed to write u32");
+    writer.write_i64(-42).expect("Failed to write i64");
+    writer.write_f64(1.5).expect("Failed to write f64");
+    writer.write_usize(7).expect("Failed to write usize");
+    writer.write_bool(true).expect("Failed to write bool");
+    writer.write_string("no_std").expect("Failed to write string");
+    writer.write_byte_vec(&[1, 2, 3]).expect("Failed to write bytes");
+
+    let mut reader = BinaryReader::with_endian(&mut stream, Endian::Big);
+    reader.set_length_prefix(LengthPrefix::Varint);
+    reader.seek_to(0).expect("Failed to seek");
+    assert_eq!(reader.read_u32().expect("Failed to read u32"), 0xDEAD_BEEF);
+    assert_eq!(reader.read_i64().expect("Failed to read i64"), -42);
+    assert_eq!(reader.read_f64().expect("Failed to read f64"), 1.5);
+    assert_eq!(reader.read_usize().expect("Failed to read usize"), 7);
+    assert!(reader.read_bool().expect("Failed to read bool"));
+    assert_eq!(reader.read_string().expect("Failed to read string"), "no_std");
+    assert_eq!(reader.read_byte_vec().expect("Failed to read bytes"), vec![1, 2, 3]);
+    assert_eq!(
+        reader.read_u8(),
+        Err(BinaryError::UnexpectedEof {
+            expected: 1,
+            got: 0,
+        })
+    );
+}